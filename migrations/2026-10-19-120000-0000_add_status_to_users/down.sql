@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS users_created_at_idx;
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_status_check;
+ALTER TABLE users DROP COLUMN status;
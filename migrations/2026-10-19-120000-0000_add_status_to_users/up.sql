@@ -0,0 +1,8 @@
+ALTER TABLE users
+  ADD COLUMN status VARCHAR NOT NULL DEFAULT 'active';
+
+ALTER TABLE users
+ADD CONSTRAINT users_status_check
+CHECK (status IN ('active', 'disabled'));
+
+CREATE INDEX users_created_at_idx ON users (created_at);
@@ -0,0 +1,10 @@
+DROP TRIGGER IF EXISTS set_updated_at ON persons;
+
+ALTER TABLE persons
+  DROP COLUMN updated_by,
+  DROP COLUMN created_by,
+  DROP COLUMN updated_at,
+  DROP COLUMN created_at,
+  DROP COLUMN phone,
+  DROP COLUMN email,
+  DROP COLUMN birth_date;
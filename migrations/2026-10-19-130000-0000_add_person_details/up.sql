@@ -0,0 +1,10 @@
+ALTER TABLE persons
+  ADD COLUMN birth_date DATE,
+  ADD COLUMN email VARCHAR,
+  ADD COLUMN phone VARCHAR,
+  ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  ADD COLUMN created_by UUID REFERENCES users (id) ON DELETE SET NULL,
+  ADD COLUMN updated_by UUID REFERENCES users (id) ON DELETE SET NULL;
+
+SELECT diesel_manage_updated_at('persons');
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS persons_created_at_idx;
+
+DROP FUNCTION IF EXISTS immutable_unaccent(text);
@@ -0,0 +1,16 @@
+CREATE EXTENSION IF NOT EXISTS unaccent;
+
+-- unaccent() is only STABLE (it depends on the search_path), so it cannot be
+-- used in index expressions. Pinning the dictionary makes it safe to mark
+-- as IMMUTABLE.
+CREATE OR REPLACE FUNCTION immutable_unaccent(text)
+RETURNS text
+LANGUAGE sql
+IMMUTABLE
+PARALLEL SAFE
+STRICT
+AS $$
+    SELECT public.unaccent('public.unaccent'::regdictionary, $1)
+$$;
+
+CREATE INDEX persons_created_at_idx ON persons (created_at);
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS persons_name_trgm_idx;
@@ -0,0 +1,7 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- pg_trgm already ignores case, so only accents need folding here. The same
+-- expression is used by the `?name=` filter and by `/person/search`.
+CREATE INDEX persons_name_trgm_idx
+  ON persons
+  USING gin (immutable_unaccent(name) gin_trgm_ops);
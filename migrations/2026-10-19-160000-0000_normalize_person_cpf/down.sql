@@ -0,0 +1,7 @@
+ALTER TABLE persons DROP CONSTRAINT IF EXISTS persons_cpf_digits_check;
+
+UPDATE persons
+SET cpf = substr(cpf, 1, 3) || '.' ||
+          substr(cpf, 4, 3) || '.' ||
+          substr(cpf, 7, 3) || '-' ||
+          substr(cpf, 10, 2);
@@ -0,0 +1,30 @@
+-- Rows whose CPFs only differ by formatting would collide once normalized.
+-- Which one to keep is not for a migration to decide, so stop and list them.
+DO $$
+DECLARE
+  duplicates TEXT;
+BEGIN
+  SELECT string_agg(digits || ' (' || ids || ')', '; ')
+  INTO duplicates
+  FROM (
+    SELECT regexp_replace(cpf, '[^0-9]', '', 'g') AS digits,
+           string_agg(id::TEXT, ', ' ORDER BY created_at, id) AS ids
+    FROM persons
+    GROUP BY 1
+    HAVING count(*) > 1
+  ) AS collisions;
+
+  IF duplicates IS NOT NULL THEN
+    RAISE EXCEPTION 'persons share a CPF once formatting is removed: %', duplicates
+      USING HINT = 'Merge or delete the duplicate persons, then run the migration again.';
+  END IF;
+END
+$$;
+
+UPDATE persons
+SET cpf = regexp_replace(cpf, '[^0-9]', '', 'g')
+WHERE cpf !~ '^[0-9]{11}$';
+
+ALTER TABLE persons
+ADD CONSTRAINT persons_cpf_digits_check
+CHECK (cpf ~ '^[0-9]{11}$');
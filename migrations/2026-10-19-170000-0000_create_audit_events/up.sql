@@ -0,0 +1,16 @@
+-- `actor_id` has no foreign key so the trail survives the actor's deletion.
+CREATE TABLE audit_events (
+  id UUID PRIMARY KEY,
+  actor_id UUID,
+  action VARCHAR NOT NULL CHECK (action IN ('create', 'update', 'delete')),
+  entity_type VARCHAR NOT NULL CHECK (entity_type IN ('person', 'user')),
+  entity_id UUID NOT NULL,
+  changes JSONB NOT NULL DEFAULT '{}',
+  request_id VARCHAR,
+  ip VARCHAR,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX audit_events_entity_idx ON audit_events (entity_type, entity_id, created_at DESC);
+CREATE INDEX audit_events_actor_idx ON audit_events (actor_id, created_at DESC);
+CREATE INDEX audit_events_created_at_idx ON audit_events (created_at DESC);
@@ -0,0 +1,3 @@
+DROP TRIGGER IF EXISTS persons_history_trigger ON persons;
+DROP FUNCTION IF EXISTS persons_record_history();
+DROP TABLE persons_history;
@@ -0,0 +1,71 @@
+-- One row per version of a person. `valid_from`/`valid_to` bound the period
+-- in which the version was current (`valid_to` is NULL for the live one and
+-- set on the last version when the person is deleted). No foreign key, so
+-- history outlives the person.
+CREATE TABLE persons_history (
+  id BIGSERIAL PRIMARY KEY,
+  person_id UUID NOT NULL,
+  version INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  cpf TEXT NOT NULL,
+  birth_date DATE,
+  email VARCHAR,
+  phone VARCHAR,
+  created_at TIMESTAMP NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  created_by UUID,
+  updated_by UUID,
+  valid_from TIMESTAMP NOT NULL,
+  valid_to TIMESTAMP,
+  UNIQUE (person_id, version)
+);
+
+CREATE INDEX persons_history_period_idx ON persons_history (person_id, valid_from);
+
+CREATE FUNCTION persons_record_history() RETURNS trigger AS $$
+DECLARE
+  next_version INTEGER;
+BEGIN
+  IF TG_OP = 'UPDATE'
+     AND (NEW.name, NEW.cpf, NEW.birth_date, NEW.email, NEW.phone)
+         IS NOT DISTINCT FROM (OLD.name, OLD.cpf, OLD.birth_date, OLD.email, OLD.phone) THEN
+    RETURN NULL;
+  END IF;
+
+  IF TG_OP IN ('UPDATE', 'DELETE') THEN
+    UPDATE persons_history
+    SET valid_to = NOW()
+    WHERE person_id = OLD.id AND valid_to IS NULL;
+  END IF;
+
+  IF TG_OP IN ('INSERT', 'UPDATE') THEN
+    SELECT COALESCE(MAX(version), 0) + 1 INTO next_version
+    FROM persons_history
+    WHERE person_id = NEW.id;
+
+    INSERT INTO persons_history (
+      person_id, version, name, cpf, birth_date, email, phone,
+      created_at, updated_at, created_by, updated_by, valid_from
+    ) VALUES (
+      NEW.id, next_version, NEW.name, NEW.cpf, NEW.birth_date, NEW.email, NEW.phone,
+      NEW.created_at, NEW.updated_at, NEW.created_by, NEW.updated_by, NOW()
+    );
+  END IF;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER persons_history_trigger
+AFTER INSERT OR UPDATE OR DELETE ON persons
+FOR EACH ROW EXECUTE FUNCTION persons_record_history();
+
+-- Existing rows start their history at their last update; earlier values
+-- were never kept.
+INSERT INTO persons_history (
+  person_id, version, name, cpf, birth_date, email, phone,
+  created_at, updated_at, created_by, updated_by, valid_from
+)
+SELECT id, 1, name, cpf, birth_date, email, phone,
+       created_at, updated_at, created_by, updated_by, updated_at
+FROM persons;
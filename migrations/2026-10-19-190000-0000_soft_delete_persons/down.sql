@@ -0,0 +1,49 @@
+-- soft-deleted rows were already gone from the API's point of view
+DELETE FROM persons WHERE deleted_at IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION persons_record_history() RETURNS trigger AS $$
+DECLARE
+  next_version INTEGER;
+BEGIN
+  IF TG_OP = 'UPDATE'
+     AND (NEW.name, NEW.cpf, NEW.birth_date, NEW.email, NEW.phone)
+         IS NOT DISTINCT FROM (OLD.name, OLD.cpf, OLD.birth_date, OLD.email, OLD.phone) THEN
+    RETURN NULL;
+  END IF;
+
+  IF TG_OP IN ('UPDATE', 'DELETE') THEN
+    UPDATE persons_history
+    SET valid_to = NOW()
+    WHERE person_id = OLD.id AND valid_to IS NULL;
+  END IF;
+
+  IF TG_OP IN ('INSERT', 'UPDATE') THEN
+    SELECT COALESCE(MAX(version), 0) + 1 INTO next_version
+    FROM persons_history
+    WHERE person_id = NEW.id;
+
+    INSERT INTO persons_history (
+      person_id, version, name, cpf, birth_date, email, phone,
+      created_at, updated_at, created_by, updated_by, valid_from
+    ) VALUES (
+      NEW.id, next_version, NEW.name, NEW.cpf, NEW.birth_date, NEW.email, NEW.phone,
+      NEW.created_at, NEW.updated_at, NEW.created_by, NEW.updated_by, NOW()
+    );
+  END IF;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DELETE FROM audit_events WHERE action IN ('restore', 'purge');
+ALTER TABLE audit_events DROP CONSTRAINT audit_events_action_check;
+ALTER TABLE audit_events ADD CONSTRAINT audit_events_action_check
+  CHECK (action IN ('create', 'update', 'delete'));
+
+DROP INDEX persons_deleted_at_idx;
+DROP INDEX persons_cpf_unique;
+ALTER TABLE persons ADD CONSTRAINT persons_cpf_unique UNIQUE (cpf);
+
+ALTER TABLE persons
+  DROP COLUMN deleted_by,
+  DROP COLUMN deleted_at;
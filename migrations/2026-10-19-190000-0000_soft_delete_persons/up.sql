@@ -0,0 +1,51 @@
+ALTER TABLE persons
+  ADD COLUMN deleted_at TIMESTAMP,
+  ADD COLUMN deleted_by UUID REFERENCES users (id) ON DELETE SET NULL;
+
+-- Deleted rows keep their CPF, so uniqueness only applies to live ones. The
+-- index keeps the constraint's name, which the API maps to a 409.
+ALTER TABLE persons DROP CONSTRAINT persons_cpf_unique;
+CREATE UNIQUE INDEX persons_cpf_unique ON persons (cpf) WHERE deleted_at IS NULL;
+
+CREATE INDEX persons_deleted_at_idx ON persons (deleted_at) WHERE deleted_at IS NOT NULL;
+
+ALTER TABLE audit_events DROP CONSTRAINT audit_events_action_check;
+ALTER TABLE audit_events ADD CONSTRAINT audit_events_action_check
+  CHECK (action IN ('create', 'update', 'delete', 'restore', 'purge'));
+
+-- A soft delete closes the current version like a hard delete did; a
+-- restore opens a new one.
+CREATE OR REPLACE FUNCTION persons_record_history() RETURNS trigger AS $$
+DECLARE
+  next_version INTEGER;
+BEGIN
+  IF TG_OP = 'UPDATE'
+     AND (NEW.name, NEW.cpf, NEW.birth_date, NEW.email, NEW.phone, NEW.deleted_at IS NULL)
+         IS NOT DISTINCT FROM
+         (OLD.name, OLD.cpf, OLD.birth_date, OLD.email, OLD.phone, OLD.deleted_at IS NULL) THEN
+    RETURN NULL;
+  END IF;
+
+  IF TG_OP IN ('UPDATE', 'DELETE') THEN
+    UPDATE persons_history
+    SET valid_to = NOW()
+    WHERE person_id = OLD.id AND valid_to IS NULL;
+  END IF;
+
+  IF TG_OP = 'INSERT' OR (TG_OP = 'UPDATE' AND NEW.deleted_at IS NULL) THEN
+    SELECT COALESCE(MAX(version), 0) + 1 INTO next_version
+    FROM persons_history
+    WHERE person_id = NEW.id;
+
+    INSERT INTO persons_history (
+      person_id, version, name, cpf, birth_date, email, phone,
+      created_at, updated_at, created_by, updated_by, valid_from
+    ) VALUES (
+      NEW.id, next_version, NEW.name, NEW.cpf, NEW.birth_date, NEW.email, NEW.phone,
+      NEW.created_at, NEW.updated_at, NEW.created_by, NEW.updated_by, NOW()
+    );
+  END IF;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,3 @@
+-- Bumped by every write; served as the ETag and checked against If-Match.
+ALTER TABLE persons ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE users ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
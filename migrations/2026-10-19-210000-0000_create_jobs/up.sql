@@ -0,0 +1,22 @@
+-- Background job queue. Workers claim due jobs with `FOR UPDATE SKIP LOCKED`;
+-- a failed job is retried at `run_at` until `max_attempts`, then left `dead`.
+CREATE TABLE jobs (
+  id UUID PRIMARY KEY,
+  kind VARCHAR NOT NULL CHECK (kind IN ('person_purge')),
+  payload JSONB NOT NULL DEFAULT '{}',
+  status VARCHAR NOT NULL DEFAULT 'queued'
+    CHECK (status IN ('queued', 'running', 'succeeded', 'dead')),
+  attempts INTEGER NOT NULL DEFAULT 0,
+  max_attempts INTEGER NOT NULL CHECK (max_attempts > 0),
+  run_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  locked_at TIMESTAMP,
+  last_error TEXT,
+  result JSONB,
+  created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  finished_at TIMESTAMP
+);
+
+CREATE INDEX jobs_due_idx ON jobs (run_at) WHERE status = 'queued';
+CREATE INDEX jobs_running_idx ON jobs (locked_at) WHERE status = 'running';
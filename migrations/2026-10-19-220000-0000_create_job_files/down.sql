@@ -0,0 +1,5 @@
+DROP TABLE job_files;
+
+DELETE FROM jobs WHERE kind IN ('person_import', 'person_export');
+ALTER TABLE jobs DROP CONSTRAINT jobs_kind_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_kind_check CHECK (kind IN ('person_purge'));
@@ -0,0 +1,15 @@
+ALTER TABLE jobs DROP CONSTRAINT jobs_kind_check;
+ALTER TABLE jobs ADD CONSTRAINT jobs_kind_check
+  CHECK (kind IN ('person_purge', 'person_import', 'person_export'));
+
+-- Files jobs read and produce: the upload of an import, kept until the job
+-- is done with it, and the file of an export, kept with the job.
+CREATE TABLE job_files (
+  job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+  purpose VARCHAR NOT NULL CHECK (purpose IN ('input', 'output')),
+  file_name VARCHAR NOT NULL,
+  content_type VARCHAR NOT NULL,
+  data BYTEA NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (job_id, purpose)
+);
@@ -0,0 +1,14 @@
+ALTER TABLE job_files ADD COLUMN data BYTEA NOT NULL DEFAULT '';
+
+UPDATE job_files f
+SET data = chunks.data
+FROM (
+  SELECT job_id, purpose, string_agg(data, ''::bytea ORDER BY seq) AS data
+  FROM job_file_chunks
+  GROUP BY job_id, purpose
+) chunks
+WHERE chunks.job_id = f.job_id AND chunks.purpose = f.purpose;
+
+ALTER TABLE job_files ALTER COLUMN data DROP DEFAULT;
+
+DROP TABLE job_file_chunks;
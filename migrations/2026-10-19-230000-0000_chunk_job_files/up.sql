@@ -0,0 +1,15 @@
+-- Job files are kept in chunks so that neither writing an export nor
+-- serving its download needs the whole file in memory.
+CREATE TABLE job_file_chunks (
+  job_id UUID NOT NULL,
+  purpose VARCHAR NOT NULL,
+  seq INTEGER NOT NULL,
+  data BYTEA NOT NULL,
+  PRIMARY KEY (job_id, purpose, seq),
+  FOREIGN KEY (job_id, purpose) REFERENCES job_files (job_id, purpose) ON DELETE CASCADE
+);
+
+INSERT INTO job_file_chunks (job_id, purpose, seq, data)
+SELECT job_id, purpose, 0, data FROM job_files;
+
+ALTER TABLE job_files DROP COLUMN data;
@@ -1,10 +1,13 @@
-use actix_web::{Error, FromRequest, HttpRequest, HttpResponse, dev::Payload};
+use actix_web::{FromRequest, HttpRequest, dev::Payload};
 use futures_util::future::{Ready, ready};
 
-use crate::{AppState, auth::claims::Claims, auth::jwt::validate_token};
+use crate::{
+    AppState, auth::claims::Claims, auth::jwt::validate_token, error::app_error::AppError,
+    i18n::Message,
+};
 
 impl FromRequest for Claims {
-    type Error = Error;
+    type Error = AppError;
     type Future = Ready<Result<Self, Self::Error>>;
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
@@ -16,8 +19,8 @@ impl FromRequest for Claims {
         let token = match auth_header.and_then(|h| h.strip_prefix("Bearer ")) {
             Some(t) => t,
             None => {
-                return ready(Err(actix_web::error::ErrorUnauthorized(
-                    "Missing or invalid authorization header",
+                return ready(Err(AppError::Unauthorized(
+                    Message::InvalidAuthorizationHeader,
                 )));
             }
         };
@@ -25,35 +28,29 @@ impl FromRequest for Claims {
         let secret = match req.app_data::<actix_web::web::Data<AppState>>() {
             Some(state) => state.secret(),
             None => {
-                return ready(Err(actix_web::error::ErrorInternalServerError(
-                    "App state not found",
-                )));
+                return ready(Err(AppError::internal("App state not found")));
             }
         };
 
-        match validate_token(token, &secret) {
+        match validate_token(token, secret) {
             Ok(claims) => ready(Ok(claims)),
-            Err(_) => ready(Err(actix_web::error::ErrorUnauthorized("Invalid token"))),
+            Err(_) => ready(Err(AppError::Unauthorized(Message::InvalidToken))),
         }
     }
 }
 
-pub fn require_admin(claims: &Claims) -> Result<(), HttpResponse> {
+pub fn require_admin(claims: &Claims) -> Result<(), AppError> {
     if claims.is_admin() {
         Ok(())
     } else {
-        Err(HttpResponse::Forbidden().json(serde_json::json!({
-            "error": "Admin access required"
-        })))
+        Err(AppError::Forbidden(Message::AdminRequired))
     }
 }
 
-pub fn require_self_or_admin(claims: &Claims, user_id: &uuid::Uuid) -> Result<(), HttpResponse> {
+pub fn require_self_or_admin(claims: &Claims, user_id: &uuid::Uuid) -> Result<(), AppError> {
     if claims.is_admin() || claims.user_id() == user_id {
         Ok(())
     } else {
-        Err(HttpResponse::Forbidden().json(serde_json::json!({
-            "error": "You can only access your own resources"
-        })))
+        Err(AppError::Forbidden(Message::OwnResourcesOnly))
     }
 }
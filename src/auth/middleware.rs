@@ -1,12 +1,20 @@
-use crate::{AppState, auth::jwt::validate_token};
+use std::rc::Rc;
+
+use crate::{
+    AppState, auth::jwt::validate_token, error::app_error::AppError, i18n::Message,
+    util::blocking::block,
+};
 use actix_web::{
-    Error, HttpMessage, HttpResponse,
+    Error, HttpMessage,
     body::BoxBody,
     dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
     web,
 };
 use futures_util::future::{LocalBoxFuture, Ready, ok};
 
+/// Requires a valid bearer token whose user is still active, and puts its
+/// `Claims` in the request. The account is looked up on every request, so
+/// disabling it cuts off tokens issued before.
 pub struct AuthMiddleware;
 
 impl<S> Transform<S, ServiceRequest> for AuthMiddleware
@@ -20,12 +28,14 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ok(AuthMiddlewareService { service })
+        ok(AuthMiddlewareService {
+            service: Rc::new(service),
+        })
     }
 }
 
 pub struct AuthMiddlewareService<S> {
-    service: S,
+    service: Rc<S>,
 }
 
 impl<S> Service<ServiceRequest> for AuthMiddlewareService<S>
@@ -39,33 +49,41 @@ where
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let secret = req
-            .app_data::<web::Data<AppState>>()
-            .map(|state| state.secret());
+        let state = req.app_data::<web::Data<AppState>>().cloned();
 
         let auth_header = req
             .headers()
             .get("Authorization")
             .and_then(|h| h.to_str().ok());
 
-        let claims = if let Some(secret) = secret {
+        let claims = state.as_ref().and_then(|state| {
             auth_header
                 .and_then(|h| h.strip_prefix("Bearer "))
-                .and_then(|token| validate_token(token, &secret).ok())
-        } else {
-            None
+                .and_then(|token| validate_token(token, state.secret()).ok())
+        });
+
+        let (Some(state), Some(claims)) = (state, claims) else {
+            let res = req.error_response(AppError::Unauthorized(Message::AuthenticationRequired));
+            return Box::pin(async move { Ok(res) });
         };
 
-        if claims.is_none() {
-            let res = HttpResponse::Unauthorized().json(serde_json::json!({
-                "error": "Authentication required"
-            }));
-            return Box::pin(async move { Ok(req.into_response(res)) });
-        }
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            let user_id = *claims.user_id();
+            let pool = state.pool();
+            let users = state.user_service();
+            let active = block(move || users.require_active(&pool, user_id))
+                .await
+                .map_err(AppError::from)
+                .and_then(|active| active);
+            if let Err(err) = active {
+                return Ok(req.error_response(err));
+            }
 
-        req.extensions_mut().insert(claims.unwrap());
+            tracing::Span::current().record("user_id", tracing::field::display(&user_id));
+            req.extensions_mut().insert(claims);
 
-        let fut = self.service.call(req);
-        Box::pin(async move { fut.await })
+            service.call(req).await
+        })
     }
 }
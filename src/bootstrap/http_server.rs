@@ -1,10 +1,28 @@
-use actix_web::middleware::Logger;
-use actix_web::{App, HttpServer, web};
+use actix_web::{App, HttpServer, Scope, web};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
-    AppState, auth::middleware::AuthMiddleware, config::AppConfig, controller::auth_controller,
+    AppState,
+    auth::middleware::AuthMiddleware,
+    bootstrap::{
+        jobs::spawn_job_workers,
+        openapi::{ApiDoc, OPENAPI_PATH},
+    },
+    config::AppConfig,
+    controller::audit_controller,
+    controller::auth_controller,
+    controller::health_controller,
+    controller::job_controller,
+    controller::metrics_controller,
     controller::person_controller::routes as person_routes,
     controller::user_controller::routes as user_routes,
+    error::{
+        extractor_config::{json_config, path_config, query_config},
+        middleware::ProblemMiddleware,
+    },
+    metrics::middleware::MetricsMiddleware,
+    telemetry::middleware::TracingMiddleware,
 };
 
 pub async fn start_http_server(
@@ -13,21 +31,131 @@ pub async fn start_http_server(
 ) -> std::io::Result<()> {
     let host = config.host().to_string();
     let port = config.port();
-    let cors_config = config.clone();
+    let app_config = web::Data::new(config.clone());
+    let openapi = ApiDoc::openapi();
+
+    spawn_job_workers(config.job_workers(), app_state.clone());
 
     HttpServer::new(move || {
         App::new()
-            .wrap(cors_config.cors())
-            .wrap(Logger::default())
+            .wrap(ProblemMiddleware)
+            .wrap(MetricsMiddleware)
+            .wrap(app_config.cors())
+            .wrap(TracingMiddleware)
             .app_data(app_state.clone())
-            .service(
-                web::scope("/api")
-                    .service(auth_controller::routes())
-                    .service(person_routes().wrap(AuthMiddleware))
-                    .service(user_routes().wrap(AuthMiddleware)),
-            )
+            .app_data(app_config.clone())
+            .app_data(json_config())
+            .app_data(path_config())
+            .app_data(query_config())
+            // registered before the `/api` scope, which would otherwise swallow these paths
+            .service(health_controller::routes())
+            .service(metrics_controller::metrics)
+            .service(web::redirect("/api/docs", "/api/docs/"))
+            .service(SwaggerUi::new("/api/docs/{_:.*}").url(OPENAPI_PATH, openapi.clone()))
+            .service(api_routes())
     })
     .bind((host, port))?
     .run()
     .await
 }
+
+fn api_routes() -> Scope {
+    web::scope("/api")
+        .service(auth_controller::routes())
+        .service(person_routes().wrap(AuthMiddleware))
+        .service(user_routes().wrap(AuthMiddleware))
+        .service(audit_controller::routes().wrap(AuthMiddleware))
+        .service(job_controller::routes().wrap(AuthMiddleware))
+}
+
+/// Building blocks for tests that go through the HTTP API. They need the
+/// migrated database at `DATABASE_URL`; nothing they write is kept.
+#[cfg(test)]
+pub(crate) mod testing {
+    use actix_web::web::{self, ServiceConfig};
+    use uuid::Uuid;
+
+    use super::api_routes;
+    use crate::{
+        AppState, PersonService,
+        auth::jwt::generate_token,
+        config::AppConfig,
+        model::{cpf::Cpf, person::NewPerson, person::Person, role::Role},
+        service::{auth_service::AuthService, db::test_pool, user_service::UserService},
+        util::audit_context::AuditContext,
+    };
+
+    pub(crate) fn state(config: &AppConfig) -> web::Data<AppState> {
+        web::Data::new(AppState::new(
+            test_pool(),
+            PersonService::new(),
+            UserService::new(),
+            config.secret().to_string(),
+        ))
+    }
+
+    /// The `/api` routes, as `App::new().configure(..)` takes them.
+    pub(crate) fn api(
+        config: AppConfig,
+        state: web::Data<AppState>,
+    ) -> impl FnOnce(&mut ServiceConfig) {
+        move |cfg| {
+            cfg.app_data(state)
+                .app_data(web::Data::new(config))
+                .service(api_routes());
+        }
+    }
+
+    /// Registers a user with the password `password123`, returning its id
+    /// and a bearer token.
+    pub(crate) fn user(state: &AppState, role: Role) -> (Uuid, String) {
+        let id = AuthService::register(
+            &state.pool(),
+            &state.user_service(),
+            format!("{}@test.io", Uuid::new_v4()),
+            role,
+            "password123".into(),
+            &AuditContext::default(),
+        )
+        .unwrap();
+        let token = generate_token(id, role.to_string(), state.secret(), 1).unwrap();
+        (id, token)
+    }
+
+    /// Creates a person with a CPF no one else has.
+    pub(crate) fn person(state: &AppState) -> Person {
+        let new_person = NewPerson::new(
+            "Pessoa de Teste".into(),
+            fresh_cpf(),
+            None,
+            None,
+            None,
+            None,
+        );
+        state
+            .person_service()
+            .create_person(&state.pool(), new_person, &AuditContext::default())
+            .unwrap()
+    }
+
+    /// A valid CPF made of random digits.
+    pub(crate) fn fresh_cpf() -> Cpf {
+        let mut digits: Vec<u32> = Uuid::new_v4()
+            .as_bytes()
+            .iter()
+            .take(9)
+            .map(|b| u32::from(*b) % 10)
+            .collect();
+        for len in [9, 10] {
+            let sum: u32 = (0..len)
+                .map(|i| digits[i] * (len as u32 + 1 - i as u32))
+                .sum();
+            digits.push(sum * 10 % 11 % 10);
+        }
+        let raw: String = digits
+            .iter()
+            .map(|d| char::from_digit(*d, 10).unwrap())
+            .collect();
+        Cpf::parse(&raw).unwrap()
+    }
+}
@@ -0,0 +1,202 @@
+use std::convert::Infallible;
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use actix_web::{rt, web};
+use futures_util::future::{Either, pending, select};
+use futures_util::pin_mut;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    dto::person_dto::{ImportReport, ImportRowErrors, PersonExportRow, PersonFilterQuery},
+    i18n::Locale,
+    model::{
+        import_mode::ImportMode, job::Job, job_file_purpose::JobFilePurpose, job_kind::JobKind,
+    },
+    service::{
+        db::DbPool,
+        job_service::{
+            JobFailure, JobService, LEASE_RENEWAL_INTERVAL, PersonExportPayload,
+            PersonImportPayload, PersonPurgePayload,
+        },
+        person_service::ImportOutcome,
+    },
+    util::{blocking::block, export_writer::ExportWriter},
+};
+
+/// How long an idle worker waits before looking for due jobs again.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Starts `workers` tasks that run queued jobs one at a time each. Several
+/// processes may run workers against the same queue.
+pub fn spawn_job_workers(workers: usize, app_state: web::Data<AppState>) {
+    for worker in 0..workers {
+        rt::spawn(run_worker(worker, app_state.clone()));
+    }
+}
+
+async fn run_worker(worker: usize, app_state: web::Data<AppState>) {
+    loop {
+        let pool = app_state.pool();
+        match block(move || JobService::claim_next(&pool)).await {
+            Ok(Ok(Some(job))) => {
+                let span = tracing::info_span!(
+                    "job",
+                    worker,
+                    job_id = %job.id(),
+                    kind = %job.kind(),
+                    attempt = job.attempts(),
+                );
+                execute(job, app_state.clone()).instrument(span).await;
+            }
+            Ok(Ok(None)) => rt::time::sleep(IDLE_POLL_INTERVAL).await,
+            Ok(Err(e)) => {
+                tracing::error!(worker, error = ?e, "claiming a job failed");
+                rt::time::sleep(IDLE_POLL_INTERVAL).await;
+            }
+            Err(e) => {
+                tracing::error!(worker, error = ?e, "claiming a job failed");
+                rt::time::sleep(IDLE_POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+async fn execute(job: Job, app_state: web::Data<AppState>) {
+    let id = *job.id();
+    let kind = job.kind();
+    let payload = job.payload().clone();
+    let state = app_state.clone();
+
+    let attempt = job.attempts();
+    let run = block(move || run(id, attempt, kind, payload, &state));
+    let renewals = keep_lease(id, attempt, app_state.pool());
+    pin_mut!(run, renewals);
+    let result = match select(run, renewals).await {
+        Either::Left((result, _)) => result,
+        Either::Right((never, _)) => match never {},
+    };
+
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        // the job panicked; retried like any other unexpected failure
+        Err(e) => Err(JobFailure::Transient(e.to_string())),
+    };
+    if let Err(failure) = &outcome {
+        tracing::warn!(error = failure.error(), ?failure, "job attempt failed");
+    }
+
+    let pool = app_state.pool();
+    match block(move || JobService::finish(&pool, &job, outcome)).await {
+        Ok(Ok(Some(job))) => tracing::info!(status = %job.status(), "job attempt finished"),
+        Ok(Ok(None)) => tracing::warn!("job lease expired before the attempt finished"),
+        Ok(Err(e)) => tracing::error!(error = ?e, "recording the job outcome failed"),
+        Err(e) => tracing::error!(error = ?e, "recording the job outcome failed"),
+    }
+}
+
+/// Renews the lease of `attempt` of job `id` until dropped, so a long
+/// attempt is not handed to another worker while still in progress.
+async fn keep_lease(id: Uuid, attempt: i32, pool: DbPool) -> Infallible {
+    loop {
+        rt::time::sleep(LEASE_RENEWAL_INTERVAL).await;
+        let pool = pool.clone();
+        match block(move || JobService::renew_lease(&pool, id, attempt)).await {
+            Ok(Ok(true)) => {}
+            Ok(Ok(false)) => {
+                tracing::warn!("job lease was lost while the attempt was running");
+                return pending().await;
+            }
+            Ok(Err(e)) => tracing::error!(error = ?e, "renewing the job lease failed"),
+            Err(e) => tracing::error!(error = ?e, "renewing the job lease failed"),
+        }
+    }
+}
+
+/// Runs attempt `attempt` of a job, returning the result stored for polling.
+fn run(
+    id: Uuid,
+    attempt: i32,
+    kind: JobKind,
+    payload: Value,
+    state: &AppState,
+) -> Result<Value, JobFailure> {
+    match kind {
+        JobKind::PersonPurge => {
+            let payload: PersonPurgePayload = parse_payload(payload)?;
+            let purged = state
+                .person_service()
+                .purge_deleted(&state.pool(), payload.retention_days)?;
+            Ok(json!({ "purged": purged }))
+        }
+        JobKind::PersonImport => {
+            let payload: PersonImportPayload = parse_payload(payload)?;
+            let pool = state.pool();
+            let data = JobService::read_file(&pool, id, JobFilePurpose::Input)?;
+            let outcome = state.person_service().import_people(
+                &pool,
+                data.as_slice(),
+                payload.mode,
+                &payload.audit,
+            )?;
+            let locale = payload.locale.parse().unwrap_or_default();
+            Ok(json!(import_report(payload.mode, &outcome, locale)))
+        }
+        JobKind::PersonExport => {
+            let payload: PersonExportPayload = parse_payload(payload)?;
+            let filter = serde_urlencoded::from_str::<PersonFilterQuery>(&payload.filters)
+                .map_err(|e| JobFailure::Permanent(format!("invalid job payload: {e}")))?
+                .to_filter()?;
+            let pool = state.pool();
+
+            let mut writer = ExportWriter::new(payload.format, PersonExportRow::COLUMNS)?;
+            let mut output = JobService::create_output(
+                &pool,
+                id,
+                attempt,
+                format!("people.{}", payload.format.extension()),
+                payload.format.content_type().to_string(),
+            )?;
+            let mut rows = 0;
+            state.person_service().export(&pool, &filter, |people| {
+                for person in &people {
+                    writer.write(&PersonExportRow::new(person, payload.mask_cpf))?;
+                }
+                rows += people.len();
+                output.write(&writer.take_chunk()?)?;
+                Ok(ControlFlow::Continue(()))
+            })?;
+            output.write(&writer.finish()?)?;
+            output.finish()?;
+
+            Ok(json!({ "rows": rows }))
+        }
+    }
+}
+
+fn parse_payload<T: DeserializeOwned>(payload: Value) -> Result<T, JobFailure> {
+    serde_json::from_value(payload)
+        .map_err(|e| JobFailure::Permanent(format!("invalid job payload: {e}")))
+}
+
+fn import_report(mode: ImportMode, outcome: &ImportOutcome, locale: Locale) -> ImportReport {
+    ImportReport {
+        mode,
+        total: outcome.total,
+        valid: outcome.valid,
+        imported: outcome.imported,
+        failed: outcome.failures.len(),
+        errors: outcome
+            .failures
+            .iter()
+            .map(|failure| ImportRowErrors {
+                line: failure.line,
+                errors: failure.errors.iter().map(|e| e.localize(locale)).collect(),
+            })
+            .collect(),
+    }
+}
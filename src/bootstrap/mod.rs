@@ -1,3 +1,8 @@
 pub mod http_server;
+pub mod jobs;
+pub mod openapi;
+pub mod retention;
 
 pub use http_server::start_http_server;
+pub use jobs::spawn_job_workers;
+pub use retention::spawn_person_retention;
@@ -0,0 +1,127 @@
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+
+use crate::controller::{
+    audit_controller::AuditApi, auth_controller::AuthApi, health_controller::HealthApi,
+    job_controller::JobApi, person_controller::PersonApi, user_controller::UserApi,
+};
+use crate::error::problem::{FieldErrorBody, ProblemDetails};
+
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+/// OpenAPI document assembled from the controllers' `#[utoipa::path]`
+/// annotations. Every operation requires a bearer token unless it says
+/// otherwise.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "rest-actix-rust",
+        description = "People and users REST API with HATEOAS links. Errors are RFC 9457 problem details."
+    ),
+    nest(
+        (path = "/api/auth", api = AuthApi, tags = ["auth"]),
+        (path = "/api/person", api = PersonApi, tags = ["person"]),
+        (path = "/api/user", api = UserApi, tags = ["user"]),
+        (path = "/api/audit", api = AuditApi, tags = ["audit"]),
+        (path = "/api/jobs", api = JobApi, tags = ["jobs"]),
+        (path = "/health", api = HealthApi, tags = ["health"])
+    ),
+    components(schemas(ProblemDetails, FieldErrorBody), responses(ProblemDetails)),
+    modifiers(&BearerAuth),
+    security(("bearer_auth" = []))
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App,
+        test::{TestRequest, call_and_read_body_json, call_service, init_service},
+    };
+    use serde_json::{Value, json};
+    use utoipa_swagger_ui::SwaggerUi;
+
+    use super::*;
+
+    fn document() -> Value {
+        serde_json::to_value(ApiDoc::openapi()).unwrap()
+    }
+
+    #[test]
+    fn the_document_is_openapi_3_1_with_every_scope() {
+        let doc = document();
+        assert_eq!(doc["openapi"], "3.1.0");
+        for path in [
+            "/api/auth/login",
+            "/api/person",
+            "/api/person/{id}",
+            "/api/user/{id}",
+            "/api/audit",
+            "/api/jobs/{id}",
+            "/health/ready",
+        ] {
+            assert!(doc["paths"][path].is_object(), "{path} is not documented");
+        }
+    }
+
+    #[test]
+    fn operations_need_a_bearer_token_unless_they_opt_out() {
+        let doc = document();
+        assert_eq!(
+            doc["components"]["securitySchemes"]["bearer_auth"],
+            json!({"type": "http", "scheme": "bearer", "bearerFormat": "JWT"})
+        );
+        assert_eq!(doc["security"], json!([{"bearer_auth": []}]));
+        assert_eq!(
+            doc["paths"]["/api/auth/login"]["post"]["security"],
+            json!([{}])
+        );
+    }
+
+    #[test]
+    fn errors_and_links_are_described() {
+        let doc = document();
+        let schemas = &doc["components"]["schemas"];
+        assert!(schemas["ProblemDetails"].is_object());
+        assert!(schemas["PersonResponse"]["properties"]["_links"].is_object());
+        assert_eq!(
+            doc["paths"]["/api/person/{id}"]["get"]["responses"]["404"]["$ref"],
+            "#/components/responses/ProblemDetails"
+        );
+    }
+
+    #[actix_web::test]
+    async fn the_document_is_served_next_to_the_docs() {
+        let app = init_service(
+            App::new()
+                .service(SwaggerUi::new("/api/docs/{_:.*}").url(OPENAPI_PATH, ApiDoc::openapi())),
+        )
+        .await;
+
+        let served: Value =
+            call_and_read_body_json(&app, TestRequest::get().uri(OPENAPI_PATH).to_request()).await;
+        assert_eq!(served, document());
+
+        let docs = call_service(&app, TestRequest::get().uri("/api/docs/").to_request()).await;
+        assert!(docs.status().is_success());
+    }
+}
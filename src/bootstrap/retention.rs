@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use actix_web::{rt, web};
+use serde_json::json;
+
+use crate::{
+    AppState,
+    model::job_kind::JobKind,
+    service::job_service::{JobService, PersonPurgePayload},
+    util::blocking::block,
+};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Enqueues a purge of people soft-deleted more than `retention_days` ago,
+/// once at startup and then every hour, unless one is still pending. The
+/// job workers run it and retry it on failure.
+pub fn spawn_person_retention(retention_days: i32, app_state: web::Data<AppState>) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let pool = app_state.pool().clone();
+            let payload = json!(PersonPurgePayload { retention_days });
+            match block(move || {
+                JobService::enqueue_unless_pending(&pool, JobKind::PersonPurge, payload)
+            })
+            .await
+            {
+                Ok(Ok(Some(job))) => tracing::debug!(job_id = %job.id(), "person purge enqueued"),
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => tracing::error!(error = %e, "enqueueing the person purge failed"),
+                Err(e) => tracing::error!(error = %e, "enqueueing the person purge failed"),
+            }
+        }
+    });
+}
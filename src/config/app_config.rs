@@ -1,7 +1,12 @@
 use std::env;
 
 use actix_cors::Cors;
-use actix_web::http::header;
+use actix_web::http::header::{self, HeaderName};
+
+use crate::{
+    i18n::Locale,
+    telemetry::{LogFormat, middleware::X_REQUEST_ID},
+};
 
 #[derive(Clone)]
 pub struct AppConfig {
@@ -10,6 +15,14 @@ pub struct AppConfig {
     database_url: String,
     secret: String,
     cors_allowed_origins: Vec<String>,
+    mask_cpf_for_non_admins: bool,
+    default_locale: Locale,
+    log_format: LogFormat,
+    otel_enabled: bool,
+    otlp_endpoint: String,
+    person_retention_days: i32,
+    require_if_match: bool,
+    job_workers: usize,
 }
 
 impl AppConfig {
@@ -32,12 +45,60 @@ impl AppConfig {
             .map(|s| s.to_string())
             .collect::<Vec<_>>();
 
+        let mask_cpf_for_non_admins = env::var("MASK_CPF_FOR_NON_ADMINS")
+            .unwrap_or_else(|_| "true".into())
+            .parse()
+            .expect("MASK_CPF_FOR_NON_ADMINS must be true or false");
+
+        let default_locale = env::var("DEFAULT_LOCALE")
+            .unwrap_or_else(|_| Locale::PtBr.tag().into())
+            .parse()
+            .expect("DEFAULT_LOCALE must be pt-BR or en");
+
+        let log_format = env::var("LOG_FORMAT")
+            .unwrap_or_else(|_| LogFormat::Text.to_string())
+            .parse()
+            .expect("LOG_FORMAT must be text or json");
+
+        let otel_enabled = env::var("OTEL_ENABLED")
+            .unwrap_or_else(|_| "false".into())
+            .parse()
+            .expect("OTEL_ENABLED must be true or false");
+
+        let otlp_endpoint = env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .unwrap_or_else(|_| "http://localhost:4318".into());
+
+        let person_retention_days = env::var("PERSON_RETENTION_DAYS")
+            .unwrap_or_else(|_| "30".into())
+            .parse()
+            .ok()
+            .filter(|days| *days >= 0)
+            .expect("PERSON_RETENTION_DAYS must be a non-negative number of days");
+
+        let require_if_match = env::var("REQUIRE_IF_MATCH")
+            .unwrap_or_else(|_| "false".into())
+            .parse()
+            .expect("REQUIRE_IF_MATCH must be true or false");
+
+        let job_workers = env::var("JOB_WORKERS")
+            .unwrap_or_else(|_| "2".into())
+            .parse()
+            .expect("JOB_WORKERS must be a non-negative number");
+
         Self {
             host,
             port,
             database_url,
             secret,
             cors_allowed_origins,
+            mask_cpf_for_non_admins,
+            default_locale,
+            log_format,
+            otel_enabled,
+            otlp_endpoint,
+            person_retention_days,
+            require_if_match,
+            job_workers,
         }
     }
 
@@ -48,8 +109,17 @@ impl AppConfig {
             .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin));
 
         cors.allowed_methods(["GET", "POST", "PUT", "PATCH", "DELETE"])
-            .allowed_headers([header::AUTHORIZATION, header::ACCEPT])
+            .allowed_headers([
+                header::AUTHORIZATION,
+                header::ACCEPT,
+                header::ACCEPT_LANGUAGE,
+                header::IF_MATCH,
+            ])
             .allowed_header(header::CONTENT_TYPE)
+            .allowed_header(X_REQUEST_ID)
+            .allowed_header(HeaderName::from_static("traceparent"))
+            .allowed_header(HeaderName::from_static("tracestate"))
+            .expose_headers([X_REQUEST_ID, header::ETAG])
             .supports_credentials()
             .max_age(3600)
     }
@@ -73,4 +143,75 @@ impl AppConfig {
     pub fn cors_allowed_origins(&self) -> &[String] {
         &self.cors_allowed_origins
     }
+
+    pub fn mask_cpf_for_non_admins(&self) -> bool {
+        self.mask_cpf_for_non_admins
+    }
+
+    /// Language for responses when `Accept-Language` names none we support.
+    pub fn default_locale(&self) -> Locale {
+        self.default_locale
+    }
+
+    pub fn log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    /// Whether spans are exported to an OpenTelemetry collector.
+    pub fn otel_enabled(&self) -> bool {
+        self.otel_enabled
+    }
+
+    /// Base URL of the collector's OTLP/HTTP receiver.
+    pub fn otlp_endpoint(&self) -> &str {
+        &self.otlp_endpoint
+    }
+
+    /// How long soft-deleted people are kept before being purged for good.
+    pub fn person_retention_days(&self) -> i32 {
+        self.person_retention_days
+    }
+
+    /// Whether updates and deletes without `If-Match` are rejected with 428.
+    pub fn require_if_match(&self) -> bool {
+        self.require_if_match
+    }
+
+    /// Background job workers run by this process; 0 leaves the queue to
+    /// other instances.
+    pub fn job_workers(&self) -> usize {
+        self.job_workers
+    }
+}
+
+#[cfg(test)]
+impl AppConfig {
+    /// The defaults of [`AppConfig::from_env`], without reading the environment.
+    pub(crate) fn for_tests() -> Self {
+        Self {
+            host: "127.0.0.1".into(),
+            port: 8080,
+            database_url: String::new(),
+            secret: "secret".into(),
+            cors_allowed_origins: Vec::new(),
+            mask_cpf_for_non_admins: true,
+            default_locale: Locale::PtBr,
+            log_format: LogFormat::Text,
+            otel_enabled: false,
+            otlp_endpoint: String::new(),
+            person_retention_days: 30,
+            require_if_match: false,
+            job_workers: 0,
+        }
+    }
+
+    pub(crate) fn with_require_if_match(mut self, require_if_match: bool) -> Self {
+        self.require_if_match = require_if_match;
+        self
+    }
+
+    pub(crate) fn with_mask_cpf_for_non_admins(mut self, mask: bool) -> Self {
+        self.mask_cpf_for_non_admins = mask;
+        self
+    }
 }
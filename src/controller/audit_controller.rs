@@ -0,0 +1,153 @@
+use actix_web::{HttpRequest, HttpResponse, Scope, get, web};
+use utoipa::OpenApi;
+
+use crate::{
+    auth::{claims::Claims, claims_extractor::require_admin},
+    dto::{
+        audit_dto::{AuditEventResponse, AuditFilterQuery},
+        hateoas::collection_page_links,
+        user_dto::{PageQuery, PaginatedResponse},
+    },
+    error::{app_error::AppError, problem::ProblemDetails},
+    model::audit_event::AuditEvent,
+    repository::audit_repository::AuditFilter,
+    service::audit_service::AuditService,
+    util::{app_state::AppState, blocking::block},
+};
+
+fn audit_filter(query: &AuditFilterQuery) -> AuditFilter {
+    AuditFilter {
+        actor_id: query.actor_id,
+        action: query.action,
+        entity_type: query.entity_type,
+        entity_id: query.entity_id,
+        created_after: query.created_after,
+        created_before: query.created_before,
+    }
+}
+
+fn audit_event_response(event: &AuditEvent) -> AuditEventResponse {
+    AuditEventResponse {
+        id: *event.id(),
+        actor_id: event.actor_id(),
+        action: event.action(),
+        entity_type: event.entity_type(),
+        entity_id: *event.entity_id(),
+        changes: event.changes().clone(),
+        request_id: event.request_id().map(str::to_string),
+        ip: event.ip().map(str::to_string),
+        created_at: *event.created_at(),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(find_audit_events))]
+pub struct AuditApi;
+
+pub fn routes() -> Scope {
+    web::scope("/audit").service(find_audit_events)
+}
+
+/// Lista eventos de auditoria, mais recentes primeiro - apenas admin
+#[utoipa::path(
+    params(PageQuery, AuditFilterQuery),
+    responses(
+        (status = 200, description = "Page of audit events", body = PaginatedResponse<AuditEventResponse>),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails)
+    )
+)]
+#[get("", name = "audit_find_all")]
+async fn find_audit_events(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    claims: Claims,
+    query: web::Query<PageQuery>,
+    filter_query: web::Query<AuditFilterQuery>,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
+
+    let filter = audit_filter(&filter_query);
+    let filters = serde_urlencoded::to_string(&*filter_query).unwrap_or_default();
+
+    let pool = state.pool().clone();
+
+    let page = query.page.max(1);
+    let size = query.size.clamp(1, 100);
+
+    let (total, events) =
+        block(move || AuditService::find_page(&pool, &filter, page, size)).await??;
+
+    Ok(HttpResponse::Ok().json(PaginatedResponse {
+        page,
+        size,
+        total,
+        items: events.iter().map(audit_event_response).collect(),
+        links: collection_page_links(&req, "audit_find_all", &filters, page, size, total),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App,
+        http::{StatusCode, header},
+        test::{self, TestRequest},
+    };
+    use serde_json::Value;
+
+    use crate::{bootstrap::http_server::testing, config::AppConfig, model::role::Role};
+
+    fn with_token(req: TestRequest, token: &str) -> TestRequest {
+        req.insert_header((header::AUTHORIZATION, format!("Bearer {token}")))
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn changes_are_listed_with_their_actor() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (admin_id, admin) = testing::user(&state, Role::Admin);
+        let person = testing::person(&state);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        let uri = format!("/api/person/{}", person.id());
+        let deleted = test::call_service(
+            &app,
+            with_token(TestRequest::delete().uri(&uri), &admin).to_request(),
+        )
+        .await;
+        assert!(deleted.status().is_success());
+
+        let uri = format!("/api/audit?entity_id={}&action=delete", person.id());
+        let res = test::call_service(
+            &app,
+            with_token(TestRequest::get().uri(&uri), &admin).to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["total"], 1);
+        let event = &body["items"][0];
+        assert_eq!(event["actor_id"], admin_id.to_string());
+        assert_eq!(event["entity_type"], "person");
+        assert_eq!(event["changes"]["name"]["before"], "Pessoa de Teste");
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn the_log_is_for_admins() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (_, user) = testing::user(&state, Role::User);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        let res = test::call_service(
+            &app,
+            with_token(TestRequest::get().uri("/api/audit"), &user).to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+}
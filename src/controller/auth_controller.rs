@@ -1,58 +1,89 @@
 use crate::{
-    dto::auth_dto::{LoginRequest, RegisterRequest},
+    dto::auth_dto::{LoginRequest, LoginResponse, RegisterRequest, RegisterResponse},
+    error::{app_error::AppError, problem::ProblemDetails},
+    metrics::METRICS,
     model::role::Role,
     service::auth_service::AuthService,
-    util::app_state::AppState,
+    util::{
+        app_state::AppState, audit_context::AuditContext, blocking::block,
+        validated_json::ValidatedJson,
+    },
 };
 use actix_web::{HttpResponse, Scope, post, web};
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(paths(login, register))]
+pub struct AuthApi;
 
 pub fn routes() -> Scope {
     web::scope("/auth").service(login).service(register)
 }
 
+/// Autentica e devolve um token de acesso
+#[utoipa::path(
+    request_body = LoginRequest,
+    security(()),
+    responses(
+        (status = 200, description = "Authenticated", body = LoginResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails)
+    )
+)]
 #[post("/login")]
-pub async fn login(state: web::Data<AppState>, body: web::Json<LoginRequest>) -> HttpResponse {
+pub async fn login(
+    state: web::Data<AppState>,
+    body: ValidatedJson<LoginRequest>,
+) -> Result<HttpResponse, AppError> {
     let pool = state.pool().clone();
 
-    let email = body.email.clone();
+    let email = body.email.trim().to_string();
     let password = body.password.clone();
 
-    let result = web::block(move || {
-        let mut conn = pool.get().map_err(|_| "Database connection error")?;
+    let result = block(move || {
         let secret = state.secret();
 
-        AuthService::login(&mut conn, email, password, &secret)
+        AuthService::login(&pool, &state.user_service(), email, password, secret)
     })
-    .await;
-
-    match result {
-        Ok(Ok(res)) => HttpResponse::Ok().json(serde_json::json!({ "id": res.0,"token": res.1 })),
-        Ok(Err(msg)) => HttpResponse::Unauthorized().json(serde_json::json!({ "error": msg })),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+    .await?;
+    // infrastructure failures say nothing about the credentials
+    match &result {
+        Ok(_) => METRICS.record_login(true),
+        Err(AppError::Unauthorized(_) | AppError::Forbidden(_)) => METRICS.record_login(false),
+        Err(_) => {}
     }
+    let (id, token) = result?;
+
+    Ok(HttpResponse::Ok().json(LoginResponse { id, token }))
 }
 
+/// Cadastra um novo usuário com papel `user`
+#[utoipa::path(
+    request_body = RegisterRequest,
+    security(()),
+    responses(
+        (status = 201, description = "User registered", body = RegisterResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 409, response = ProblemDetails)
+    )
+)]
 #[post("/register")]
 pub async fn register(
     state: web::Data<AppState>,
-    body: web::Json<RegisterRequest>,
-) -> HttpResponse {
+    body: ValidatedJson<RegisterRequest>,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
     let pool = state.pool().clone();
-    let email = body.email.clone();
+    let email = body.email.trim().to_string();
     let role = Role::User;
 
     let password = body.password.clone();
 
-    let result = web::block(move || {
-        let mut conn = pool.get().map_err(|_| "Database connection error")?;
-
-        AuthService::register(&mut conn, email, role, password)
+    let id = block(move || {
+        AuthService::register(&pool, &state.user_service(), email, role, password, &ctx)
     })
-    .await;
+    .await??;
 
-    match result {
-        Ok(Ok(id)) => HttpResponse::Created().json(serde_json::json!({ "id": id })),
-        Ok(Err(msg)) => HttpResponse::BadRequest().json(serde_json::json!({ "error": msg })),
-        Err(_) => HttpResponse::InternalServerError().finish(),
-    }
+    Ok(HttpResponse::Created().json(RegisterResponse { id }))
 }
@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use actix_web::{HttpResponse, Scope, get, rt::time::timeout, web};
+use utoipa::OpenApi;
+
+use crate::{
+    dto::health_dto::{BuildInfo, ComponentHealth, HealthResponse, HealthStatus},
+    error::app_error::AppError,
+    service::health_service::HealthService,
+    util::{app_state::AppState, blocking::block},
+};
+
+/// Upper bound for each readiness check, including waiting for a connection.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(OpenApi)]
+#[openapi(paths(live, ready))]
+pub struct HealthApi;
+
+pub fn routes() -> Scope {
+    web::scope("/health").service(live).service(ready)
+}
+
+/// Runs a blocking check on the thread pool, giving up after `CHECK_TIMEOUT`.
+async fn run_check<T, F>(check: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    let result = match timeout(CHECK_TIMEOUT, block(check)).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(err)) => Err(AppError::from(err)),
+        Err(_) => return Err("Timed out".into()),
+    };
+
+    result.map_err(|err| {
+        tracing::warn!(error = ?err, "health check failed");
+        err.to_string()
+    })
+}
+
+/// Processo no ar
+#[utoipa::path(
+    security(()),
+    responses((status = 200, description = "Process is up", body = HealthResponse))
+)]
+#[get("/live")]
+async fn live() -> HttpResponse {
+    HttpResponse::Ok().json(HealthResponse {
+        status: HealthStatus::Up,
+        build: BuildInfo::current(),
+        checks: BTreeMap::new(),
+    })
+}
+
+/// Pronto para receber tráfego: banco acessível e sem migrações pendentes
+#[utoipa::path(
+    security(()),
+    responses(
+        (status = 200, description = "All checks passed", body = HealthResponse),
+        (status = 503, description = "At least one check failed", body = HealthResponse)
+    )
+)]
+#[get("/ready")]
+async fn ready(state: web::Data<AppState>) -> HttpResponse {
+    let pool = state.pool();
+    let started = Instant::now();
+    let database = match run_check(move || HealthService::ping_database(&pool, CHECK_TIMEOUT)).await
+    {
+        Ok(()) => ComponentHealth {
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+            ..ComponentHealth::up()
+        },
+        Err(err) => ComponentHealth::down(err),
+    };
+
+    let pool = state.pool();
+    let migrations =
+        match run_check(move || HealthService::pending_migrations(&pool, CHECK_TIMEOUT)).await {
+            Ok(pending) if pending.is_empty() => ComponentHealth {
+                pending: Some(pending),
+                ..ComponentHealth::up()
+            },
+            Ok(pending) => ComponentHealth {
+                pending: Some(pending),
+                ..ComponentHealth::down("Pending migrations")
+            },
+            Err(err) => ComponentHealth::down(err),
+        };
+
+    let checks = BTreeMap::from([("database", database), ("migrations", migrations)]);
+    let status = if checks.values().all(|c| c.status == HealthStatus::Up) {
+        HealthStatus::Up
+    } else {
+        HealthStatus::Down
+    };
+
+    let body = HealthResponse {
+        status,
+        build: BuildInfo::current(),
+        checks,
+    };
+
+    match status {
+        HealthStatus::Up => HttpResponse::Ok().json(body),
+        HealthStatus::Down => HttpResponse::ServiceUnavailable().json(body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App,
+        http::StatusCode,
+        test::{self, TestRequest},
+    };
+    use diesel::{
+        PgConnection,
+        r2d2::{ConnectionManager, Pool},
+    };
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{
+        bootstrap::http_server::testing, config::AppConfig, service::person_service::PersonService,
+        service::user_service::UserService,
+    };
+
+    async fn probe(state: web::Data<AppState>, uri: &str) -> (StatusCode, Value) {
+        let app = test::init_service(App::new().app_data(state).service(routes())).await;
+        let res = test::call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+        (res.status(), test::read_body_json(res).await)
+    }
+
+    /// State whose pool points at a port nothing listens on.
+    fn unreachable_database() -> web::Data<AppState> {
+        let pool =
+            Pool::builder()
+                .max_size(1)
+                .build_unchecked(ConnectionManager::<PgConnection>::new(
+                    "postgres://nobody@127.0.0.1:1/none",
+                ));
+        web::Data::new(AppState::new(
+            pool,
+            PersonService::new(),
+            UserService::new(),
+            "secret".into(),
+        ))
+    }
+
+    #[actix_web::test]
+    async fn liveness_does_not_touch_the_database() {
+        let (status, body) = probe(unreachable_database(), "/health/live").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "up");
+        assert_eq!(body["build"]["version"], env!("CARGO_PKG_VERSION"));
+        assert!(body.get("checks").is_none());
+    }
+
+    #[actix_web::test]
+    async fn readiness_fails_when_the_database_is_unreachable() {
+        let (status, body) = probe(unreachable_database(), "/health/ready").await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "down");
+        assert_eq!(body["checks"]["database"]["status"], "down");
+        assert!(body["checks"]["database"]["error"].is_string());
+        assert_eq!(body["checks"]["migrations"]["status"], "down");
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn readiness_reports_each_check_of_a_migrated_database() {
+        let state = testing::state(&AppConfig::for_tests());
+        let (status, body) = probe(state, "/health/ready").await;
+
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["status"], "up");
+        assert!(body["checks"]["database"]["latency_ms"].is_u64());
+        assert_eq!(
+            body["checks"]["migrations"]["pending"],
+            serde_json::json!([])
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use actix_web::{
+    HttpRequest, HttpResponse, Scope, get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web::{self, Bytes},
+};
+use futures_util::stream;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        claims::Claims,
+        claims_extractor::{require_admin, require_self_or_admin},
+    },
+    dto::{
+        hateoas::{Link, Links},
+        job_dto::JobResponse,
+    },
+    error::{app_error::AppError, problem::ProblemDetails},
+    model::{job::Job, job_file_purpose::JobFilePurpose, job_kind::JobKind, job_status::JobStatus},
+    service::job_service::JobService,
+    util::{app_state::AppState, blocking::block},
+};
+
+/// URL clients poll for the job `id`.
+pub fn job_href(req: &HttpRequest, id: &Uuid) -> String {
+    req.url_for("job_get_by_id", [id.to_string()])
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| format!("/jobs/{id}"))
+}
+
+pub fn job_response(req: &HttpRequest, job: &Job) -> JobResponse {
+    let mut links = Links::new();
+    links.insert("self".into(), Link::get(job_href(req, job.id())));
+    if job.kind() == JobKind::PersonExport && job.status() == JobStatus::Succeeded {
+        let file_href = req
+            .url_for("job_get_file", [job.id().to_string()])
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| format!("/jobs/{}/file", job.id()));
+        links.insert("file".into(), Link::get(file_href));
+    }
+
+    JobResponse {
+        id: *job.id(),
+        kind: job.kind(),
+        status: job.status(),
+        attempts: job.attempts(),
+        max_attempts: job.max_attempts(),
+        run_at: *job.run_at(),
+        last_error: job.last_error().map(str::to_string),
+        result: job.result().cloned(),
+        created_by: job.created_by(),
+        created_at: *job.created_at(),
+        updated_at: *job.updated_at(),
+        finished_at: job.finished_at().copied(),
+        links,
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_job_by_id, get_job_file))]
+pub struct JobApi;
+
+pub fn routes() -> Scope {
+    web::scope("/jobs")
+        .service(get_job_by_id)
+        .service(get_job_file)
+}
+
+/// Consulta o estado e o resultado de uma tarefa - dono ou admin
+#[utoipa::path(
+    params(("id" = Uuid, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Job", body = JobResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails)
+    )
+)]
+#[get("/{id}", name = "job_get_by_id")]
+async fn get_job_by_id(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    claims: Claims,
+) -> Result<HttpResponse, AppError> {
+    let pool = state.pool().clone();
+    let id = path.into_inner();
+
+    let job = block(move || JobService::find_by_id(&pool, id)).await??;
+    require_job_access(&claims, &job)?;
+
+    Ok(HttpResponse::Ok().json(job_response(&req, &job)))
+}
+
+/// Baixa o arquivo produzido por uma tarefa - dono ou admin
+#[utoipa::path(
+    params(("id" = Uuid, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "The file, as an attachment",
+            content(
+                (String = "text/csv"),
+                (String = "application/x-ndjson"),
+                (String = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+            )
+        ),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails)
+    )
+)]
+#[get("/{id}/file", name = "job_get_file")]
+async fn get_job_file(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    claims: Claims,
+) -> Result<HttpResponse, AppError> {
+    let pool = state.pool().clone();
+    let id = path.into_inner();
+
+    let file = block(move || {
+        let job = JobService::find_by_id(&pool, id)?;
+        require_job_access(&claims, &job)?;
+        JobService::find_file(&pool, id, JobFilePurpose::Output)
+    })
+    .await??;
+
+    // sent a stored chunk at a time; an error aborts the response, so a
+    // cut-short file is not taken as complete
+    let body = stream::unfold(Some(0), move |seq| {
+        let pool = state.pool();
+        async move {
+            let seq = seq?;
+            let chunk =
+                block(move || JobService::find_file_chunk(&pool, id, JobFilePurpose::Output, seq))
+                    .await
+                    .map_err(AppError::from)
+                    .and_then(|chunk| chunk);
+            match chunk {
+                Ok(Some(data)) => Some((Ok(Bytes::from(data)), Some(seq + 1))),
+                Ok(None) => None,
+                Err(err) => {
+                    tracing::error!(error = %err, "sending a job file failed");
+                    Some((Err(err), None))
+                }
+            }
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(file.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(file.file_name().to_string())],
+        })
+        .streaming(body))
+}
+
+fn require_job_access(claims: &Claims, job: &Job) -> Result<(), AppError> {
+    // system jobs have no owner
+    match job.created_by() {
+        Some(owner) => require_self_or_admin(claims, &owner),
+        None => require_admin(claims),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App,
+        http::{StatusCode, header},
+        test::{self, TestRequest},
+    };
+    use diesel::prelude::*;
+    use serde_json::json;
+
+    use crate::{
+        bootstrap::http_server::testing,
+        config::AppConfig,
+        model::{job_kind::JobKind, role::Role},
+        schema::jobs::dsl::jobs,
+        service::job_service::JobService,
+    };
+
+    fn get(uri: &str, token: &str) -> TestRequest {
+        TestRequest::get()
+            .uri(uri)
+            .insert_header((header::AUTHORIZATION, format!("Bearer {token}")))
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn jobs_are_seen_by_their_owner_and_admins() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (owner_id, owner) = testing::user(&state, Role::User);
+        let (_, other) = testing::user(&state, Role::User);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let job = JobService::enqueue(
+            &state.pool(),
+            JobKind::PersonExport,
+            json!({}),
+            Some(owner_id),
+        )
+        .unwrap();
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        let uri = format!("/api/jobs/{}", job.id());
+        for (token, status) in [
+            (&owner, StatusCode::OK),
+            (&admin, StatusCode::OK),
+            (&other, StatusCode::FORBIDDEN),
+        ] {
+            let res = test::call_service(&app, get(&uri, token).to_request()).await;
+            assert_eq!(res.status(), status);
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn the_file_is_downloadable_once_written() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (owner_id, owner) = testing::user(&state, Role::User);
+        let (_, other) = testing::user(&state, Role::User);
+        let pool = state.pool();
+        diesel::delete(jobs)
+            .execute(&mut pool.get().unwrap())
+            .unwrap();
+        JobService::enqueue(&pool, JobKind::PersonExport, json!({}), Some(owner_id)).unwrap();
+        // the attempt that writes the file
+        let job = JobService::claim_next(&pool).unwrap().unwrap();
+        let app =
+            test::init_service(App::new().configure(testing::api(config, state.clone()))).await;
+
+        let uri = format!("/api/jobs/{}/file", job.id());
+        let res = test::call_service(&app, get(&uri, &owner).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let mut output = JobService::create_output(
+            &pool,
+            *job.id(),
+            job.attempts(),
+            "people.csv".into(),
+            "text/csv".into(),
+        )
+        .unwrap();
+        output.write(b"id,").unwrap();
+        output.write(b"name\n").unwrap();
+        output.finish().unwrap();
+
+        let res = test::call_service(&app, get(&uri, &other).to_request()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = test::call_service(&app, get(&uri, &owner).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let headers = res.headers();
+        assert_eq!(headers.get(header::CONTENT_TYPE).unwrap(), "text/csv");
+        assert_eq!(
+            headers.get(header::CONTENT_DISPOSITION).unwrap(),
+            "attachment; filename=\"people.csv\""
+        );
+        assert_eq!(test::read_body(res).await, "id,name\n");
+    }
+}
@@ -0,0 +1,13 @@
+use actix_web::{HttpResponse, get, web};
+
+use crate::{metrics::METRICS, util::app_state::AppState};
+
+/// Métricas no formato texto do Prometheus
+#[get("/metrics")]
+pub async fn metrics(state: web::Data<AppState>) -> HttpResponse {
+    METRICS.observe_pool(&state.pool());
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(METRICS.render())
+}
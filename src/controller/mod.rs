@@ -1,3 +1,7 @@
+pub mod audit_controller;
 pub mod auth_controller;
+pub mod health_controller;
+pub mod job_controller;
+pub mod metrics_controller;
 pub mod person_controller;
 pub mod user_controller;
@@ -1,70 +1,130 @@
 use crate::{
-    dto::person_dto::{PaginatedResponse, PaginationQuery},
-    error::error_response::ErrorResponse,
+    dto::person_dto::{
+        ExportQuery, ImportQuery, ImportReport, PaginatedResponse, PaginationQuery,
+        PersonExportRow, PersonFilterQuery, PersonImportForm, PersonSearchItem, PersonSearchQuery,
+        optional_text,
+    },
+    error::{app_error::AppError, problem::ProblemDetails},
+    i18n::{Locale, Message},
+};
+use actix_web::{
+    HttpRequest, HttpResponse, Scope, delete, get,
+    http::header::{
+        self, CacheControl, CacheDirective, ContentDisposition, DispositionParam, DispositionType,
+        EntityTag,
+    },
+    patch, post, put, rt,
+    web::{self, Bytes},
 };
-use actix_web::{HttpRequest, HttpResponse, Scope, delete, get, patch, post, put, web};
-use cpf_util;
+use futures_util::stream;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::ops::ControlFlow;
+use tokio::sync::mpsc;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
     auth::claims::Claims,
     auth::claims_extractor::require_admin,
+    config::AppConfig,
+    controller::job_controller::{job_href, job_response},
+    dto::job_dto::JobResponse,
     dto::person_dto::{
-        PersonRequest, PersonResponse, UpdateCpfRequest, UpdateNameRequest, UpdatePersonRequest,
+        AsOfQuery, IncludeDeletedQuery, PersonHistoryResponse, PersonRequest, PersonResponse,
+        PersonVersionResponse, UpdateCpfRequest, UpdateNameRequest, UpdatePersonRequest,
+    },
+    model::{
+        cpf::{Cpf, InvalidCpf},
+        job_kind::JobKind,
+        person::{NewPerson, Person, UpdatePerson},
+        person_version::PersonVersion,
+    },
+    service::job_service::{
+        FileContent, JobService, PersonExportPayload, PersonImportPayload, PersonPurgePayload,
+    },
+    util::{
+        app_state::AppState,
+        audit_context::AuditContext,
+        blocking::block,
+        csv_upload::CsvUpload,
+        export_writer::ExportWriter,
+        http_cache::Validators,
+        if_match::{IfMatch, variant_tag},
+        patch_document::PatchDocument,
+        validated_json::ValidatedJson,
     },
-    util::app_state::AppState,
 };
 
-use crate::dto::hateoas::{Link, Links};
+use crate::dto::hateoas::{Link, Links, collection_page_links};
+
+/// Encoded chunks an export may run ahead of the client.
+const EXPORT_BUFFERED_CHUNKS: usize = 4;
 
-fn page_count(total: i64, size: i64) -> i64 {
-    if size <= 0 {
-        return 1;
+/// Weak tag for a page of people. There is no `Last-Modified` for pages:
+/// people leaving a page do not move any `updated_at` left on it. The hash
+/// is fixed, so every instance and release tags the same page alike.
+fn person_page_tag(
+    filters: &str,
+    page: i64,
+    size: i64,
+    total: i64,
+    people: &[Person],
+    is_admin: bool,
+    mask_cpf: bool,
+) -> EntityTag {
+    let mut hasher = Sha256::new();
+    hasher.update((filters.len() as u64).to_be_bytes());
+    hasher.update(filters);
+    for n in [page, size, total] {
+        hasher.update(n.to_be_bytes());
     }
-    ((total + size - 1) / size).max(1)
+    hasher.update([u8::from(is_admin), u8::from(mask_cpf)]);
+    for person in people {
+        hasher.update(person.id().as_bytes());
+        hasher.update(person.version().to_be_bytes());
+    }
+    let digest = hasher.finalize();
+    let hex: String = digest[..16].iter().map(|b| format!("{b:02x}")).collect();
+    EntityTag::new_weak(hex)
 }
 
-fn person_collection_links(req: &HttpRequest, page: i64, size: i64, total: i64) -> Links {
-    let mut links = Links::new();
+/// Whether the caller gets the CPF masked; see `MASK_CPF_FOR_NON_ADMINS`.
+fn masks_cpf(req: &HttpRequest, claims: Option<&Claims>) -> bool {
+    !claims.is_some_and(|c| c.is_admin())
+        && req
+            .app_data::<web::Data<AppConfig>>()
+            .is_some_and(|config| config.mask_cpf_for_non_admins())
+}
 
-    let base = req
-        .url_for("person_find_all", std::iter::empty::<&str>())
-        .map(|u| u.to_string())
-        .unwrap_or_else(|_| "/person".to_string());
+/// Tag of `person` as the caller sees it: admins get links, and possibly a
+/// CPF, that other users do not.
+fn person_tag(req: &HttpRequest, person: &Person, claims: Option<&Claims>) -> EntityTag {
+    let variant = if claims.is_some_and(|c| c.is_admin()) {
+        "admin"
+    } else if masks_cpf(req, claims) {
+        "masked"
+    } else {
+        "user"
+    };
+    variant_tag(person.version(), variant)
+}
 
-    let last = page_count(total, size);
-
-    links.insert(
-        "self".into(),
-        Link::get(format!("{base}?page={page}&size={size}")),
-    );
-    links.insert(
-        "first".into(),
-        Link::get(format!("{base}?page=1&size={size}")),
-    );
-    links.insert(
-        "last".into(),
-        Link::get(format!("{base}?page={last}&size={size}")),
-    );
-
-    if page > 1 {
-        links.insert(
-            "prev".into(),
-            Link::get(format!("{base}?page={}&size={}", page - 1, size)),
-        );
-    }
-    if page < last {
-        links.insert(
-            "next".into(),
-            Link::get(format!("{base}?page={}&size={}", page + 1, size)),
-        );
+/// Soft-deleted people are only visible to admins.
+fn require_admin_for_deleted(
+    include_deleted: bool,
+    claims: Option<&Claims>,
+) -> Result<(), AppError> {
+    match claims {
+        Some(claims) if include_deleted => require_admin(claims),
+        None if include_deleted => Err(AppError::Forbidden(Message::AdminRequired)),
+        _ => Ok(()),
     }
-
-    links
 }
 
-fn person_links(req: &HttpRequest, id: Uuid, claims: Option<&Claims>) -> Links {
+fn person_links(req: &HttpRequest, person: &Person, claims: Option<&Claims>) -> Links {
     let mut links = Links::new();
+    let id = *person.id();
     let id_s = id.to_string();
 
     let self_href = req
@@ -85,7 +145,14 @@ fn person_links(req: &HttpRequest, id: Uuid, claims: Option<&Claims>) -> Links {
         .unwrap_or_else(|_| "/person".to_string());
     links.insert("create".into(), Link::post(create_href));
 
-    if claims.map_or(false, |c| c.is_admin()) {
+    let is_admin = claims.is_some_and(|c| c.is_admin());
+    if is_admin && person.is_deleted() {
+        let restore_href = req
+            .url_for("person_restore", [id_s.as_str()])
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| format!("/person/{id}/restore"));
+        links.insert("restore".into(), Link::post(restore_href));
+    } else if is_admin {
         let del_href = req
             .url_for("person_delete", [id_s.as_str()])
             .map(|u| u.to_string())
@@ -98,6 +165,12 @@ fn person_links(req: &HttpRequest, id: Uuid, claims: Option<&Claims>) -> Links {
             .unwrap_or_else(|_| format!("/person/{id}"));
         links.insert("update".into(), Link::put(put_href));
 
+        let patch_href = req
+            .url_for("person_patch", [id_s.as_str()])
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| format!("/person/{id}"));
+        links.insert("patch".into(), Link::patch(patch_href));
+
         let patch_name_href = req
             .url_for("person_patch_name", [id_s.as_str()])
             .map(|u| u.to_string())
@@ -111,249 +184,1225 @@ fn person_links(req: &HttpRequest, id: Uuid, claims: Option<&Claims>) -> Links {
         links.insert("update_cpf".into(), Link::patch(patch_cpf_href));
     }
 
+    if is_admin {
+        let history_href = req
+            .url_for("person_history", [id_s.as_str()])
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| format!("/person/{id}/history"));
+        links.insert("history".into(), Link::get(history_href));
+    }
+
     links
 }
 
+fn person_version_response(version: &PersonVersion) -> PersonVersionResponse {
+    PersonVersionResponse {
+        version: version.version(),
+        name: version.name().to_string(),
+        cpf: version.cpf().formatted(),
+        birth_date: version.birth_date(),
+        email: version.email().map(str::to_string),
+        phone: version.phone().map(str::to_string),
+        changed_by: version.changed_by(),
+        valid_from: *version.valid_from(),
+        valid_to: version.valid_to().copied(),
+    }
+}
+
+fn person_response(req: &HttpRequest, person: &Person, claims: Option<&Claims>) -> PersonResponse {
+    PersonResponse {
+        id: *person.id(),
+        name: person.name().to_string(),
+        cpf: person.cpf().for_display(masks_cpf(req, claims)),
+        birth_date: person.birth_date(),
+        email: person.email().map(str::to_string),
+        phone: person.phone().map(str::to_string),
+        created_at: *person.created_at(),
+        updated_at: *person.updated_at(),
+        created_by: person.created_by(),
+        updated_by: person.updated_by(),
+        deleted_at: person.deleted_at().copied(),
+        deleted_by: person.deleted_by(),
+        links: person_links(req, person, claims),
+    }
+}
+
+fn new_person(body: &PersonRequest, actor: Option<Uuid>) -> Result<NewPerson, InvalidCpf> {
+    Ok(NewPerson::new(
+        body.name.trim().to_string(),
+        Cpf::parse(&body.cpf)?,
+        body.birth_date,
+        optional_text(&body.email),
+        optional_text(&body.phone),
+        actor,
+    ))
+}
+
+fn person_changes(body: &UpdatePersonRequest, actor: Uuid) -> Result<UpdatePerson, AppError> {
+    Ok(UpdatePerson::new(
+        body.name.trim().to_string(),
+        Cpf::parse(&body.cpf)?,
+        body.birth_date,
+        optional_text(&body.email),
+        optional_text(&body.phone),
+        Some(actor),
+    ))
+}
+
+/// Editable fields of a person, the document `PATCH /person/{id}` applies to.
+fn person_document(person: &Person) -> Value {
+    json!({
+        "name": person.name(),
+        "cpf": person.cpf().formatted(),
+        "birth_date": person.birth_date(),
+        "email": person.email(),
+        "phone": person.phone(),
+    })
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        create_person,
+        import_people,
+        export_people,
+        enqueue_person_export,
+        purge_people,
+        find_all_people,
+        search_people,
+        get_person_by_cpf,
+        get_person_by_id,
+        get_person_history,
+        update_person,
+        patch_person,
+        delete_person,
+        restore_person,
+        patch_person_name,
+        patch_person_cpf
+    ),
+    components(schemas(ImportReport))
+)]
+pub struct PersonApi;
+
 pub fn routes() -> Scope {
     web::scope("/person")
         .service(create_person)
+        .service(import_people)
         .service(find_all_people)
+        .service(export_people)
+        .service(enqueue_person_export)
+        .service(search_people)
+        .service(get_person_by_cpf)
         .service(get_person_by_id)
+        .service(get_person_history)
         .service(update_person)
+        .service(patch_person)
         .service(delete_person)
+        .service(restore_person)
+        .service(purge_people)
         .service(patch_person_name)
         .service(patch_person_cpf)
 }
 
+/// Cadastra uma pessoa
+#[utoipa::path(
+    request_body = PersonRequest,
+    responses(
+        (status = 201, description = "Person created", body = PersonResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 409, response = ProblemDetails)
+    )
+)]
 #[post("", name = "person_create")]
 async fn create_person(
     req: HttpRequest,
     claims: Option<Claims>,
     state: web::Data<AppState>,
-    body: web::Json<PersonRequest>,
-) -> HttpResponse {
+    body: ValidatedJson<PersonRequest>,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
     let pool = state.pool().clone();
     let service = state.person_service().clone();
 
-    let name = body.name.clone();
-    let cpf = cpf_util::format(&body.cpf.clone());
+    let actor = claims.as_ref().map(|c| *c.user_id());
+    let new_person = new_person(&body, actor)?;
 
-    if !cpf_util::is_valid(&cpf) {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Invalid CPF".to_string(),
-        });
-    }
+    let person = block(move || service.create_person(&pool, new_person, &ctx)).await??;
 
-    let result = web::block(move || service.create_person(&pool, name, cpf)).await;
-
-    match result {
-        Ok(Ok(person)) => {
-            let response = PersonResponse {
-                id: *person.id(),
-                name: person.name().to_string(),
-                cpf: person.cpf().to_string(),
-                links: person_links(&req, *person.id(), claims.as_ref()),
-            };
-            HttpResponse::Created().json(response)
-        }
-        Ok(Err(_)) => HttpResponse::InternalServerError().finish(),
-        Err(_) => HttpResponse::InternalServerError().finish(),
-    }
+    Ok(HttpResponse::Created()
+        .insert_header(header::ETag(person_tag(&req, &person, claims.as_ref())))
+        .json(person_response(&req, &person, claims.as_ref())))
+}
+
+/// Agenda a importação de pessoas de um arquivo CSV de até 10 MiB - apenas admin
+///
+/// The upload is buffered in memory, up to the limit, and its header checked
+/// before it is stored with the queued job; uploads are not streamed, which
+/// is why the limit stays small.
+///
+/// API change: this used to answer 200 with the `ImportReport` once the
+/// import was done. It now answers 202 with the job; poll the job at
+/// `Location` (`GET /api/jobs/{id}`) until it has `succeeded`, and read the
+/// report from its `result`.
+#[utoipa::path(
+    params(ImportQuery),
+    request_body(content = PersonImportForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 202, description = "Import enqueued; poll the job at `Location` for its `ImportReport`", body = JobResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 413, description = "The file is larger than 10 MiB", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 415, response = ProblemDetails)
+    )
+)]
+#[post("/import", name = "person_import")]
+async fn import_people(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    query: web::Query<ImportQuery>,
+    claims: Claims,
+    ctx: AuditContext,
+    upload: CsvUpload,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
+
+    state.person_service().check_import_file(&upload.0[..])?;
+
+    let pool = state.pool().clone();
+    let payload = json!(PersonImportPayload {
+        mode: query.mode,
+        locale: Locale::from_request(&req).tag().to_string(),
+        audit: ctx,
+    });
+    let owner = Some(*claims.user_id());
+    let input = FileContent {
+        file_name: "people.csv".to_string(),
+        content_type: "text/csv".to_string(),
+        data: upload.0,
+    };
+
+    let job = block(move || {
+        JobService::enqueue_with_input(&pool, JobKind::PersonImport, payload, owner, input)
+    })
+    .await??;
+
+    Ok(HttpResponse::Accepted()
+        .insert_header((header::LOCATION, job_href(&req, job.id())))
+        .json(job_response(&req, &job)))
 }
 
+/// Lista pessoas paginadas, com filtros e ordenação
+#[utoipa::path(
+    params(PaginationQuery, PersonFilterQuery),
+    responses(
+        (status = 200, description = "Page of people", body = PaginatedResponse<PersonResponse>),
+        (status = 304, description = "Page unchanged since the cached copy"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails)
+    )
+)]
 #[get("", name = "person_find_all")]
 async fn find_all_people(
     req: HttpRequest,
     state: web::Data<AppState>,
     query: web::Query<PaginationQuery>,
+    filter_query: web::Query<PersonFilterQuery>,
     claims: Option<Claims>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
+    let filter = filter_query.to_filter()?;
+    require_admin_for_deleted(filter.include_deleted, claims.as_ref())?;
+    let filters = serde_urlencoded::to_string(&*filter_query).unwrap_or_default();
+
     let pool = state.pool().clone();
     let service = state.person_service().clone();
 
     let page = query.page.max(1);
-    let size = query.size.max(1).min(100);
-
-    let result = web::block(move || service.find_page(&pool, page, size)).await;
-
-    match result {
-        Ok(Ok((total, people))) => {
-            let links = person_collection_links(&req, page, size, total);
-
-            let items: Vec<PersonResponse> = people
-                .into_iter()
-                .map(|person| {
-                    let id = *person.id();
-                    PersonResponse {
-                        id,
-                        name: person.name().to_string(),
-                        cpf: person.cpf().to_string(),
-                        links: person_links(&req, id, claims.as_ref()),
-                    }
-                })
-                .collect();
-
-            HttpResponse::Ok().json(PaginatedResponse {
-                page,
-                size,
-                total,
-                items,
-                links,
-            })
+    let size = query.size.clamp(1, 100);
+
+    let (total, people) = block(move || service.find_page(&pool, &filter, page, size)).await??;
+
+    let is_admin = claims.as_ref().is_some_and(|c| c.is_admin());
+    let validators = Validators::new(person_page_tag(
+        &filters,
+        page,
+        size,
+        total,
+        &people,
+        is_admin,
+        masks_cpf(&req, claims.as_ref()),
+    ));
+
+    Ok(validators.respond(&req, || {
+        let links = collection_page_links(&req, "person_find_all", &filters, page, size, total);
+
+        let items: Vec<PersonResponse> = people
+            .iter()
+            .map(|person| person_response(&req, person, claims.as_ref()))
+            .collect();
+
+        PaginatedResponse {
+            page,
+            size,
+            total,
+            items,
+            links,
+        }
+    }))
+}
+
+/// Exporta as pessoas filtradas em CSV, NDJSON ou XLSX
+#[utoipa::path(
+    params(ExportQuery, PersonFilterQuery),
+    responses(
+        (status = 200, description = "Every matching person, streamed as a file attachment",
+            content(
+                (String = "text/csv"),
+                (String = "application/x-ndjson"),
+                (String = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+            )
+        ),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails)
+    )
+)]
+#[get("/export", name = "person_export")]
+async fn export_people(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    query: web::Query<ExportQuery>,
+    filter_query: web::Query<PersonFilterQuery>,
+    claims: Option<Claims>,
+) -> Result<HttpResponse, AppError> {
+    let filter = filter_query.to_filter()?;
+    require_admin_for_deleted(filter.include_deleted, claims.as_ref())?;
+
+    let format = query.format;
+    let mask = masks_cpf(&req, claims.as_ref());
+    let pool = state.pool().clone();
+    let service = state.person_service().clone();
+
+    // rows are encoded on a blocking thread while the response is being sent;
+    // the bounded channel keeps the database from outrunning the client
+    let (tx, rx) = mpsc::channel::<Result<Bytes, AppError>>(EXPORT_BUFFERED_CHUNKS);
+    rt::spawn(block(move || {
+        let export = || {
+            let mut writer = ExportWriter::new(format, PersonExportRow::COLUMNS)?;
+            service.export(&pool, &filter, |people| {
+                for person in &people {
+                    writer.write(&PersonExportRow::new(person, mask))?;
+                }
+                let chunk = writer.take_chunk()?;
+                if !chunk.is_empty() && tx.blocking_send(Ok(chunk)).is_err() {
+                    // the client went away
+                    return Ok(ControlFlow::Break(()));
+                }
+                Ok(ControlFlow::Continue(()))
+            })?;
+            writer.finish()
+        };
+        // an error aborts the response, so a cut-short file is not taken as complete
+        let last =
+            export().inspect_err(|err| tracing::error!(error = %err, "person export failed"));
+        if !last.as_ref().is_ok_and(Bytes::is_empty) {
+            let _ = tx.blocking_send(last);
         }
-        _ => HttpResponse::InternalServerError().finish(),
+    }));
+    let body = stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "people.{}",
+                format.extension()
+            ))],
+        })
+        .streaming(body))
+}
+
+/// Agenda a exportação das pessoas filtradas para um arquivo
+///
+/// Like `GET /person/export`, but the file is written by a job and
+/// downloaded from the job once it succeeds.
+#[utoipa::path(
+    params(ExportQuery, PersonFilterQuery),
+    responses(
+        (status = 202, description = "Export enqueued; poll the job at `Location`, then follow its `file` link", body = JobResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails)
+    )
+)]
+#[post("/export", name = "person_export_job")]
+async fn enqueue_person_export(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    query: web::Query<ExportQuery>,
+    filter_query: web::Query<PersonFilterQuery>,
+    claims: Claims,
+) -> Result<HttpResponse, AppError> {
+    let filter = filter_query.to_filter()?;
+    require_admin_for_deleted(filter.include_deleted, Some(&claims))?;
+
+    let pool = state.pool().clone();
+    let payload = json!(PersonExportPayload {
+        format: query.format,
+        filters: serde_urlencoded::to_string(&*filter_query).unwrap_or_default(),
+        mask_cpf: masks_cpf(&req, Some(&claims)),
+    });
+    let owner = Some(*claims.user_id());
+
+    let job =
+        block(move || JobService::enqueue(&pool, JobKind::PersonExport, payload, owner)).await??;
+
+    Ok(HttpResponse::Accepted()
+        .insert_header((header::LOCATION, job_href(&req, job.id())))
+        .json(job_response(&req, &job)))
+}
+
+/// Busca pessoas pelo nome, ignorando acentos e erros de digitação
+#[utoipa::path(
+    params(PaginationQuery, PersonSearchQuery),
+    responses(
+        (status = 200, description = "Matches ordered by score", body = PaginatedResponse<PersonSearchItem>),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails)
+    )
+)]
+#[get("/search", name = "person_search")]
+async fn search_people(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    query: web::Query<PaginationQuery>,
+    search: web::Query<PersonSearchQuery>,
+    claims: Option<Claims>,
+) -> Result<HttpResponse, AppError> {
+    let term = search.q.trim().to_string();
+    if term.is_empty() {
+        return Err(AppError::invalid_field("q", Message::EmptySearchQuery));
     }
+    let filters = serde_urlencoded::to_string(&*search).unwrap_or_default();
+
+    let pool = state.pool().clone();
+    let service = state.person_service().clone();
+
+    let page = query.page.max(1);
+    let size = query.size.clamp(1, 100);
+
+    let (total, matches) = block(move || service.search(&pool, &term, page, size)).await??;
+
+    let links = collection_page_links(&req, "person_search", &filters, page, size, total);
+
+    let items: Vec<PersonSearchItem> = matches
+        .iter()
+        .map(|(person, score)| PersonSearchItem {
+            person: person_response(&req, person, claims.as_ref()),
+            score: *score,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(PaginatedResponse {
+        page,
+        size,
+        total,
+        items,
+        links,
+    }))
 }
 
+/// Busca pessoa por CPF, com ou sem pontuação
+#[utoipa::path(
+    params(
+        ("cpf" = String, Path, description = "CPF, digits optionally punctuated with `.`, `-` or spaces"),
+        IncludeDeletedQuery
+    ),
+    responses(
+        (status = 200, description = "Person found", body = PersonResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails)
+    )
+)]
+#[get("/by-cpf/{cpf}", name = "person_get_by_cpf")]
+async fn get_person_by_cpf(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<IncludeDeletedQuery>,
+    claims: Option<Claims>,
+) -> Result<HttpResponse, AppError> {
+    let cpf = Cpf::parse(&path.into_inner())?;
+    let include_deleted = query.include_deleted;
+    require_admin_for_deleted(include_deleted, claims.as_ref())?;
+
+    let pool = state.pool().clone();
+    let service = state.person_service().clone();
+
+    let person = block(move || service.find_by_cpf(&pool, &cpf, include_deleted)).await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(header::ETag(person_tag(&req, &person, claims.as_ref())))
+        .json(person_response(&req, &person, claims.as_ref())))
+}
+
+/// Busca pessoa por ID, opcionalmente como estava em `as_of`
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "Person id"),
+        AsOfQuery,
+        IncludeDeletedQuery
+    ),
+    responses(
+        (status = 200, description = "Person found", body = PersonResponse),
+        (status = 304, description = "Person unchanged since the cached copy"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails)
+    )
+)]
 #[get("/{id}", name = "person_get_by_id")]
 async fn get_person_by_id(
     req: HttpRequest,
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
+    query: web::Query<AsOfQuery>,
+    deleted: web::Query<IncludeDeletedQuery>,
     claims: Option<Claims>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
+    let as_of = query.as_of;
+    let include_deleted = deleted.include_deleted;
+    require_admin_for_deleted(include_deleted, claims.as_ref())?;
 
     let pool = state.pool().clone();
     let service = state.person_service().clone();
 
-    let result = web::block(move || service.find_by_id(&pool, id)).await;
-
-    match result {
-        Ok(Ok(person)) => HttpResponse::Ok().json(PersonResponse {
-            id,
-            name: person.name().to_string(),
-            cpf: person.cpf().to_string(),
-            links: person_links(&req, id, claims.as_ref()),
-        }),
-        Ok(Err(diesel::result::Error::NotFound)) => HttpResponse::NotFound().finish(),
-        _ => HttpResponse::InternalServerError().finish(),
+    let person = block(move || match as_of {
+        Some(at) => service.find_as_of(&pool, id, at, include_deleted),
+        None => service.find_by_id(&pool, id, include_deleted),
+    })
+    .await??;
+
+    // what was true at `as_of` changes while `as_of` is still ahead, and a
+    // past version is never the target of a conditional write
+    if as_of.is_some() {
+        return Ok(HttpResponse::Ok()
+            .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+            .json(person_response(&req, &person, claims.as_ref())));
     }
+
+    let validators = Validators::new(person_tag(&req, &person, claims.as_ref()))
+        .last_modified(*person.updated_at());
+
+    Ok(validators.respond(&req, || person_response(&req, &person, claims.as_ref())))
 }
 
+/// Lista as versões de uma pessoa, mais recentes primeiro - apenas admin
+#[utoipa::path(
+    params(("id" = Uuid, Path, description = "Person id")),
+    responses(
+        (status = 200, description = "Versions of the person", body = PersonHistoryResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails)
+    )
+)]
+#[get("/{id}/history", name = "person_history")]
+async fn get_person_history(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    claims: Claims,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
+
+    let id = path.into_inner();
+    let pool = state.pool().clone();
+    let service = state.person_service().clone();
+
+    let versions = block(move || service.history(&pool, id)).await??;
+
+    let id_s = id.to_string();
+    let mut links = Links::new();
+    let self_href = req
+        .url_for("person_history", [id_s.as_str()])
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| format!("/person/{id}/history"));
+    links.insert("self".into(), Link::get(self_href));
+    let person_href = req
+        .url_for("person_get_by_id", [id_s.as_str()])
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| format!("/person/{id}"));
+    links.insert("person".into(), Link::get(person_href));
+
+    Ok(HttpResponse::Ok().json(PersonHistoryResponse {
+        person_id: id,
+        versions: versions.iter().map(person_version_response).collect(),
+        links,
+    }))
+}
+
+/// Remove pessoa - apenas admin
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "Person id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    responses(
+        (status = 204, description = "Person deleted"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
 #[delete("/{id}", name = "person_delete")]
 async fn delete_person(
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
     claims: Claims,
-) -> HttpResponse {
-    if let Err(response) = require_admin(&claims) {
-        return response;
-    }
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
+
     let pool = state.pool().clone();
     let service = state.person_service().clone();
     let id = path.into_inner();
 
-    let result = web::block(move || service.delete_person(&pool, id)).await;
+    block(move || service.delete_person(&pool, id, &if_match, &ctx)).await??;
 
-    match result {
-        Ok(Ok(_person)) => HttpResponse::NoContent().finish(),
-        Ok(Err(diesel::result::Error::NotFound)) => HttpResponse::NotFound().finish(),
-        _ => HttpResponse::InternalServerError().finish(),
-    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Restaura pessoa removida - apenas admin
+#[utoipa::path(
+    params(("id" = Uuid, Path, description = "Person id")),
+    responses(
+        (status = 200, description = "Person restored", body = PersonResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails)
+    )
+)]
+#[post("/{id}/restore", name = "person_restore")]
+async fn restore_person(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    claims: Claims,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
+
+    let pool = state.pool().clone();
+    let service = state.person_service().clone();
+    let id = path.into_inner();
+
+    let person = block(move || service.restore_person(&pool, id, &ctx)).await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(header::ETag(person_tag(&req, &person, Some(&claims))))
+        .json(person_response(&req, &person, Some(&claims))))
+}
+
+/// Agenda a remoção definitiva das pessoas excluídas há mais tempo que a retenção - apenas admin
+#[utoipa::path(
+    responses(
+        (status = 202, description = "Purge enqueued; poll the job at `Location`", body = JobResponse),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails)
+    )
+)]
+#[post("/purge", name = "person_purge")]
+async fn purge_people(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    config: web::Data<AppConfig>,
+    claims: Claims,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
+
+    let pool = state.pool().clone();
+    let payload = json!(PersonPurgePayload {
+        retention_days: config.person_retention_days(),
+    });
+    let owner = Some(*claims.user_id());
+
+    let job =
+        block(move || JobService::enqueue(&pool, JobKind::PersonPurge, payload, owner)).await??;
+
+    Ok(HttpResponse::Accepted()
+        .insert_header((header::LOCATION, job_href(&req, job.id())))
+        .json(job_response(&req, &job)))
 }
 
+/// Atualiza nome - apenas admin
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "Person id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    request_body = UpdateNameRequest,
+    responses(
+        (status = 200, description = "Person updated", body = PersonResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
 #[patch("/name/{id}", name = "person_patch_name")]
 async fn patch_person_name(
     req: HttpRequest,
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
-    body: web::Json<UpdateNameRequest>,
+    body: ValidatedJson<UpdateNameRequest>,
     claims: Claims,
-) -> HttpResponse {
-    if let Err(response) = require_admin(&claims) {
-        return response;
-    }
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
 
     let pool = state.pool().clone();
     let service = state.person_service().clone();
     let id = path.into_inner();
-    let name = body.name.clone();
-
-    let result = web::block(move || service.update_name(&pool, id, name)).await;
-
-    match result {
-        Ok(Ok(person)) => HttpResponse::Ok().json(PersonResponse {
-            id: *person.id(),
-            name: person.name().to_string(),
-            cpf: person.cpf().to_string(),
-            links: person_links(&req, *person.id(), Some(&claims)),
-        }),
-        Ok(Err(diesel::result::Error::NotFound)) => HttpResponse::NotFound().finish(),
-        _ => HttpResponse::InternalServerError().finish(),
-    }
+    let name = body.name.trim().to_string();
+
+    let person = block(move || service.update_name(&pool, id, name, &if_match, &ctx)).await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(header::ETag(person_tag(&req, &person, Some(&claims))))
+        .json(person_response(&req, &person, Some(&claims))))
 }
 
+/// Atualiza CPF - apenas admin
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "Person id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    request_body = UpdateCpfRequest,
+    responses(
+        (status = 200, description = "Person updated", body = PersonResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
 #[patch("/cpf/{id}", name = "person_patch_cpf")]
 async fn patch_person_cpf(
     req: HttpRequest,
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
-    body: web::Json<UpdateCpfRequest>,
+    body: ValidatedJson<UpdateCpfRequest>,
     claims: Claims,
-) -> HttpResponse {
-    if let Err(response) = require_admin(&claims) {
-        return response;
-    }
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
+
     let pool = state.pool().clone();
     let service = state.person_service().clone();
     let id = path.into_inner();
-    let cpf = cpf_util::format(&body.cpf.clone());
+    let cpf = Cpf::parse(&body.cpf)?;
 
-    if !cpf_util::is_valid(&cpf) {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Invalid CPF".to_string(),
-        });
-    }
+    let person = block(move || service.update_cpf(&pool, id, cpf, &if_match, &ctx)).await??;
 
-    let result = web::block(move || service.update_cpf(&pool, id, cpf)).await;
-
-    match result {
-        Ok(Ok(person)) => HttpResponse::Ok().json(PersonResponse {
-            id: *person.id(),
-            name: person.name().to_string(),
-            cpf: person.cpf().to_string(),
-            links: person_links(&req, id, Some(&claims)),
-        }),
-        Ok(Err(diesel::result::Error::NotFound)) => HttpResponse::NotFound().finish(),
-        _ => HttpResponse::InternalServerError().finish(),
-    }
+    Ok(HttpResponse::Ok()
+        .insert_header(header::ETag(person_tag(&req, &person, Some(&claims))))
+        .json(person_response(&req, &person, Some(&claims))))
 }
 
+/// Atualiza pessoa completa - apenas admin
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "Person id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    request_body = UpdatePersonRequest,
+    responses(
+        (status = 200, description = "Person updated", body = PersonResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
 #[put("/{id}", name = "person_update")]
 async fn update_person(
     req: HttpRequest,
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
-    body: web::Json<UpdatePersonRequest>,
+    body: ValidatedJson<UpdatePersonRequest>,
     claims: Claims,
-) -> HttpResponse {
-    if let Err(response) = require_admin(&claims) {
-        return response;
-    }
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
+
+    let pool = state.pool().clone();
+    let service = state.person_service().clone();
+    let id = path.into_inner();
+    let changes = person_changes(&body, *claims.user_id())?;
+
+    let person =
+        block(move || service.update_person(&pool, id, changes, &if_match, &ctx)).await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(header::ETag(person_tag(&req, &person, Some(&claims))))
+        .json(person_response(&req, &person, Some(&claims))))
+}
+
+/// Atualiza campos de uma pessoa via merge patch ou JSON patch - apenas admin
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "Person id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    request_body(
+        description = "Patch over `name`, `cpf`, `birth_date`, `email` and `phone`",
+        content(
+            (Object = "application/merge-patch+json"),
+            (Vec<Object> = "application/json-patch+json")
+        )
+    ),
+    responses(
+        (status = 200, description = "Person updated", body = PersonResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 415, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
+#[patch("/{id}", name = "person_patch")]
+async fn patch_person(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    patch: PatchDocument,
+    claims: Claims,
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
 
     let pool = state.pool().clone();
     let service = state.person_service().clone();
     let id = path.into_inner();
-    let name = body.name.clone();
-    let cpf = cpf_util::format(&body.cpf);
+    let actor = *claims.user_id();
+
+    let person = block(move || {
+        service.patch_person(&pool, id, &if_match, &ctx, |person| {
+            let body: UpdatePersonRequest = patch.apply_to(person_document(person))?;
+            person_changes(&body, actor)
+        })
+    })
+    .await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(header::ETag(person_tag(&req, &person, Some(&claims))))
+        .json(person_response(&req, &person, Some(&claims))))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App,
+        http::{StatusCode, header},
+        test::{self, TestRequest},
+    };
+    use serde_json::Value;
+
+    use crate::{
+        bootstrap::http_server::testing,
+        config::AppConfig,
+        model::{job_file_purpose::JobFilePurpose, job_kind::JobKind, role::Role},
+        service::job_service::JobService,
+    };
+
+    const BOUNDARY: &str = "test-boundary";
+
+    fn csv_upload(uri: &str, token: &str, csv: &str) -> TestRequest {
+        let body = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"people.csv\"\r\n\
+             Content-Type: text/csv\r\n\r\n{csv}\r\n--{BOUNDARY}--\r\n"
+        );
+        TestRequest::post()
+            .uri(uri)
+            .insert_header((header::AUTHORIZATION, format!("Bearer {token}")))
+            .insert_header((
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            ))
+            .set_payload(body)
+    }
+
+    fn get(uri: &str, token: &str) -> TestRequest {
+        TestRequest::get()
+            .uri(uri)
+            .insert_header((header::AUTHORIZATION, format!("Bearer {token}")))
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn import_is_queued_with_its_file() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let app =
+            test::init_service(App::new().configure(testing::api(config, state.clone()))).await;
+
+        let req = csv_upload(
+            "/api/person/import",
+            &admin,
+            "name,cpf\nAna,529.982.247-25\n",
+        );
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        assert!(res.headers().contains_key(header::LOCATION));
+
+        let job: Value = test::read_body_json(res).await;
+        assert_eq!(job["kind"], "person_import");
+        assert_eq!(job["status"], "queued");
+
+        let id = job["id"].as_str().unwrap().parse().unwrap();
+        let data = JobService::read_file(&state.pool(), id, JobFilePurpose::Input).unwrap();
+        assert!(data.starts_with(b"name,cpf"));
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn import_without_the_required_columns_is_not_queued() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        let req = csv_upload("/api/person/import", &admin, "nome,documento\nAna,1\n");
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn import_is_for_admins() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (_, user) = testing::user(&state, Role::User);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        let req = csv_upload("/api/person/import", &user, "name,cpf\n");
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn export_job_remembers_whether_to_mask() {
+        let config = AppConfig::for_tests().with_mask_cpf_for_non_admins(true);
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let (_, user) = testing::user(&state, Role::User);
+        let app =
+            test::init_service(App::new().configure(testing::api(config, state.clone()))).await;
+
+        for (token, masked) in [(&admin, false), (&user, true)] {
+            let req = TestRequest::post()
+                .uri("/api/person/export?format=ndjson&name=Ana")
+                .insert_header((header::AUTHORIZATION, format!("Bearer {token}")));
+            let res = test::call_service(&app, req.to_request()).await;
+            assert_eq!(res.status(), StatusCode::ACCEPTED);
+
+            let body: Value = test::read_body_json(res).await;
+            let id = body["id"].as_str().unwrap().parse().unwrap();
+            let job = JobService::find_by_id(&state.pool(), id).unwrap();
+            assert_eq!(job.kind(), JobKind::PersonExport);
+            assert_eq!(job.payload()["mask_cpf"], masked);
+            assert_eq!(job.payload()["filters"], "name=Ana");
+        }
+
+        let req = TestRequest::post()
+            .uri("/api/person/export?include_deleted=true")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {user}")));
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn cpf_is_masked_for_non_admins_in_every_response() {
+        let config = AppConfig::for_tests().with_mask_cpf_for_non_admins(true);
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let (_, user) = testing::user(&state, Role::User);
+        let person = testing::person(&state);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        let cpf = person.cpf().as_str();
+        let uris = [
+            format!("/api/person/{}", person.id()),
+            format!("/api/person/by-cpf/{cpf}"),
+            format!("/api/person?cpf={cpf}"),
+        ];
+        for uri in &uris {
+            for (token, expected) in [
+                (&admin, person.cpf().formatted()),
+                (&user, person.cpf().masked()),
+            ] {
+                let res = test::call_service(&app, get(uri, token).to_request()).await;
+                assert_eq!(res.status(), StatusCode::OK, "{uri}");
+
+                let body: Value = test::read_body_json(res).await;
+                let shown = body["items"][0]["cpf"].as_str().or(body["cpf"].as_str());
+                assert_eq!(shown, Some(expected.as_str()), "{uri}");
+            }
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn people_are_found_by_cpf_in_any_notation() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let person = testing::person(&state);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        let cpf = person.cpf();
+        let spaced = cpf.formatted().replace(['.', '-'], " ");
+        for notation in [cpf.as_str().to_string(), cpf.formatted(), spaced] {
+            let uri = format!("/api/person/by-cpf/{}", notation.replace(' ', "%20"));
+            let res = test::call_service(&app, get(&uri, &admin).to_request()).await;
+            assert_eq!(res.status(), StatusCode::OK, "{notation}");
+
+            let body: Value = test::read_body_json(res).await;
+            assert_eq!(body["id"], person.id().to_string());
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn unknown_and_invalid_cpfs_are_told_apart() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let unknown = testing::fresh_cpf();
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        for (cpf, status, code) in [
+            (
+                unknown.formatted(),
+                StatusCode::NOT_FOUND,
+                "person.not_found",
+            ),
+            (
+                "529.982.247-26".to_string(),
+                StatusCode::BAD_REQUEST,
+                "validation.invalid_cpf",
+            ),
+        ] {
+            let uri = format!("/api/person/by-cpf/{cpf}");
+            let res = test::call_service(&app, get(&uri, &admin).to_request()).await;
+            assert_eq!(res.status(), status, "{cpf}");
+
+            let body: Value = test::read_body_json(res).await;
+            assert_eq!(body["code"], code, "{cpf}");
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn history_lists_each_version_with_who_made_it() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (admin_id, admin) = testing::user(&state, Role::Admin);
+        let (_, user) = testing::user(&state, Role::User);
+        let person = testing::person(&state);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        let renamed = test::call_service(
+            &app,
+            TestRequest::patch()
+                .uri(&format!("/api/person/name/{}", person.id()))
+                .insert_header((header::AUTHORIZATION, format!("Bearer {admin}")))
+                .set_json(serde_json::json!({ "name": "Pessoa Renomeada" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(renamed.status(), StatusCode::OK);
+
+        let uri = format!("/api/person/{}/history", person.id());
+        let res = test::call_service(&app, get(&uri, &admin).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(res).await;
+        let versions = body["versions"].as_array().unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0]["name"], "Pessoa Renomeada");
+        assert_eq!(versions[0]["changed_by"], admin_id.to_string());
+        assert!(versions[0]["valid_to"].is_null());
+        assert_eq!(versions[1]["name"], "Pessoa de Teste");
+
+        let res = test::call_service(&app, get(&uri, &user).to_request()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn deleted_people_are_only_shown_to_admins_who_ask() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let (_, user) = testing::user(&state, Role::User);
+        let person = testing::person(&state);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+        let uri = format!("/api/person/{}", person.id());
+        let with_deleted = format!("{uri}?include_deleted=true");
+        let admin_call = |req: TestRequest| {
+            req.insert_header((header::AUTHORIZATION, format!("Bearer {admin}")))
+                .to_request()
+        };
+
+        let res = test::call_service(&app, admin_call(TestRequest::delete().uri(&uri))).await;
+        assert!(res.status().is_success());
+
+        let res = test::call_service(&app, get(&uri, &admin).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = test::call_service(&app, get(&with_deleted, &admin).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(res).await;
+        assert!(body["deleted_at"].is_string());
+        let res = test::call_service(&app, get(&with_deleted, &user).to_request()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let restore = TestRequest::post().uri(&format!("{uri}/restore"));
+        let res = test::call_service(&app, admin_call(restore)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = test::call_service(&app, get(&uri, &user).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    fn rename(uri: &str, token: &str, if_match: Option<&str>) -> TestRequest {
+        let req = TestRequest::patch()
+            .uri(uri)
+            .insert_header((header::AUTHORIZATION, format!("Bearer {token}")))
+            .set_json(serde_json::json!({ "name": "Pessoa Renomeada" }));
+        match if_match {
+            Some(tag) => req.insert_header((header::IF_MATCH, tag)),
+            None => req,
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn stale_writes_are_refused_with_412() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let person = testing::person(&state);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+        let uri = format!("/api/person/{}", person.id());
+        let rename_uri = format!("/api/person/name/{}", person.id());
+
+        let res = test::call_service(&app, get(&uri, &admin).to_request()).await;
+        let etag = res
+            .headers()
+            .get(header::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let res =
+            test::call_service(&app, rename(&rename_uri, &admin, Some(&etag)).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let new_etag = res
+            .headers()
+            .get(header::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_ne!(new_etag, etag);
+
+        // a second writer still holding the first tag
+        let res =
+            test::call_service(&app, rename(&rename_uri, &admin, Some(&etag)).to_request()).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        let res = test::call_service(
+            &app,
+            TestRequest::delete()
+                .uri(&uri)
+                .insert_header((header::AUTHORIZATION, format!("Bearer {admin}")))
+                .insert_header((header::IF_MATCH, etag))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn writes_without_if_match_are_428_when_required() {
+        let config = AppConfig::for_tests().with_require_if_match(true);
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let person = testing::person(&state);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+        let rename_uri = format!("/api/person/name/{}", person.id());
+
+        let res = test::call_service(&app, rename(&rename_uri, &admin, None).to_request()).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_REQUIRED);
+
+        let res =
+            test::call_service(&app, rename(&rename_uri, &admin, Some("*")).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
 
-    if !cpf_util::is_valid(&cpf) {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Invalid CPF".into(),
-        });
+    #[test]
+    fn page_tags_do_not_change_between_builds() {
+        let tag = super::person_page_tag("name=ana", 1, 20, 0, &[], false, true);
+        assert!(tag.weak);
+        assert_eq!(tag.tag(), "416f7c6eb53ab093f9b06fb5c4a5ce9a");
     }
 
-    let result = web::block(move || service.update_person(&pool, id, name, cpf)).await;
-
-    match result {
-        Ok(Ok(person)) => HttpResponse::Ok().json(PersonResponse {
-            id: *person.id(),
-            name: person.name().to_string(),
-            cpf: person.cpf().to_string(),
-            links: person_links(&req, id, Some(&claims)),
-        }),
-        Ok(Err(diesel::result::Error::NotFound)) => HttpResponse::NotFound().finish(),
-        _ => HttpResponse::InternalServerError().finish(),
+    #[test]
+    fn page_tags_differ_by_query_and_view() {
+        let tag = |filters, page, is_admin, mask_cpf| {
+            super::person_page_tag(filters, page, 20, 0, &[], is_admin, mask_cpf)
+        };
+        let base = tag("name=ana", 1, false, true);
+        assert_eq!(base, tag("name=ana", 1, false, true));
+        assert_ne!(base, tag("name=bia", 1, false, true));
+        assert_ne!(base, tag("name=ana", 2, false, true));
+        assert_ne!(base, tag("name=ana", 1, true, false));
+        assert_ne!(base, tag("name=ana", 1, false, false));
     }
 }
@@ -1,70 +1,61 @@
 use crate::{
-    dto::user_dto::{PageQuery, PaginatedResponse},
+    dto::user_dto::{PageQuery, PaginatedResponse, UserFilterQuery},
+    error::{app_error::AppError, problem::ProblemDetails},
+    i18n::Message,
     model::role::Role,
+    repository::{
+        query::parse_sort,
+        user_repository::{UserFilter, UserSortColumn},
+    },
 };
 use actix_web::{HttpResponse, Scope, delete, get, patch, put, web};
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 use crate::{
     auth::claims::Claims,
     auth::claims_extractor::{require_admin, require_self_or_admin},
     dto::user_dto::{
-        UpdateEmailRequest, UpdatePasswordRequest, UpdateRoleRequest, UpdateUserRequest,
-        UserResponse,
+        UpdateEmailRequest, UpdatePasswordRequest, UpdateRoleRequest, UpdateStatusRequest,
+        UpdateUserRequest, UserPatchDocument, UserResponse,
+    },
+    model::user::User,
+    service::user_service::UserPatch,
+    util::{
+        app_state::AppState,
+        audit_context::AuditContext,
+        blocking::block,
+        if_match::{IfMatch, etag},
+        patch_document::PatchDocument,
+        validated_json::ValidatedJson,
     },
-    error::user_service_error::UserServiceError,
-    util::app_state::AppState,
 };
 
-use crate::dto::hateoas::{Link, Links};
+use crate::dto::hateoas::{Link, Links, collection_page_links};
 use actix_web::HttpRequest;
+use serde_json::json;
+
+fn user_filter(query: &UserFilterQuery) -> Result<UserFilter, AppError> {
+    let sort = match query.sort.as_deref() {
+        Some(sort) => parse_sort::<UserSortColumn>(sort).map_err(|column| {
+            AppError::invalid_field("sort", Message::InvalidSortColumn(column))
+        })?,
+        None => Vec::new(),
+    };
 
-fn page_count(total: i64, size: i64) -> i64 {
-    if size <= 0 {
-        return 1;
-    }
-    let pages = (total + size - 1) / size;
-    pages.max(1)
-}
-
-fn collection_page_links(
-    req: &HttpRequest,
-    route_name: &str,
-    page: i64,
-    size: i64,
-    total: i64,
-) -> Links {
-    let mut links = Links::new();
-
-    let base = req
-        .url_for(route_name, std::iter::empty::<&str>())
-        .map(|u| u.to_string())
-        .unwrap_or_else(|_| "".to_string());
-
-    let last = page_count(total, size);
-
-    let self_href = format!("{base}?page={page}&size={size}");
-    let first_href = format!("{base}?page=1&size={size}");
-    let last_href = format!("{base}?page={last}&size={size}");
-
-    links.insert("self".into(), Link::get(self_href));
-    links.insert("first".into(), Link::get(first_href));
-    links.insert("last".into(), Link::get(last_href));
-
-    if page > 1 {
-        links.insert(
-            "prev".into(),
-            Link::get(format!("{base}?page={}&size={}", page - 1, size)),
-        );
-    }
-    if page < last {
-        links.insert(
-            "next".into(),
-            Link::get(format!("{base}?page={}&size={}", page + 1, size)),
-        );
-    }
-
-    links
+    Ok(UserFilter {
+        role: query.role,
+        email_contains: query
+            .email_contains
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string),
+        created_after: query.created_after,
+        created_before: query.created_before,
+        status: query.status,
+        sort,
+    })
 }
 
 fn user_links(req: &HttpRequest, id: Uuid, claims: &Claims) -> Links {
@@ -98,6 +89,12 @@ fn user_links(req: &HttpRequest, id: Uuid, claims: &Claims) -> Links {
         .unwrap_or_else(|_| format!("/user/{id}"));
     links.insert("update".into(), Link::put(put_href));
 
+    let patch_href = req
+        .url_for("user_patch", [id_s.as_str()])
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| format!("/user/{id}"));
+    links.insert("patch".into(), Link::patch(patch_href));
+
     let patch_email_href = req
         .url_for("user_patch_email", [id_s.as_str()])
         .map(|u| u.to_string())
@@ -116,288 +113,547 @@ fn user_links(req: &HttpRequest, id: Uuid, claims: &Claims) -> Links {
             .map(|u| u.to_string())
             .unwrap_or_else(|_| format!("/user/role/{id}"));
         links.insert("update_role".into(), Link::patch(patch_role_href));
+
+        let patch_status_href = req
+            .url_for("user_patch_status", [id_s.as_str()])
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| format!("/user/status/{id}"));
+        links.insert("update_status".into(), Link::patch(patch_status_href));
     }
 
     links
 }
 
+fn user_response(req: &HttpRequest, user: &User, claims: &Claims) -> UserResponse {
+    UserResponse {
+        id: *user.id(),
+        email: user.email().to_string(),
+        role: user.role(),
+        status: user.status(),
+        links: user_links(req, *user.id(), claims),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    find_all_users,
+    get_user_by_id,
+    delete_user,
+    update_user,
+    patch_user,
+    patch_user_email,
+    patch_user_password,
+    patch_user_role,
+    patch_user_status
+))]
+pub struct UserApi;
+
 pub fn routes() -> Scope {
     web::scope("/user")
         .service(find_all_users)
         .service(get_user_by_id)
         .service(delete_user)
         .service(update_user)
+        .service(patch_user)
         .service(patch_user_email)
         .service(patch_user_password)
         .service(patch_user_role)
+        .service(patch_user_status)
 }
 
 /// Lista todos os usuários - apenas admin
+#[utoipa::path(
+    params(PageQuery, UserFilterQuery),
+    responses(
+        (status = 200, description = "Page of users", body = PaginatedResponse<UserResponse>),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails)
+    )
+)]
 #[get("", name = "user_find_all")]
 async fn find_all_users(
     req: HttpRequest,
     state: web::Data<AppState>,
     claims: Claims,
     query: web::Query<PageQuery>,
-) -> HttpResponse {
-    if let Err(response) = require_admin(&claims) {
-        return response;
-    }
+    filter_query: web::Query<UserFilterQuery>,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
+
+    let filter = user_filter(&filter_query)?;
+    let filters = serde_urlencoded::to_string(&*filter_query).unwrap_or_default();
 
     let pool = state.pool().clone();
     let service = state.user_service().clone();
 
     let page = query.page.max(1);
-    let size = query.size.max(1).min(100);
-
-    let result = web::block(move || service.find_page(&pool, page, size)).await;
-
-    match result {
-        Ok(Ok((total, users))) => {
-            let items: Vec<UserResponse> = users
-                .into_iter()
-                .map(|user| {
-                    let id = *user.id();
-                    UserResponse {
-                        id,
-                        email: user.email().to_string(),
-                        role: user.role(),
-                        links: user_links(&req, id, &claims),
-                    }
-                })
-                .collect();
-
-            HttpResponse::Ok().json(PaginatedResponse {
-                page,
-                size,
-                total,
-                items,
-                links: collection_page_links(&req, "user_find_all", page, size, total),
-            })
-        }
-        _ => HttpResponse::InternalServerError().finish(),
-    }
+    let size = query.size.clamp(1, 100);
+
+    let (total, users) = block(move || service.find_page(&pool, &filter, page, size)).await??;
+
+    let items: Vec<UserResponse> = users
+        .iter()
+        .map(|user| user_response(&req, user, &claims))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(PaginatedResponse {
+        page,
+        size,
+        total,
+        items,
+        links: collection_page_links(&req, "user_find_all", &filters, page, size, total),
+    }))
 }
 
 /// Busca usuário por ID - próprio usuário ou admin
+#[utoipa::path(
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "User found", body = UserResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails)
+    )
+)]
 #[get("/{id}", name = "user_get_by_id")]
 async fn get_user_by_id(
     req: HttpRequest,
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
     claims: Claims,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
 
-    if let Err(response) = require_self_or_admin(&claims, &id) {
-        return response;
-    }
+    require_self_or_admin(&claims, &id)?;
 
     let pool = state.pool().clone();
     let service = state.user_service().clone();
 
-    let result = web::block(move || service.find_by_id(&pool, id)).await;
-
-    match result {
-        Ok(Ok(user)) => HttpResponse::Ok().json(UserResponse {
-            id,
-            email: user.email().to_string(),
-            role: user.role(),
-            links: user_links(&req, id, &claims),
-        }),
-        Ok(Err(diesel::result::Error::NotFound)) => HttpResponse::NotFound().finish(),
-        _ => HttpResponse::InternalServerError().finish(),
-    }
+    let user = block(move || service.find_by_id(&pool, id)).await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(user.version()))
+        .json(user_response(&req, &user, &claims)))
 }
 
 /// Deleta usuário - apenas admin e o próprio usuário
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    responses(
+        (status = 204, description = "User deleted"),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
 #[delete("/{id}", name = "user_delete")]
 async fn delete_user(
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
     claims: Claims,
-) -> HttpResponse {
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
 
-    if let Err(response) = require_self_or_admin(&claims, &id) {
-        return response;
-    }
+    require_self_or_admin(&claims, &id)?;
 
     let pool = state.pool().clone();
     let service = state.user_service().clone();
 
-    let result = web::block(move || service.delete_user(&pool, id)).await;
+    block(move || service.delete_user(&pool, id, &if_match, &ctx)).await??;
 
-    match result {
-        Ok(Ok(_user)) => HttpResponse::NoContent().finish(),
-        Ok(Err(diesel::result::Error::NotFound)) => HttpResponse::NotFound().finish(),
-        _ => HttpResponse::InternalServerError().finish(),
-    }
+    Ok(HttpResponse::NoContent().finish())
 }
 
 /// Atualiza usuário completo - próprio usuário pode atualizar (exceto role) ou admin pode atualizar tudo
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    request_body = UpdateUserRequest,
+    responses(
+        (status = 200, description = "User updated", body = UserResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
 #[put("/{id}", name = "user_update")]
 async fn update_user(
     req: HttpRequest,
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
-    body: web::Json<UpdateUserRequest>,
+    body: ValidatedJson<UpdateUserRequest>,
     claims: Claims,
-) -> HttpResponse {
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
 
-    if let Err(response) = require_self_or_admin(&claims, &id) {
-        return response;
-    }
+    require_self_or_admin(&claims, &id)?;
 
     let pool = state.pool().clone();
     let service = state.user_service().clone();
-    let email = body.email.clone();
+    let email = body.email.trim().to_string();
     let password = body.password.clone();
-
-    let desired_role: Role = match body.role.as_str().parse() {
-        Ok(r) => r,
-        Err(_) => {
-            return HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Invalid role (use 'admin' or 'user')"
-            }));
-        }
-    };
+    let desired_role: Role = body.role;
 
     let role: Role = if claims.is_admin() {
         desired_role
     } else {
-        let current = match web::block({
+        let current = block({
             let pool = pool.clone();
             let service = service.clone();
             move || service.find_by_id(&pool, id)
         })
-        .await
-        {
-            Ok(Ok(u)) => u,
-            Ok(Err(diesel::result::Error::NotFound)) => return HttpResponse::NotFound().finish(),
-            _ => return HttpResponse::InternalServerError().finish(),
-        };
+        .await??;
 
         if current.role() != desired_role {
-            return HttpResponse::Forbidden().json(serde_json::json!({
-                "error": "You cannot change your own role"
-            }));
+            return Err(AppError::Forbidden(Message::CannotChangeOwnRole));
         }
 
         current.role()
     };
 
-    let result = web::block(move || service.update_user(&pool, id, email, role, password)).await;
-
-    match result {
-        Ok(Ok(user)) => HttpResponse::Ok().json(UserResponse {
-            id: *user.id(),
-            email: user.email().to_string(),
-            role: user.role(),
-            links: user_links(&req, id, &claims),
-        }),
-        Ok(Err(diesel::result::Error::NotFound)) => HttpResponse::NotFound().finish(),
-        _ => HttpResponse::InternalServerError().finish(),
-    }
+    let user =
+        block(move || service.update_user(&pool, id, email, role, password, &if_match, &ctx))
+            .await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(user.version()))
+        .json(user_response(&req, &user, &claims)))
 }
 
 /// Atualiza email - próprio usuário ou admin
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    request_body = UpdateEmailRequest,
+    responses(
+        (status = 200, description = "User updated", body = UserResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
 #[patch("/email/{id}", name = "user_patch_email")]
 async fn patch_user_email(
     req: HttpRequest,
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
-    body: web::Json<UpdateEmailRequest>,
+    body: ValidatedJson<UpdateEmailRequest>,
     claims: Claims,
-) -> HttpResponse {
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
 
-    if let Err(response) = require_self_or_admin(&claims, &id) {
-        return response;
-    }
+    require_self_or_admin(&claims, &id)?;
 
     let pool = state.pool().clone();
     let service = state.user_service().clone();
-    let email = body.email.clone();
-
-    let result = web::block(move || service.update_email(&pool, id, email)).await;
-
-    match result {
-        Ok(Ok(user)) => HttpResponse::Ok().json(UserResponse {
-            id: *user.id(),
-            email: user.email().to_string(),
-            role: user.role(),
-            links: user_links(&req, id, &claims),
-        }),
-        Ok(Err(diesel::result::Error::NotFound)) => HttpResponse::NotFound().finish(),
-        _ => HttpResponse::InternalServerError().finish(),
-    }
+    let email = body.email.trim().to_string();
+
+    let user = block(move || service.update_email(&pool, id, email, &if_match, &ctx)).await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(user.version()))
+        .json(user_response(&req, &user, &claims)))
 }
 
 /// Atualiza role - apenas admin
-#[patch("/password/{id}", name = "user_patch_password")]
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    request_body = UpdateRoleRequest,
+    responses(
+        (status = 200, description = "User updated", body = UserResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
+#[patch("/role/{id}", name = "user_patch_role")]
 async fn patch_user_role(
     req: HttpRequest,
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
     body: web::Json<UpdateRoleRequest>,
     claims: Claims,
-) -> HttpResponse {
-    if let Err(response) = require_admin(&claims) {
-        return response;
-    }
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
 
     let pool = state.pool().clone();
     let service = state.user_service().clone();
     let id = path.into_inner();
-    let role = body.role.clone();
-
-    let result = web::block(move || service.update_role(&pool, id, role)).await;
-
-    match result {
-        Ok(Ok(user)) => HttpResponse::Ok().json(UserResponse {
-            id: *user.id(),
-            email: user.email().to_string(),
-            role: user.role(),
-            links: user_links(&req, id, &claims),
-        }),
-        Ok(Err(diesel::result::Error::NotFound)) => HttpResponse::NotFound().finish(),
-        _ => HttpResponse::InternalServerError().finish(),
-    }
+    let role = body.role;
+
+    let user = block(move || service.update_role(&pool, id, role, &if_match, &ctx)).await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(user.version()))
+        .json(user_response(&req, &user, &claims)))
+}
+
+/// Ativa ou desativa um usuário - apenas admin
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    request_body = UpdateStatusRequest,
+    responses(
+        (status = 200, description = "User updated", body = UserResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
+#[patch("/status/{id}", name = "user_patch_status")]
+async fn patch_user_status(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    body: web::Json<UpdateStatusRequest>,
+    claims: Claims,
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
+    require_admin(&claims)?;
+
+    let pool = state.pool().clone();
+    let service = state.user_service().clone();
+    let id = path.into_inner();
+    let status = body.status;
+
+    let user = block(move || service.update_status(&pool, id, status, &if_match, &ctx)).await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(user.version()))
+        .json(user_response(&req, &user, &claims)))
 }
 
 /// Atualiza senha - próprio usuário ou admin
-#[patch("/role/{id}", name = "user_patch_role")]
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    request_body = UpdatePasswordRequest,
+    responses(
+        (status = 200, description = "User updated", body = UserResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
+#[patch("/password/{id}", name = "user_patch_password")]
 async fn patch_user_password(
     req: HttpRequest,
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
-    body: web::Json<UpdatePasswordRequest>,
+    body: ValidatedJson<UpdatePasswordRequest>,
     claims: Claims,
-) -> HttpResponse {
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
 
-    if let Err(response) = require_self_or_admin(&claims, &id) {
-        return response;
-    }
+    require_self_or_admin(&claims, &id)?;
 
     let pool = state.pool().clone();
     let service = state.user_service().clone();
     let password = body.password.clone();
 
-    let result = web::block(move || service.update_password(&pool, id, password)).await;
-
-    match result {
-        Ok(Ok(user)) => HttpResponse::Ok().json(UserResponse {
-            id: *user.id(),
-            email: user.email().to_string(),
-            role: user.role(),
-            links: user_links(&req, id, &claims),
-        }),
-        Ok(Err(UserServiceError::NotFound)) => HttpResponse::NotFound().finish(),
-        Ok(Err(UserServiceError::HashError)) => HttpResponse::InternalServerError().finish(),
-        Ok(Err(_)) => HttpResponse::InternalServerError().finish(),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+    let user =
+        block(move || service.update_password(&pool, id, password, &if_match, &ctx)).await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(user.version()))
+        .json(user_response(&req, &user, &claims)))
+}
+
+/// Atualiza campos de um usuário via merge patch ou JSON patch - próprio usuário ou admin
+#[utoipa::path(
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed")
+    ),
+    request_body(
+        description = "Patch over `email`, `role` (admins only) and `password`, which reads as `null`",
+        content(
+            (Object = "application/merge-patch+json"),
+            (Vec<Object> = "application/json-patch+json")
+        )
+    ),
+    responses(
+        (status = 200, description = "User updated", body = UserResponse),
+        (status = 400, response = ProblemDetails),
+        (status = 401, response = ProblemDetails),
+        (status = 403, response = ProblemDetails),
+        (status = 404, response = ProblemDetails),
+        (status = 409, response = ProblemDetails),
+        (status = 412, response = ProblemDetails),
+        (status = 415, response = ProblemDetails),
+        (status = 428, response = ProblemDetails)
+    )
+)]
+#[patch("/{id}", name = "user_patch")]
+async fn patch_user(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    patch: PatchDocument,
+    claims: Claims,
+    if_match: IfMatch,
+    ctx: AuditContext,
+) -> Result<HttpResponse, AppError> {
+    let id = path.into_inner();
+
+    require_self_or_admin(&claims, &id)?;
+
+    let pool = state.pool().clone();
+    let service = state.user_service().clone();
+    let is_admin = claims.is_admin();
+
+    let user = block(move || {
+        service.patch_user(&pool, id, &if_match, &ctx, |user| {
+            let body: UserPatchDocument = patch.apply_to(json!({
+                "email": user.email(),
+                "role": user.role(),
+                "password": null,
+            }))?;
+
+            if body.role != user.role() && !is_admin {
+                return Err(AppError::Forbidden(Message::CannotChangeOwnRole));
+            }
+
+            Ok(UserPatch {
+                email: body.email.trim().to_string(),
+                role: body.role,
+                password: body.password,
+            })
+        })
+    })
+    .await??;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(etag(user.version()))
+        .json(user_response(&req, &user, &claims)))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App,
+        http::{StatusCode, header},
+        test::{self, TestRequest},
+    };
+    use serde_json::json;
+
+    use crate::{bootstrap::http_server::testing, config::AppConfig, model::role::Role};
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn disabled_users_cannot_log_in() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let (user_id, _) = testing::user(&state, Role::User);
+        let email = state
+            .user_service()
+            .find_by_id(&state.pool(), user_id)
+            .unwrap()
+            .email()
+            .to_string();
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        let login = || {
+            TestRequest::post()
+                .uri("/api/auth/login")
+                .set_json(json!({ "email": email, "password": "password123" }))
+                .to_request()
+        };
+        let set_status = |status: &str| {
+            TestRequest::patch()
+                .uri(&format!("/api/user/status/{user_id}"))
+                .insert_header((header::AUTHORIZATION, format!("Bearer {admin}")))
+                .set_json(json!({ "status": status }))
+                .to_request()
+        };
+
+        let res = test::call_service(&app, set_status("disabled")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = test::call_service(&app, login()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = test::call_service(&app, set_status("active")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = test::call_service(&app, login()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn tokens_stop_working_once_the_account_is_disabled() {
+        let config = AppConfig::for_tests();
+        let state = testing::state(&config);
+        let (_, admin) = testing::user(&state, Role::Admin);
+        let (user_id, token) = testing::user(&state, Role::User);
+        let app = test::init_service(App::new().configure(testing::api(config, state))).await;
+
+        let get_self = || {
+            TestRequest::get()
+                .uri(&format!("/api/user/{user_id}"))
+                .insert_header((header::AUTHORIZATION, format!("Bearer {token}")))
+                .to_request()
+        };
+
+        let res = test::call_service(&app, get_self()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let disable = TestRequest::patch()
+            .uri(&format!("/api/user/status/{user_id}"))
+            .insert_header((header::AUTHORIZATION, format!("Bearer {admin}")))
+            .set_json(json!({ "status": "disabled" }))
+            .to_request();
+        let res = test::call_service(&app, disable).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = test::call_service(&app, get_self()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
     }
 }
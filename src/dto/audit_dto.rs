@@ -0,0 +1,42 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::model::{audit_action::AuditAction, entity_type::EntityType};
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditEventResponse {
+    pub id: Uuid,
+    /// `null` for anonymous actions such as self-registration.
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub entity_type: EntityType,
+    pub entity_id: Uuid,
+    /// Changed fields as `{"field": {"before": .., "after": ..}}`.
+    #[schema(value_type = Object)]
+    pub changes: Value,
+    pub request_id: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Filters for `GET /audit`. Serialized back into the pagination links so
+/// that `next`/`prev` keep the same filters.
+#[derive(Default, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditFilterQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<AuditAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_type: Option<EntityType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_before: Option<NaiveDateTime>,
+}
@@ -1,13 +1,44 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
 
-#[derive(Deserialize)]
+use crate::util::validation::{FieldErrors, Validate};
+
+#[derive(Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
 }
 
-#[derive(Deserialize)]
+/// Only presence is checked so login does not reveal the password policy.
+impl Validate for LoginRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("email", &self.email);
+        errors.required("password", &self.password);
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct RegisterRequest {
     pub email: String,
     pub password: String,
 }
+
+impl Validate for RegisterRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.email("email", &self.email);
+        errors.password("password", &self.password);
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LoginResponse {
+    pub id: Uuid,
+    /// Bearer token, valid for 24 hours.
+    pub token: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RegisterResponse {
+    pub id: Uuid,
+}
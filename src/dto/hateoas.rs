@@ -1,7 +1,9 @@
+use actix_web::HttpRequest;
 use serde::Serialize;
 use std::collections::HashMap;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct Link {
     pub href: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -42,3 +44,59 @@ impl Link {
 }
 
 pub type Links = HashMap<String, Link>;
+
+fn page_count(total: i64, size: i64) -> i64 {
+    if size <= 0 {
+        return 1;
+    }
+    let pages = (total + size - 1) / size;
+    pages.max(1)
+}
+
+/// `filters` is an already encoded query string (without the leading `&`)
+/// appended to every link so paging keeps the current filters.
+pub fn collection_page_links(
+    req: &HttpRequest,
+    route_name: &str,
+    filters: &str,
+    page: i64,
+    size: i64,
+    total: i64,
+) -> Links {
+    let mut links = Links::new();
+
+    let base = req
+        .url_for(route_name, std::iter::empty::<&str>())
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| "".to_string());
+
+    let last = page_count(total, size);
+    let filters = if filters.is_empty() {
+        String::new()
+    } else {
+        format!("&{filters}")
+    };
+
+    let self_href = format!("{base}?page={page}&size={size}{filters}");
+    let first_href = format!("{base}?page=1&size={size}{filters}");
+    let last_href = format!("{base}?page={last}&size={size}{filters}");
+
+    links.insert("self".into(), Link::get(self_href));
+    links.insert("first".into(), Link::get(first_href));
+    links.insert("last".into(), Link::get(last_href));
+
+    if page > 1 {
+        links.insert(
+            "prev".into(),
+            Link::get(format!("{base}?page={}&size={}{filters}", page - 1, size)),
+        );
+    }
+    if page < last {
+        links.insert(
+            "next".into(),
+            Link::get(format!("{base}?page={}&size={}{filters}", page + 1, size)),
+        );
+    }
+
+    links
+}
@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::dto::hateoas::Links;
+use crate::model::{job_kind::JobKind, job_status::JobStatus};
+
+#[derive(Serialize, ToSchema)]
+pub struct JobResponse {
+    pub id: Uuid,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    /// Attempts started so far, including a running one.
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// When the job is due; for a queued retry, when it will be retried.
+    pub run_at: NaiveDateTime,
+    /// Error of the latest failed attempt.
+    pub last_error: Option<String>,
+    /// Set once the job succeeded.
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<Value>,
+    /// `null` for jobs started by the system.
+    pub created_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// When the job succeeded or was given up on.
+    pub finished_at: Option<NaiveDateTime>,
+
+    #[serde(rename = "_links")]
+    #[schema(inline)]
+    pub links: Links,
+}
@@ -1,4 +1,7 @@
+pub mod audit_dto;
 pub mod auth_dto;
 pub mod hateoas;
+pub mod health_dto;
+pub mod job_dto;
 pub mod person_dto;
 pub mod user_dto;
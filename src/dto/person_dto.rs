@@ -1,40 +1,111 @@
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::dto::hateoas::Links;
+use crate::error::{app_error::AppError, problem::FieldErrorBody};
+use crate::i18n::Message;
+use crate::model::{cpf::Cpf, import_mode::ImportMode, person::Person};
+use crate::repository::{
+    person_repository::{PersonFilter, PersonSortColumn},
+    query::parse_sort,
+};
+use crate::util::export_writer::ExportFormat;
+use crate::util::validation::{FieldErrors, Validate};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct PersonRequest {
     pub name: String,
     pub cpf: String,
+    pub birth_date: Option<NaiveDate>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
 }
 
-#[derive(Serialize)]
+impl Validate for PersonRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+        errors.cpf("cpf", &self.cpf);
+        errors.birth_date("birth_date", self.birth_date);
+        errors.optional_email("email", self.email.as_deref());
+        errors.optional_phone("phone", self.phone.as_deref());
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct PersonResponse {
     pub id: Uuid,
     pub name: String,
+    /// Formatted as `000.000.000-00`, or masked for non-admins when configured.
     pub cpf: String,
+    pub birth_date: Option<NaiveDate>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub created_by: Option<Uuid>,
+    pub updated_by: Option<Uuid>,
+    /// Only present for soft-deleted people.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<Uuid>,
 
     #[serde(rename = "_links")]
+    #[schema(inline)]
     pub links: Links,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, ToSchema)]
+pub struct PersonSearchItem {
+    #[serde(flatten)]
+    pub person: PersonResponse,
+    /// Trigram word similarity between the query and the name, from 0 to 1.
+    pub score: f32,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdatePersonRequest {
     pub name: String,
     pub cpf: String,
+    pub birth_date: Option<NaiveDate>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
 }
 
-#[derive(Deserialize)]
+impl Validate for UpdatePersonRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+        errors.cpf("cpf", &self.cpf);
+        errors.birth_date("birth_date", self.birth_date);
+        errors.optional_email("email", self.email.as_deref());
+        errors.optional_phone("phone", self.phone.as_deref());
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateNameRequest {
     pub name: String,
 }
 
-#[derive(Deserialize)]
+impl Validate for UpdateNameRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateCpfRequest {
     pub cpf: String,
 }
 
+impl Validate for UpdateCpfRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.cpf("cpf", &self.cpf);
+    }
+}
+
 fn default_page() -> i64 {
     1
 }
@@ -42,7 +113,8 @@ fn default_size() -> i64 {
     20
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PaginationQuery {
     #[serde(default = "default_page")]
     pub page: i64,
@@ -50,7 +122,189 @@ pub struct PaginationQuery {
     pub size: i64,
 }
 
+/// Filters for `GET /person`. Serialized back into the pagination links so
+/// that `next`/`prev` keep the same filters.
+#[derive(Default, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PersonFilterQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Exact CPF, digits optionally punctuated with `.`, `-` or spaces.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpf: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Also list soft-deleted people. Admins only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_deleted: Option<bool>,
+}
+
+impl PersonFilterQuery {
+    pub fn to_filter(&self) -> Result<PersonFilter, AppError> {
+        let sort = match self.sort.as_deref() {
+            Some(sort) => parse_sort::<PersonSortColumn>(sort).map_err(|column| {
+                AppError::invalid_field("sort", Message::InvalidSortColumn(column))
+            })?,
+            None => Vec::new(),
+        };
+
+        let cpf = match optional_text(&self.cpf) {
+            Some(raw) => Some(Cpf::parse(&raw)?),
+            None => None,
+        };
+
+        Ok(PersonFilter {
+            name: optional_text(&self.name),
+            cpf,
+            created_after: self.created_after,
+            include_deleted: self.include_deleted.unwrap_or(false),
+            sort,
+        })
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AsOfQuery {
+    /// Returns the person as it was at this moment instead of now.
+    pub as_of: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct IncludeDeletedQuery {
+    /// Also find soft-deleted people. Admins only.
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    #[serde(default)]
+    #[param(inline)]
+    pub format: ExportFormat,
+}
+
+/// One person in an export, flat so it fits a CSV or spreadsheet row.
 #[derive(Serialize)]
+pub struct PersonExportRow {
+    pub id: Uuid,
+    pub name: String,
+    /// Masked for non-admins when configured, as in the API.
+    pub cpf: String,
+    pub birth_date: Option<NaiveDate>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Only set when soft-deleted people were included.
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+impl PersonExportRow {
+    pub fn new(person: &Person, mask_cpf: bool) -> Self {
+        Self {
+            id: *person.id(),
+            name: person.name().to_string(),
+            cpf: person.cpf().for_display(mask_cpf),
+            birth_date: person.birth_date(),
+            email: person.email().map(str::to_string),
+            phone: person.phone().map(str::to_string),
+            created_at: *person.created_at(),
+            updated_at: *person.updated_at(),
+            deleted_at: person.deleted_at().copied(),
+        }
+    }
+
+    /// Field names, in order; the header of every export.
+    pub const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "cpf",
+        "birth_date",
+        "email",
+        "phone",
+        "created_at",
+        "updated_at",
+        "deleted_at",
+    ];
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    #[serde(default)]
+    #[param(inline)]
+    pub mode: ImportMode,
+}
+
+/// `multipart/form-data` body of `POST /person/import`.
+#[derive(ToSchema)]
+pub struct PersonImportForm {
+    /// CSV with a header row naming the columns `name`, `cpf` and optionally
+    /// `birth_date` (`YYYY-MM-DD`), `email` and `phone`; at most 10 MiB.
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportRowErrors {
+    /// Line in the file, counting the header as line 1.
+    pub line: u64,
+    pub errors: Vec<FieldErrorBody>,
+}
+
+/// Result of a `person_import` job.
+#[derive(Serialize, ToSchema)]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    /// Data rows in the file.
+    pub total: usize,
+    /// Rows that passed validation and the duplicate checks.
+    pub valid: usize,
+    /// Zero for `dry_run`, and for `all_or_nothing` when any row failed.
+    pub imported: usize,
+    pub failed: usize,
+    /// Ordered by line.
+    pub errors: Vec<ImportRowErrors>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PersonVersionResponse {
+    pub version: i32,
+    pub name: String,
+    pub cpf: String,
+    pub birth_date: Option<NaiveDate>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    /// Who made the change that produced this version.
+    pub changed_by: Option<Uuid>,
+    pub valid_from: NaiveDateTime,
+    /// `null` while this is the current version.
+    pub valid_to: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PersonHistoryResponse {
+    pub person_id: Uuid,
+    /// Newest first.
+    pub versions: Vec<PersonVersionResponse>,
+
+    #[serde(rename = "_links")]
+    #[schema(inline)]
+    pub links: Links,
+}
+
+#[derive(Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PersonSearchQuery {
+    pub q: String,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct PaginatedResponse<T> {
     pub page: i64,
     pub size: i64,
@@ -58,5 +312,145 @@ pub struct PaginatedResponse<T> {
     pub items: Vec<T>,
 
     #[serde(rename = "_links")]
+    #[schema(inline)]
     pub links: Links,
 }
+
+/// Trims optional text fields, treating blank values as absent.
+pub fn optional_text(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, NaiveDate, Utc};
+
+    use super::*;
+    use crate::repository::query::SortDirection;
+
+    fn person(birth_date: Option<NaiveDate>, email: &str, phone: &str) -> PersonRequest {
+        PersonRequest {
+            name: "Ana Souza".into(),
+            cpf: "529.982.247-25".into(),
+            birth_date,
+            email: Some(email.into()),
+            phone: Some(phone.into()),
+        }
+    }
+
+    fn invalid_fields(request: &PersonRequest) -> Vec<(String, Message)> {
+        let mut errors = FieldErrors::default();
+        request.validate(&mut errors);
+        errors
+            .into_vec()
+            .into_iter()
+            .map(|e| (e.field, e.message))
+            .collect()
+    }
+
+    #[test]
+    fn contact_and_birth_date_are_optional() {
+        let mut request = person(None, "", "  ");
+        assert!(invalid_fields(&request).is_empty());
+
+        request.email = None;
+        request.phone = None;
+        assert!(invalid_fields(&request).is_empty());
+    }
+
+    #[test]
+    fn accepts_a_complete_record() {
+        let born = NaiveDate::from_ymd_opt(1990, 5, 17);
+        let request = person(born, "ana@example.com", "+55 (11) 98765-4321");
+        assert!(invalid_fields(&request).is_empty());
+    }
+
+    #[test]
+    fn reports_each_invalid_detail_against_its_field() {
+        let tomorrow = Utc::now().date_naive().checked_add_days(Days::new(1));
+        let request = person(tomorrow, "ana@", "12345");
+        assert_eq!(
+            invalid_fields(&request),
+            [
+                ("birth_date".to_string(), Message::InvalidBirthDate),
+                ("email".to_string(), Message::InvalidEmail),
+                ("phone".to_string(), Message::InvalidPhone),
+            ]
+        );
+    }
+
+    #[test]
+    fn birth_dates_before_1900_are_rejected() {
+        let request = person(NaiveDate::from_ymd_opt(1899, 12, 31), "", "");
+        assert_eq!(
+            invalid_fields(&request),
+            [("birth_date".to_string(), Message::InvalidBirthDate)]
+        );
+    }
+
+    fn filter(query: &str) -> Result<PersonFilter, AppError> {
+        serde_urlencoded::from_str::<PersonFilterQuery>(query)
+            .unwrap()
+            .to_filter()
+    }
+
+    fn invalid_field(error: AppError) -> (String, Message) {
+        match error {
+            AppError::Validation { mut errors, .. } if errors.len() == 1 => {
+                let error = errors.remove(0);
+                (error.field, error.message)
+            }
+            other => panic!("expected one field error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn filters_default_to_everything_not_deleted() {
+        let filter = filter("name=%20%20&cpf=").unwrap();
+        assert_eq!(filter.name, None);
+        assert!(filter.cpf.is_none());
+        assert!(!filter.include_deleted);
+        assert!(filter.sort.is_empty());
+    }
+
+    #[test]
+    fn filters_parse_the_cpf_and_sort() {
+        let filter =
+            filter("name=%20ana%20&cpf=529.982.247-25&sort=-birth_date,name&include_deleted=true")
+                .unwrap();
+        assert_eq!(filter.name.as_deref(), Some("ana"));
+        assert_eq!(filter.cpf.unwrap().as_str(), "52998224725");
+        assert!(filter.include_deleted);
+        let sort: Vec<_> = filter
+            .sort
+            .iter()
+            .map(|o| (o.column, o.direction))
+            .collect();
+        assert_eq!(
+            sort,
+            [
+                (PersonSortColumn::BirthDate, SortDirection::Desc),
+                (PersonSortColumn::Name, SortDirection::Asc),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_sort_columns_and_bad_cpfs_are_field_errors() {
+        assert_eq!(
+            invalid_field(filter("sort=name,-password").unwrap_err()),
+            (
+                "sort".to_string(),
+                Message::InvalidSortColumn("password".into())
+            )
+        );
+        assert_eq!(
+            invalid_field(filter("cpf=529.982.247-26").unwrap_err()),
+            ("cpf".to_string(), Message::InvalidCpf)
+        );
+    }
+}
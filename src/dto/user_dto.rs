@@ -1,47 +1,102 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::dto::hateoas::Links;
 use crate::model::role::Role;
+use crate::model::user_status::UserStatus;
+use crate::util::validation::{FieldErrors, Validate};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UserRequest {
     pub email: String,
     pub password: String,
 }
 
-#[derive(Serialize)]
+impl Validate for UserRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.email("email", &self.email);
+        errors.password("password", &self.password);
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct UserResponse {
     pub id: Uuid,
     pub email: String,
     pub role: Role,
+    pub status: UserStatus,
 
     #[serde(rename = "_links")]
+    #[schema(inline)]
     pub links: Links,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateUserRequest {
     pub email: String,
     pub password: String,
     pub role: Role,
 }
 
+impl Validate for UpdateUserRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.email("email", &self.email);
+        errors.password("password", &self.password);
+    }
+}
+
+/// A user as seen by `PATCH /user/{id}`. `password` reads as `null`; setting
+/// it changes the password.
 #[derive(Deserialize)]
+pub struct UserPatchDocument {
+    pub email: String,
+    pub role: Role,
+    pub password: Option<String>,
+}
+
+impl Validate for UserPatchDocument {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.email("email", &self.email);
+        if let Some(password) = &self.password {
+            errors.password("password", password);
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateEmailRequest {
     pub email: String,
 }
 
-#[derive(Deserialize)]
+impl Validate for UpdateEmailRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.email("email", &self.email);
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateRoleRequest {
     pub role: Role,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateStatusRequest {
+    pub status: UserStatus,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdatePasswordRequest {
     pub password: String,
 }
 
+impl Validate for UpdatePasswordRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.password("password", &self.password);
+    }
+}
+
 fn default_page() -> i64 {
     1
 }
@@ -49,7 +104,8 @@ fn default_size() -> i64 {
     20
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PageQuery {
     #[serde(default = "default_page")]
     pub page: i64,
@@ -57,7 +113,26 @@ pub struct PageQuery {
     pub size: i64,
 }
 
-#[derive(Serialize)]
+/// Filters for `GET /user`. Serialized back into the pagination links so
+/// that `next`/`prev` keep the same filters.
+#[derive(Default, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserFilterQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<Role>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_contains: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_before: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<UserStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct PaginatedResponse<T> {
     pub page: i64,
     pub size: i64,
@@ -65,5 +140,6 @@ pub struct PaginatedResponse<T> {
     pub items: Vec<T>,
 
     #[serde(rename = "_links")]
+    #[schema(inline)]
     pub links: Links,
 }
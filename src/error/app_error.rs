@@ -0,0 +1,320 @@
+use actix_web::{HttpResponse, ResponseError, error::BlockingError, http::StatusCode};
+use diesel::r2d2::PoolError;
+use diesel::result::Error as DieselError;
+use std::fmt::{Display, Formatter};
+
+use crate::error::{
+    conflict_error::ConflictError,
+    problem::{FieldError, ProblemDetails},
+};
+use crate::i18n::{Locale, Message};
+use crate::model::cpf::InvalidCpf;
+
+const VALIDATION_PROBLEM: &str = "/problems/validation-error";
+const CONFLICT_PROBLEM: &str = "/problems/conflict";
+
+/// Error type shared by services and handlers.
+///
+/// Handlers return `Result<HttpResponse, AppError>` and let `ResponseError`
+/// render it as an `application/problem+json` body. Messages are catalog
+/// entries, localized only when the response is rendered.
+#[derive(Debug)]
+pub enum AppError {
+    /// Invalid input; `errors` lists the offending fields when known.
+    Validation {
+        detail: Message,
+        errors: Vec<FieldError>,
+    },
+    NotFound(Message),
+    Conflict(ConflictError),
+    Unauthorized(Message),
+    Forbidden(Message),
+    /// `If-Match` did not match the current version of the resource.
+    PreconditionFailed,
+    /// `If-Match` is mandatory but was not sent.
+    PreconditionRequired,
+    PayloadTooLarge(Message),
+    UnsupportedMediaType(Message),
+    /// No database connection became available before the pool timeout.
+    PoolExhausted,
+    /// Unexpected failure; the detail is logged but never sent to clients.
+    Internal(String),
+}
+
+impl AppError {
+    pub fn validation(message: Message) -> Self {
+        AppError::Validation {
+            detail: message,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Validation failure pinned to a single request field.
+    pub fn invalid_field(field: impl Into<String>, message: Message) -> Self {
+        FieldError::new(field, message).into()
+    }
+
+    /// 413 naming the largest body accepted, in bytes.
+    pub fn payload_too_large(max: usize) -> Self {
+        AppError::PayloadTooLarge(Message::PayloadTooLarge { max })
+    }
+
+    /// 415 naming the media types the endpoint accepts.
+    pub fn unsupported_media_type(expected: &[&str]) -> Self {
+        AppError::UnsupportedMediaType(Message::UnsupportedMediaType {
+            expected: expected.join(", "),
+        })
+    }
+
+    pub fn internal(detail: impl Into<String>) -> Self {
+        AppError::Internal(detail.into())
+    }
+
+    /// Problem details for this error, without `instance` or request id;
+    /// those are filled in by `ProblemMiddleware`.
+    pub fn problem(&self, locale: Locale) -> ProblemDetails {
+        let status = self.status_code();
+        match self {
+            AppError::Validation { detail, errors } => ProblemDetails::new(
+                VALIDATION_PROBLEM,
+                Message::ValidationFailed.text(locale),
+                status,
+                locale,
+            )
+            .with_message(detail)
+            .with_errors(errors),
+            AppError::Conflict(conflict) => {
+                let errors: Vec<FieldError> = conflict
+                    .field
+                    .map(|field| FieldError::new(field, conflict.message.clone()))
+                    .into_iter()
+                    .collect();
+                ProblemDetails::new(
+                    CONFLICT_PROBLEM,
+                    Message::Conflict.text(locale),
+                    status,
+                    locale,
+                )
+                .with_message(&conflict.message)
+                .with_errors(&errors)
+            }
+            AppError::NotFound(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::PayloadTooLarge(message)
+            | AppError::UnsupportedMediaType(message) => {
+                ProblemDetails::for_status(status, locale).with_message(message)
+            }
+            AppError::PreconditionFailed
+            | AppError::PreconditionRequired
+            | AppError::PoolExhausted => {
+                ProblemDetails::for_status(status, locale).with_message(self.message())
+            }
+            AppError::Internal(_) => {
+                ProblemDetails::for_status(status, locale).with_message(&Message::InternalError)
+            }
+        }
+    }
+
+    /// Replaces the generic "not found" message coming from Diesel.
+    pub fn or_not_found(self, message: Message) -> Self {
+        match self {
+            AppError::NotFound(_) => AppError::NotFound(message),
+            other => other,
+        }
+    }
+
+    fn message(&self) -> &Message {
+        match self {
+            AppError::Validation {
+                detail: message, ..
+            }
+            | AppError::NotFound(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::PayloadTooLarge(message)
+            | AppError::UnsupportedMediaType(message) => message,
+            AppError::Conflict(conflict) => &conflict.message,
+            AppError::PreconditionFailed => &Message::VersionMismatch,
+            AppError::PreconditionRequired => &Message::IfMatchRequired,
+            AppError::PoolExhausted => &Message::ServiceUnavailable,
+            AppError::Internal(_) => &Message::InternalError,
+        }
+    }
+}
+
+/// English text, for logs.
+impl Display for AppError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message().text(Locale::En))
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Validation { .. } => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            AppError::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::PoolExhausted => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if let AppError::Internal(detail) = self {
+            tracing::error!(%detail, "internal error");
+        }
+
+        self.problem(Locale::default()).to_response()
+    }
+}
+
+impl From<FieldError> for AppError {
+    fn from(err: FieldError) -> Self {
+        AppError::Validation {
+            detail: err.message.clone(),
+            errors: vec![err],
+        }
+    }
+}
+
+impl From<InvalidCpf> for AppError {
+    fn from(_: InvalidCpf) -> Self {
+        AppError::invalid_field("cpf", Message::InvalidCpf)
+    }
+}
+
+impl From<DieselError> for AppError {
+    fn from(err: DieselError) -> Self {
+        if let Some(conflict) = ConflictError::from_diesel(&err) {
+            return AppError::Conflict(conflict);
+        }
+        match err {
+            DieselError::NotFound => AppError::NotFound(Message::ResourceNotFound),
+            _ => AppError::Internal(format!("database error: {}", err)),
+        }
+    }
+}
+
+impl From<PoolError> for AppError {
+    fn from(err: PoolError) -> Self {
+        tracing::warn!(error = %err, "database pool exhausted");
+        AppError::PoolExhausted
+    }
+}
+
+impl From<bcrypt::BcryptError> for AppError {
+    fn from(err: bcrypt::BcryptError) -> Self {
+        AppError::Internal(format!("password hashing error: {}", err))
+    }
+}
+
+impl From<BlockingError> for AppError {
+    fn from(err: BlockingError) -> Self {
+        AppError::Internal(format!("blocking task error: {}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::MessageBody;
+
+    use super::*;
+
+    #[test]
+    fn each_variant_has_its_status() {
+        let cases = [
+            (
+                AppError::validation(Message::Blank),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                AppError::NotFound(Message::PersonNotFound),
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                AppError::Unauthorized(Message::InvalidToken),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                AppError::Forbidden(Message::AdminRequired),
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                AppError::PreconditionFailed,
+                StatusCode::PRECONDITION_FAILED,
+            ),
+            (
+                AppError::PreconditionRequired,
+                StatusCode::PRECONDITION_REQUIRED,
+            ),
+            (
+                AppError::payload_too_large(1),
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+            (
+                AppError::unsupported_media_type(&["text/csv"]),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ),
+            (AppError::PoolExhausted, StatusCode::SERVICE_UNAVAILABLE),
+            (
+                AppError::internal("boom"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        ];
+        for (error, status) in cases {
+            assert_eq!(error.status_code(), status, "{error:?}");
+            assert_eq!(error.problem(Locale::En).status, status.as_u16());
+        }
+    }
+
+    #[test]
+    fn internal_details_are_never_sent() {
+        let error = AppError::internal("database error: password authentication failed");
+        let body = error.error_response().into_body().try_into_bytes().unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(!body.contains("password"), "{body}");
+        assert!(body.contains("server.internal_error"), "{body}");
+    }
+
+    #[test]
+    fn diesel_not_found_is_a_404_that_callers_can_name() {
+        let error = AppError::from(DieselError::NotFound);
+        assert!(matches!(
+            error,
+            AppError::NotFound(Message::ResourceNotFound)
+        ));
+
+        let named = error.or_not_found(Message::UserNotFound);
+        assert!(matches!(named, AppError::NotFound(Message::UserNotFound)));
+
+        let untouched = AppError::PoolExhausted.or_not_found(Message::UserNotFound);
+        assert!(matches!(untouched, AppError::PoolExhausted));
+    }
+
+    #[test]
+    fn other_diesel_errors_are_internal() {
+        let error = AppError::from(DieselError::RollbackTransaction);
+        assert!(matches!(error, AppError::Internal(_)));
+    }
+
+    #[test]
+    fn a_field_error_becomes_a_validation_error_about_that_field() {
+        let error = AppError::invalid_field("email", Message::InvalidEmail);
+        let problem = error.problem(Locale::En);
+
+        assert_eq!(problem.code, Some("validation.invalid_email"));
+        assert_eq!(problem.errors.len(), 1);
+        assert_eq!(problem.errors[0].field, "email");
+    }
+}
@@ -0,0 +1,134 @@
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+use crate::i18n::Message;
+
+/// A `409 Conflict` caused by a unique constraint violation.
+#[derive(Debug, Clone)]
+pub struct ConflictError {
+    pub message: Message,
+    pub field: Option<&'static str>,
+}
+
+impl ConflictError {
+    /// Maps a unique constraint name to the request field it protects.
+    pub fn for_constraint(constraint: Option<&str>) -> Self {
+        match constraint {
+            Some("persons_cpf_unique") => Self {
+                message: Message::CpfTaken,
+                field: Some("cpf"),
+            },
+            Some("users_email_key") => Self {
+                message: Message::EmailTaken,
+                field: Some("email"),
+            },
+            _ => Self {
+                message: Message::ResourceExists,
+                field: None,
+            },
+        }
+    }
+
+    /// Returns the conflict for `UniqueViolation` errors, `None` for anything else.
+    pub fn from_diesel(err: &DieselError) -> Option<Self> {
+        match err {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => {
+                Some(Self::for_constraint(info.constraint_name()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::result::DatabaseErrorInformation;
+
+    use super::*;
+    use crate::{error::app_error::AppError, i18n::Locale};
+
+    struct Violation(Option<&'static str>);
+
+    impl DatabaseErrorInformation for Violation {
+        fn message(&self) -> &str {
+            "duplicate key value violates unique constraint"
+        }
+        fn details(&self) -> Option<&str> {
+            None
+        }
+        fn hint(&self) -> Option<&str> {
+            None
+        }
+        fn table_name(&self) -> Option<&str> {
+            None
+        }
+        fn column_name(&self) -> Option<&str> {
+            None
+        }
+        fn constraint_name(&self) -> Option<&str> {
+            self.0
+        }
+        fn statement_position(&self) -> Option<i32> {
+            None
+        }
+    }
+
+    fn database_error(kind: DatabaseErrorKind, constraint: Option<&'static str>) -> DieselError {
+        DieselError::DatabaseError(kind, Box::new(Violation(constraint)))
+    }
+
+    #[test]
+    fn known_constraints_name_their_field() {
+        let cpf = ConflictError::for_constraint(Some("persons_cpf_unique"));
+        assert_eq!((cpf.field, cpf.message), (Some("cpf"), Message::CpfTaken));
+
+        let email = ConflictError::for_constraint(Some("users_email_key"));
+        assert_eq!(
+            (email.field, email.message),
+            (Some("email"), Message::EmailTaken)
+        );
+    }
+
+    #[test]
+    fn other_constraints_are_a_generic_conflict() {
+        for constraint in [Some("some_other_key"), None] {
+            let conflict = ConflictError::for_constraint(constraint);
+            assert_eq!(conflict.field, None);
+            assert_eq!(conflict.message, Message::ResourceExists);
+        }
+    }
+
+    #[test]
+    fn only_unique_violations_are_conflicts() {
+        let unique = database_error(
+            DatabaseErrorKind::UniqueViolation,
+            Some("persons_cpf_unique"),
+        );
+        assert_eq!(
+            ConflictError::from_diesel(&unique).map(|c| c.field),
+            Some(Some("cpf"))
+        );
+
+        let foreign_key = database_error(
+            DatabaseErrorKind::ForeignKeyViolation,
+            Some("persons_cpf_unique"),
+        );
+        assert!(ConflictError::from_diesel(&foreign_key).is_none());
+        assert!(ConflictError::from_diesel(&DieselError::NotFound).is_none());
+    }
+
+    #[test]
+    fn conflicts_render_as_409_with_the_field() {
+        let error = AppError::from(database_error(
+            DatabaseErrorKind::UniqueViolation,
+            Some("users_email_key"),
+        ));
+        let problem = error.problem(Locale::En);
+
+        assert_eq!(problem.status, 409);
+        assert_eq!(problem.problem_type, "/problems/conflict");
+        assert_eq!(problem.code, Some("user.email_taken"));
+        assert_eq!(problem.errors.len(), 1);
+        assert_eq!(problem.errors[0].field, "email");
+        assert_eq!(problem.errors[0].code, "user.email_taken");
+    }
+}
@@ -1,6 +0,0 @@
-use serde::Serialize;
-
-#[derive(Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-}
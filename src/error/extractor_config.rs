@@ -0,0 +1,66 @@
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    web,
+};
+
+use crate::{error::app_error::AppError, i18n::Message};
+
+/// Extractor configs that turn deserialization failures into validation
+/// problems instead of actix's plain-text bodies.
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|err, _req| match err {
+        JsonPayloadError::Deserialize(e) => body_error(&e).into(),
+        JsonPayloadError::Overflow { limit }
+        | JsonPayloadError::OverflowKnownLength { limit, .. } => {
+            AppError::payload_too_large(limit).into()
+        }
+        JsonPayloadError::ContentType => {
+            AppError::unsupported_media_type(&["application/json"]).into()
+        }
+        other => other.into(),
+    })
+}
+
+/// Validation problem for a JSON body that does not deserialize.
+pub(crate) fn body_error(err: &serde_json::Error) -> AppError {
+    let message = err.to_string();
+    field_error(&message)
+        .unwrap_or_else(|| AppError::invalid_field("body", Message::MalformedBody(message)))
+}
+
+pub fn path_config() -> web::PathConfig {
+    web::PathConfig::default().error_handler(|err, _req| match err {
+        PathError::Deserialize(e) => {
+            AppError::invalid_field("path", Message::InvalidPathParam(e.to_string())).into()
+        }
+        other => other.into(),
+    })
+}
+
+pub fn query_config() -> web::QueryConfig {
+    web::QueryConfig::default().error_handler(|err, _req| match err {
+        QueryPayloadError::Deserialize(e) => {
+            let message = e.to_string();
+            field_error(&message)
+                .unwrap_or_else(|| {
+                    AppError::invalid_field("query", Message::InvalidQueryParam(message.clone()))
+                })
+                .into()
+        }
+        other => other.into(),
+    })
+}
+
+/// Recognizes serde's "missing field `x`" and "unknown field `x`" messages,
+/// which name the offending field.
+fn field_error(message: &str) -> Option<AppError> {
+    let (rest, kind) = match message.strip_prefix("missing field `") {
+        Some(rest) => (rest, Message::Required),
+        None => (
+            message.strip_prefix("unknown field `")?,
+            Message::UnknownField,
+        ),
+    };
+    let field = rest.split('`').next()?;
+    Some(AppError::invalid_field(field, kind))
+}
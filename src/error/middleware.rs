@@ -0,0 +1,212 @@
+use actix_web::{
+    Error, HttpMessage,
+    body::{BodySize, BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    http::header::{self, HeaderValue},
+};
+use futures_util::future::{LocalBoxFuture, Ready, ok};
+
+use crate::{
+    error::{
+        app_error::AppError,
+        problem::{PROBLEM_JSON, ProblemDetails},
+    },
+    i18n::Locale,
+    util::request_id::RequestId,
+};
+
+/// Renders every error response as `application/problem+json`.
+///
+/// Errors raised by handlers and extractors reach this middleware attached to
+/// the response, so `instance` and the request id are filled in one place. Error
+/// statuses produced with neither an error value nor a body (e.g. unmatched
+/// routes) get an `about:blank` problem as well.
+pub struct ProblemMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for ProblemMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ProblemMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(ProblemMiddlewareService { service })
+    }
+}
+
+pub struct ProblemMiddlewareService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for ProblemMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let existing = req.extensions().get::<RequestId>().cloned();
+        let request_id = existing.unwrap_or_else(|| {
+            let id = RequestId::generate();
+            req.extensions_mut().insert(id.clone());
+            id
+        });
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?.map_into_boxed_body();
+            Ok(render_problem(res, &request_id))
+        })
+    }
+}
+
+fn render_problem(res: ServiceResponse, request_id: &RequestId) -> ServiceResponse {
+    let status = res.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return res;
+    }
+
+    let locale = Locale::from_request(res.request());
+    let mut problem = match res.response().error() {
+        Some(err) => match err.as_error::<AppError>() {
+            Some(app_error) => app_error.problem(locale),
+            // built-in actix errors carry English messages, so only the
+            // localized title is shown
+            None => {
+                tracing::debug!(error = %err, "request failed in actix");
+                ProblemDetails::for_status(status, locale)
+            }
+        },
+        // handlers that deliberately answer with an error status and a body
+        // (e.g. a failing readiness probe) are left alone
+        None if has_body(&res) => return res,
+        None => ProblemDetails::for_status(status, locale),
+    };
+
+    problem.instance = Some(res.request().path().to_string());
+    problem.request_id = Some(request_id.to_string());
+
+    let body = match serde_json::to_string(&problem) {
+        Ok(body) => body,
+        Err(_) => return res,
+    };
+
+    res.map_body(|head, _| {
+        head.headers
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        head.headers.insert(
+            header::CONTENT_LANGUAGE,
+            HeaderValue::from_static(locale.tag()),
+        );
+        head.headers
+            .append(header::VARY, HeaderValue::from_static("Accept-Language"));
+        head.headers.remove(header::CONTENT_LENGTH);
+        BoxBody::new(body)
+    })
+}
+
+fn has_body(res: &ServiceResponse) -> bool {
+    !matches!(
+        res.response().body().size(),
+        BodySize::None | BodySize::Sized(0)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App, HttpResponse,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web,
+    };
+    use serde_json::Value;
+
+    use super::*;
+    use crate::i18n::Message;
+
+    async fn person_not_found() -> Result<HttpResponse, AppError> {
+        Err(AppError::NotFound(Message::PersonNotFound))
+    }
+
+    async fn not_ready() -> HttpResponse {
+        HttpResponse::ServiceUnavailable().json(serde_json::json!({ "database": "down" }))
+    }
+
+    async fn call(req: TestRequest) -> actix_web::dev::ServiceResponse {
+        let app = test::init_service(
+            App::new()
+                .wrap(ProblemMiddleware)
+                .route("/people/{id}", web::get().to(person_not_found))
+                .route("/ready", web::get().to(not_ready)),
+        )
+        .await;
+        test::call_service(&app, req.to_request()).await
+    }
+
+    #[actix_web::test]
+    async fn handler_errors_become_problems_about_the_request() {
+        let res = call(
+            TestRequest::get()
+                .uri("/people/42")
+                .insert_header((header::ACCEPT_LANGUAGE, "en")),
+        )
+        .await;
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_JSON
+        );
+        assert_eq!(res.headers().get(header::CONTENT_LANGUAGE).unwrap(), "en");
+
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["type"], "about:blank");
+        assert_eq!(body["status"], 404);
+        assert_eq!(body["title"], "Not Found");
+        assert_eq!(body["detail"], "Person not found");
+        assert_eq!(body["code"], "person.not_found");
+        assert_eq!(body["instance"], "/people/42");
+        assert!(body["request_id"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn unmatched_routes_get_a_localized_blank_problem() {
+        let res = call(
+            TestRequest::get()
+                .uri("/nowhere")
+                .insert_header((header::ACCEPT_LANGUAGE, "pt-BR")),
+        )
+        .await;
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["title"], "Não encontrado");
+        assert_eq!(body["instance"], "/nowhere");
+        assert!(body.get("detail").is_none());
+    }
+
+    #[actix_web::test]
+    async fn error_statuses_with_a_body_of_their_own_are_left_alone() {
+        let res = call(TestRequest::get().uri("/ready")).await;
+
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["database"], "down");
+    }
+}
@@ -1,2 +1,5 @@
-pub mod error_response;
-pub mod user_service_error;
+pub mod app_error;
+pub mod conflict_error;
+pub mod extractor_config;
+pub mod middleware;
+pub mod problem;
@@ -0,0 +1,113 @@
+use actix_web::{HttpResponse, http::StatusCode};
+use serde::Serialize;
+use utoipa::{ToResponse, ToSchema};
+
+use crate::i18n::{Locale, Message, message::status_title};
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Problem type used when the HTTP status says everything there is to say.
+pub const ABOUT_BLANK: &str = "about:blank";
+
+/// A single invalid request field.
+#[derive(Debug, Clone)]
+pub struct FieldError {
+    pub field: String,
+    pub message: Message,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: Message) -> Self {
+        Self {
+            field: field.into(),
+            message,
+        }
+    }
+
+    pub fn localize(&self, locale: Locale) -> FieldErrorBody {
+        FieldErrorBody {
+            field: self.field.clone(),
+            code: self.message.code(),
+            message: self.message.text(locale),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldErrorBody {
+    pub field: String,
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// RFC 9457 problem details body.
+#[derive(Debug, Clone, Serialize, ToSchema, ToResponse)]
+#[response(
+    description = "Problem details (RFC 9457)",
+    content_type = "application/problem+json"
+)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Stable, language-independent code for `detail`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldErrorBody>,
+    #[serde(skip)]
+    locale: Locale,
+}
+
+impl ProblemDetails {
+    pub fn new(problem_type: &str, title: String, status: StatusCode, locale: Locale) -> Self {
+        Self {
+            problem_type: problem_type.to_string(),
+            title,
+            status: status.as_u16(),
+            detail: None,
+            code: None,
+            instance: None,
+            request_id: None,
+            errors: Vec::new(),
+            locale,
+        }
+    }
+
+    /// `about:blank` problem titled with the status reason phrase.
+    pub fn for_status(status: StatusCode, locale: Locale) -> Self {
+        Self::new(ABOUT_BLANK, status_title(status, locale), status, locale)
+    }
+
+    pub fn with_message(mut self, message: &Message) -> Self {
+        self.detail = Some(message.text(self.locale));
+        self.code = Some(message.code());
+        self
+    }
+
+    pub fn with_errors(mut self, errors: &[FieldError]) -> Self {
+        self.errors = errors.iter().map(|e| e.localize(self.locale)).collect();
+        self
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    pub fn to_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .content_type(PROBLEM_JSON)
+            .json(self)
+    }
+}
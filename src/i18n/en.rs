@@ -0,0 +1,60 @@
+use actix_web::http::StatusCode;
+
+use crate::i18n::Message;
+
+pub fn text(message: &Message) -> String {
+    match message {
+        Message::ValidationFailed => "Validation failed".into(),
+        Message::FieldsInvalid { count } => format!("{} fields are invalid", count),
+        Message::Blank => "Must not be blank".into(),
+        Message::Required => "Field is required".into(),
+        Message::UnknownField => "Unknown field".into(),
+        Message::TooLong { max } => format!("Must be at most {} characters", max),
+        Message::TooShort { min } => format!("Must be at least {} characters", min),
+        Message::PasswordTooLong { max } => format!("Must be at most {} bytes", max),
+        Message::InvalidEmail => "Invalid email".into(),
+        Message::InvalidCpf => "Invalid CPF".into(),
+        Message::InvalidPhone => "Invalid phone".into(),
+        Message::InvalidBirthDate => "Invalid birth date".into(),
+        Message::InvalidSortColumn(column) => format!("Invalid sort column '{}'", column),
+        Message::EmptySearchQuery => "Search query must not be empty".into(),
+        Message::MalformedBody(detail) => format!("Malformed request body: {}", detail),
+        Message::PatchFailed(detail) => format!("Patch could not be applied: {}", detail),
+        Message::MalformedCsvRow(detail) => format!("Malformed CSV row: {}", detail),
+        Message::MissingCsvColumn(column) => format!("Missing CSV column '{}'", column),
+        Message::InvalidPathParam(detail) => format!("Invalid path parameter: {}", detail),
+        Message::InvalidQueryParam(detail) => format!("Invalid query parameter: {}", detail),
+        Message::AuthenticationRequired => "Authentication required".into(),
+        Message::InvalidAuthorizationHeader => "Missing or invalid authorization header".into(),
+        Message::InvalidToken => "Invalid token".into(),
+        Message::InvalidCredentials => "Invalid credentials".into(),
+        Message::AdminRequired => "Admin access required".into(),
+        Message::OwnResourcesOnly => "You can only access your own resources".into(),
+        Message::CannotChangeOwnRole => "You cannot change your own role".into(),
+        Message::AccountDisabled => "This account is disabled".into(),
+        Message::ResourceNotFound => "Resource not found".into(),
+        Message::PersonNotFound => "Person not found".into(),
+        Message::UserNotFound => "User not found".into(),
+        Message::JobNotFound => "Job not found".into(),
+        Message::JobFileNotFound => "The job has no file to download".into(),
+        Message::Conflict => "Conflict".into(),
+        Message::CpfTaken => "A person with this CPF already exists".into(),
+        Message::DuplicateCpfInFile { line } => format!("Same CPF as line {}", line),
+        Message::EmailTaken => "A user with this email already exists".into(),
+        Message::ResourceExists => "Resource already exists".into(),
+        Message::VersionMismatch => {
+            "The resource was modified since it was read; fetch it again and retry".into()
+        }
+        Message::IfMatchRequired => "This request requires an If-Match header".into(),
+        Message::PayloadTooLarge { max } => format!("The body must be at most {} bytes", max),
+        Message::UnsupportedMediaType { expected } => {
+            format!("The body must be one of: {}", expected)
+        }
+        Message::ServiceUnavailable => "Service temporarily unavailable".into(),
+        Message::InternalError => "Internal server error".into(),
+    }
+}
+
+pub fn status_title(status: StatusCode) -> Option<&'static str> {
+    status.canonical_reason()
+}
@@ -0,0 +1,143 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use actix_web::{HttpRequest, http::header, web};
+
+use crate::config::AppConfig;
+
+/// Languages with a message catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    PtBr,
+    En,
+}
+
+impl Locale {
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::PtBr => "pt-BR",
+            Locale::En => "en",
+        }
+    }
+
+    /// Picks the best supported language from an `Accept-Language` value,
+    /// honouring `q` weights. Only the primary subtag is matched, so
+    /// `pt-PT` gets the pt-BR catalog and `en-US` the English one.
+    pub fn negotiate(accept_language: Option<&str>, default: Locale) -> Locale {
+        let Some(accept_language) = accept_language else {
+            return default;
+        };
+
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                let tag = parts.next().filter(|t| !t.is_empty())?;
+                let q = parts
+                    .find_map(|p| p.strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+                Some((tag, q))
+            })
+            .filter(|(_, q)| *q > 0.0)
+            .collect();
+
+        // stable sort keeps the client's order among equal weights
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranges
+            .into_iter()
+            .find_map(|(tag, _)| match tag {
+                "*" => Some(default),
+                _ => tag.parse().ok(),
+            })
+            .unwrap_or(default)
+    }
+
+    /// Locale for a request: `Accept-Language`, falling back to the
+    /// configured default.
+    pub fn from_request(req: &HttpRequest) -> Locale {
+        let default = req
+            .app_data::<web::Data<AppConfig>>()
+            .map(|config| config.default_locale())
+            .unwrap_or_default();
+        let accept_language = req
+            .headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|h| h.to_str().ok());
+
+        Locale::negotiate(accept_language, default)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let primary = s.split(['-', '_']).next().unwrap_or_default();
+        match primary.to_ascii_lowercase().as_str() {
+            "pt" => Ok(Locale::PtBr),
+            "en" => Ok(Locale::En),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negotiate(accept_language: &str) -> Locale {
+        Locale::negotiate(Some(accept_language), Locale::PtBr)
+    }
+
+    #[test]
+    fn missing_or_unsupported_header_gives_the_default() {
+        assert_eq!(Locale::negotiate(None, Locale::En), Locale::En);
+        assert_eq!(negotiate("fr-FR, de"), Locale::PtBr);
+        assert_eq!(negotiate(""), Locale::PtBr);
+    }
+
+    #[test]
+    fn primary_subtag_picks_the_catalog() {
+        assert_eq!(negotiate("en-US"), Locale::En);
+        assert_eq!(negotiate("pt-PT"), Locale::PtBr);
+        assert_eq!(negotiate("EN_gb"), Locale::En);
+    }
+
+    #[test]
+    fn highest_weight_wins() {
+        assert_eq!(negotiate("pt-BR;q=0.5, en;q=0.9"), Locale::En);
+        assert_eq!(negotiate("fr, en;q=0.8, pt;q=0.9"), Locale::PtBr);
+    }
+
+    #[test]
+    fn order_breaks_ties() {
+        assert_eq!(negotiate("en, pt"), Locale::En);
+        assert_eq!(negotiate("pt;q=0.7, en;q=0.7"), Locale::PtBr);
+    }
+
+    #[test]
+    fn zero_weight_excludes_and_malformed_weights_are_skipped() {
+        assert_eq!(negotiate("en;q=0, pt;q=0.1"), Locale::PtBr);
+        assert_eq!(negotiate("en;q=abc"), Locale::PtBr);
+        assert_eq!(
+            Locale::negotiate(Some("pt;q=x, en"), Locale::PtBr),
+            Locale::En
+        );
+    }
+
+    #[test]
+    fn wildcard_means_the_default() {
+        assert_eq!(
+            Locale::negotiate(Some("fr, *;q=0.5"), Locale::En),
+            Locale::En
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use actix_web::http::StatusCode;
+
+use crate::i18n::{Locale, en, pt_br};
+
+/// Every client-facing error message. `code` is stable across releases and
+/// languages; the text comes from the catalog of the request's locale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    // validation
+    ValidationFailed,
+    FieldsInvalid {
+        count: usize,
+    },
+    Blank,
+    Required,
+    UnknownField,
+    TooLong {
+        max: usize,
+    },
+    TooShort {
+        min: usize,
+    },
+    PasswordTooLong {
+        max: usize,
+    },
+    InvalidEmail,
+    InvalidCpf,
+    InvalidPhone,
+    InvalidBirthDate,
+    InvalidSortColumn(String),
+    EmptySearchQuery,
+    /// Carries the parser's own (English) description.
+    MalformedBody(String),
+    /// Carries the patch library's (English) description.
+    PatchFailed(String),
+    /// Carries the CSV reader's (English) description.
+    MalformedCsvRow(String),
+    MissingCsvColumn(String),
+    InvalidPathParam(String),
+    InvalidQueryParam(String),
+
+    // auth
+    AuthenticationRequired,
+    InvalidAuthorizationHeader,
+    InvalidToken,
+    InvalidCredentials,
+    AdminRequired,
+    OwnResourcesOnly,
+    CannotChangeOwnRole,
+    AccountDisabled,
+
+    // not found
+    ResourceNotFound,
+    PersonNotFound,
+    UserNotFound,
+    JobNotFound,
+    JobFileNotFound,
+
+    // conflict
+    Conflict,
+    CpfTaken,
+    /// An earlier line of the same import has this CPF.
+    DuplicateCpfInFile {
+        line: u64,
+    },
+    EmailTaken,
+    ResourceExists,
+
+    // preconditions
+    VersionMismatch,
+    IfMatchRequired,
+
+    // request body
+    PayloadTooLarge {
+        max: usize,
+    },
+    /// `expected` lists the accepted media types.
+    UnsupportedMediaType {
+        expected: String,
+    },
+
+    ServiceUnavailable,
+    InternalError,
+}
+
+impl Message {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Message::ValidationFailed => "validation.failed",
+            Message::FieldsInvalid { .. } => "validation.fields_invalid",
+            Message::Blank => "validation.blank",
+            Message::Required => "validation.required",
+            Message::UnknownField => "validation.unknown_field",
+            Message::TooLong { .. } => "validation.too_long",
+            Message::TooShort { .. } => "validation.too_short",
+            Message::PasswordTooLong { .. } => "validation.password_too_long",
+            Message::InvalidEmail => "validation.invalid_email",
+            Message::InvalidCpf => "validation.invalid_cpf",
+            Message::InvalidPhone => "validation.invalid_phone",
+            Message::InvalidBirthDate => "validation.invalid_birth_date",
+            Message::InvalidSortColumn(_) => "validation.invalid_sort_column",
+            Message::EmptySearchQuery => "validation.empty_search_query",
+            Message::MalformedBody(_) => "validation.malformed_body",
+            Message::PatchFailed(_) => "validation.patch_failed",
+            Message::MalformedCsvRow(_) => "validation.malformed_csv_row",
+            Message::MissingCsvColumn(_) => "validation.missing_csv_column",
+            Message::InvalidPathParam(_) => "validation.invalid_path_param",
+            Message::InvalidQueryParam(_) => "validation.invalid_query_param",
+            Message::AuthenticationRequired => "auth.authentication_required",
+            Message::InvalidAuthorizationHeader => "auth.invalid_authorization_header",
+            Message::InvalidToken => "auth.invalid_token",
+            Message::InvalidCredentials => "auth.invalid_credentials",
+            Message::AdminRequired => "auth.admin_required",
+            Message::OwnResourcesOnly => "auth.own_resources_only",
+            Message::CannotChangeOwnRole => "auth.cannot_change_own_role",
+            Message::AccountDisabled => "auth.account_disabled",
+            Message::ResourceNotFound => "resource.not_found",
+            Message::PersonNotFound => "person.not_found",
+            Message::UserNotFound => "user.not_found",
+            Message::JobNotFound => "job.not_found",
+            Message::JobFileNotFound => "job.file_not_found",
+            Message::Conflict => "conflict",
+            Message::CpfTaken => "person.cpf_taken",
+            Message::DuplicateCpfInFile { .. } => "person.duplicate_cpf_in_file",
+            Message::EmailTaken => "user.email_taken",
+            Message::ResourceExists => "resource.exists",
+            Message::VersionMismatch => "precondition.version_mismatch",
+            Message::IfMatchRequired => "precondition.if_match_required",
+            Message::PayloadTooLarge { .. } => "request.payload_too_large",
+            Message::UnsupportedMediaType { .. } => "request.unsupported_media_type",
+            Message::ServiceUnavailable => "server.unavailable",
+            Message::InternalError => "server.internal_error",
+        }
+    }
+
+    pub fn text(&self, locale: Locale) -> String {
+        match locale {
+            Locale::PtBr => pt_br::text(self),
+            Locale::En => en::text(self),
+        }
+    }
+}
+
+/// Localized title for `about:blank` problems.
+pub fn status_title(status: StatusCode, locale: Locale) -> String {
+    let title = match locale {
+        Locale::PtBr => pt_br::status_title(status),
+        Locale::En => en::status_title(status),
+    };
+
+    title
+        .or(status.canonical_reason())
+        .unwrap_or("Error")
+        .to_string()
+}
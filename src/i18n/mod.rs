@@ -0,0 +1,7 @@
+pub mod en;
+pub mod locale;
+pub mod message;
+pub mod pt_br;
+
+pub use locale::Locale;
+pub use message::Message;
@@ -0,0 +1,87 @@
+use actix_web::http::StatusCode;
+
+use crate::i18n::Message;
+
+pub fn text(message: &Message) -> String {
+    match message {
+        Message::ValidationFailed => "Falha na validação".into(),
+        Message::FieldsInvalid { count } => format!("{} campos são inválidos", count),
+        Message::Blank => "Não pode ficar em branco".into(),
+        Message::Required => "Campo obrigatório".into(),
+        Message::UnknownField => "Campo desconhecido".into(),
+        Message::TooLong { max } => format!("Deve ter no máximo {} caracteres", max),
+        Message::TooShort { min } => format!("Deve ter no mínimo {} caracteres", min),
+        Message::PasswordTooLong { max } => format!("Deve ter no máximo {} bytes", max),
+        Message::InvalidEmail => "E-mail inválido".into(),
+        Message::InvalidCpf => "CPF inválido".into(),
+        Message::InvalidPhone => "Telefone inválido".into(),
+        Message::InvalidBirthDate => "Data de nascimento inválida".into(),
+        Message::InvalidSortColumn(column) => {
+            format!("Coluna de ordenação inválida '{}'", column)
+        }
+        Message::EmptySearchQuery => "O termo de busca não pode ficar vazio".into(),
+        Message::MalformedBody(detail) => {
+            format!("Corpo da requisição malformado: {}", detail)
+        }
+        Message::PatchFailed(detail) => {
+            format!("Não foi possível aplicar o patch: {}", detail)
+        }
+        Message::MalformedCsvRow(detail) => format!("Linha CSV malformada: {}", detail),
+        Message::MissingCsvColumn(column) => format!("Coluna CSV ausente '{}'", column),
+        Message::InvalidPathParam(detail) => format!("Parâmetro de caminho inválido: {}", detail),
+        Message::InvalidQueryParam(detail) => {
+            format!("Parâmetro de consulta inválido: {}", detail)
+        }
+        Message::AuthenticationRequired => "Autenticação necessária".into(),
+        Message::InvalidAuthorizationHeader => {
+            "Cabeçalho de autorização ausente ou inválido".into()
+        }
+        Message::InvalidToken => "Token inválido".into(),
+        Message::InvalidCredentials => "Credenciais inválidas".into(),
+        Message::AdminRequired => "Acesso restrito a administradores".into(),
+        Message::OwnResourcesOnly => "Você só pode acessar os seus próprios recursos".into(),
+        Message::CannotChangeOwnRole => "Você não pode alterar o seu próprio papel".into(),
+        Message::AccountDisabled => "Esta conta está desativada".into(),
+        Message::ResourceNotFound => "Recurso não encontrado".into(),
+        Message::PersonNotFound => "Pessoa não encontrada".into(),
+        Message::UserNotFound => "Usuário não encontrado".into(),
+        Message::JobNotFound => "Tarefa não encontrada".into(),
+        Message::JobFileNotFound => "A tarefa não tem arquivo para baixar".into(),
+        Message::Conflict => "Conflito".into(),
+        Message::CpfTaken => "Já existe uma pessoa com este CPF".into(),
+        Message::DuplicateCpfInFile { line } => format!("Mesmo CPF da linha {}", line),
+        Message::EmailTaken => "Já existe um usuário com este e-mail".into(),
+        Message::ResourceExists => "O recurso já existe".into(),
+        Message::VersionMismatch => {
+            "O recurso foi alterado desde a leitura; busque-o novamente e tente de novo".into()
+        }
+        Message::IfMatchRequired => "Esta requisição exige o cabeçalho If-Match".into(),
+        Message::PayloadTooLarge { max } => {
+            format!("O corpo deve ter no máximo {} bytes", max)
+        }
+        Message::UnsupportedMediaType { expected } => {
+            format!("O corpo deve ser de um destes tipos: {}", expected)
+        }
+        Message::ServiceUnavailable => "Serviço temporariamente indisponível".into(),
+        Message::InternalError => "Erro interno do servidor".into(),
+    }
+}
+
+pub fn status_title(status: StatusCode) -> Option<&'static str> {
+    let title = match status {
+        StatusCode::BAD_REQUEST => "Requisição inválida",
+        StatusCode::UNAUTHORIZED => "Não autorizado",
+        StatusCode::FORBIDDEN => "Proibido",
+        StatusCode::NOT_FOUND => "Não encontrado",
+        StatusCode::METHOD_NOT_ALLOWED => "Método não permitido",
+        StatusCode::CONFLICT => "Conflito",
+        StatusCode::PRECONDITION_FAILED => "Pré-condição falhou",
+        StatusCode::PAYLOAD_TOO_LARGE => "Conteúdo muito grande",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => "Tipo de mídia não suportado",
+        StatusCode::PRECONDITION_REQUIRED => "Pré-condição necessária",
+        StatusCode::INTERNAL_SERVER_ERROR => "Erro interno do servidor",
+        StatusCode::SERVICE_UNAVAILABLE => "Serviço indisponível",
+        _ => return None,
+    };
+    Some(title)
+}
@@ -5,15 +5,19 @@ pub mod auth;
 pub mod controller;
 pub mod dto;
 pub mod error;
+pub mod i18n;
+pub mod metrics;
 pub mod model;
 pub mod repository;
 pub mod schema;
 pub mod service;
+pub mod telemetry;
 pub mod util;
 
-pub use bootstrap::start_http_server;
+pub use bootstrap::{spawn_person_retention, start_http_server};
 pub use config::AppConfig;
 pub use service::db::create_pool;
 pub use service::person_service::PersonService;
 pub use service::user_service::UserService;
+pub use telemetry::init_tracing;
 pub use util::app_state::AppState;
@@ -1,19 +1,19 @@
 use actix_web::web;
 use dotenvy::dotenv;
-use env_logger::Env;
 
 use rest_actix_rust::{
-    AppConfig, AppState, PersonService, UserService, create_pool, start_http_server,
+    AppConfig, AppState, PersonService, UserService, create_pool, init_tracing,
+    spawn_person_retention, start_http_server,
 };
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    env_logger::init_from_env(Env::default().default_filter_or("info"));
 
     let config = AppConfig::from_env();
+    let _tracing = init_tracing(&config);
 
-    let pool = create_pool(&config.database_url());
+    let pool = create_pool(config.database_url());
     let app_state = web::Data::new(AppState::new(
         pool,
         PersonService::new(),
@@ -21,5 +21,7 @@ async fn main() -> std::io::Result<()> {
         config.secret().to_string(),
     ));
 
+    spawn_person_retention(config.person_retention_days(), app_state.clone());
+
     start_http_server(config, app_state).await
 }
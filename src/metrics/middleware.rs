@@ -0,0 +1,136 @@
+use std::time::Instant;
+
+use actix_web::{
+    Error,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+};
+use futures_util::future::{LocalBoxFuture, Ready, ok};
+
+use crate::metrics::METRICS;
+
+/// Counts requests and records their latency, labelled by route name (the
+/// `name = "..."` of the handler, falling back to its path pattern) and
+/// status code.
+pub struct MetricsMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for MetricsMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MetricsMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(MetricsMiddlewareService { service })
+    }
+}
+
+pub struct MetricsMiddlewareService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let started = Instant::now();
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+
+            let request = res.request();
+            // unmatched paths share one label so random URLs cannot blow up cardinality
+            let route = request
+                .match_name()
+                .map(str::to_string)
+                .or_else(|| request.match_pattern())
+                .unwrap_or_else(|| "unmatched".into());
+            let status = res.status().as_u16().to_string();
+            let labels = [route.as_str(), status.as_str()];
+
+            METRICS.http_requests.with_label_values(&labels).inc();
+            METRICS
+                .http_request_duration
+                .with_label_values(&labels)
+                .observe(started.elapsed().as_secs_f64());
+
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App, HttpResponse,
+        test::{self, TestRequest},
+        web,
+    };
+
+    use super::*;
+
+    fn requests(route: &str, status: &str) -> u64 {
+        METRICS
+            .http_requests
+            .with_label_values(&[route, status])
+            .get()
+    }
+
+    #[actix_web::test]
+    async fn requests_are_labelled_by_route_name_and_status() {
+        let app = test::init_service(
+            App::new().wrap(MetricsMiddleware).service(
+                web::resource("/things/{id}")
+                    .name("metrics_test_thing")
+                    .to(HttpResponse::NoContent),
+            ),
+        )
+        .await;
+        let before = requests("metrics_test_thing", "204");
+
+        for id in 1..=2 {
+            let uri = format!("/things/{id}");
+            test::call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+        }
+
+        assert_eq!(requests("metrics_test_thing", "204"), before + 2);
+        assert_eq!(
+            METRICS
+                .http_request_duration
+                .with_label_values(&["metrics_test_thing", "204"])
+                .get_sample_count(),
+            before + 2
+        );
+    }
+
+    #[actix_web::test]
+    async fn unnamed_routes_fall_back_to_their_pattern_and_misses_share_a_label() {
+        let app = test::init_service(
+            App::new()
+                .wrap(MetricsMiddleware)
+                .route("/metrics-test/{id}", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let pattern = requests("/metrics-test/{id}", "200");
+        let unmatched = requests("unmatched", "404");
+
+        test::call_service(&app, TestRequest::get().uri("/metrics-test/7").to_request()).await;
+        test::call_service(&app, TestRequest::get().uri("/nowhere/8").to_request()).await;
+
+        assert_eq!(requests("/metrics-test/{id}", "200"), pattern + 1);
+        assert!(requests("unmatched", "404") > unmatched);
+    }
+}
@@ -0,0 +1,200 @@
+pub mod middleware;
+pub mod pool_events;
+
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+
+use crate::service::db::DbPool;
+
+/// Process-wide metrics, registered on a dedicated registry that backs
+/// `GET /metrics`.
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Buckets for work measured in milliseconds to a few seconds.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub pool_connections: IntGaugeVec,
+    pub pool_wait: Histogram,
+    pub pool_timeouts: IntCounter,
+    pub blocking_queue: Histogram,
+    pub login_attempts: IntCounterVec,
+    pub bcrypt_duration: HistogramVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["route", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by route and status",
+            )
+            .buckets(LATENCY_BUCKETS.to_vec()),
+            &["route", "status"],
+        )
+        .unwrap();
+        let pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )
+        .unwrap();
+        let pool_wait = Histogram::with_opts(
+            HistogramOpts::new(
+                "db_pool_wait_seconds",
+                "Time spent waiting to check a connection out of the pool",
+            )
+            .buckets(LATENCY_BUCKETS.to_vec()),
+        )
+        .unwrap();
+        let pool_timeouts = IntCounter::new(
+            "db_pool_timeouts_total",
+            "Connection checkouts that hit the pool timeout",
+        )
+        .unwrap();
+        let blocking_queue = Histogram::with_opts(
+            HistogramOpts::new(
+                "blocking_queue_seconds",
+                "Time `web::block` closures wait for a blocking thread",
+            )
+            .buckets(LATENCY_BUCKETS.to_vec()),
+        )
+        .unwrap();
+        let login_attempts = IntCounterVec::new(
+            Opts::new("login_attempts_total", "Login attempts by outcome"),
+            &["outcome"],
+        )
+        .unwrap();
+        let bcrypt_duration = HistogramVec::new(
+            HistogramOpts::new("bcrypt_duration_seconds", "bcrypt hash and verify duration")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["operation"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(pool_connections.clone()))
+            .unwrap();
+        registry.register(Box::new(pool_wait.clone())).unwrap();
+        registry.register(Box::new(pool_timeouts.clone())).unwrap();
+        registry.register(Box::new(blocking_queue.clone())).unwrap();
+        registry.register(Box::new(login_attempts.clone())).unwrap();
+        registry
+            .register(Box::new(bcrypt_duration.clone()))
+            .unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            pool_connections,
+            pool_wait,
+            pool_timeouts,
+            blocking_queue,
+            login_attempts,
+            bcrypt_duration,
+        }
+    }
+
+    pub fn record_login(&self, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.login_attempts.with_label_values(&[outcome]).inc();
+    }
+
+    /// Refreshes the pool gauges; r2d2 only exposes them as a snapshot.
+    pub fn observe_pool(&self, pool: &DbPool) {
+        let state = pool.state();
+        let idle = i64::from(state.idle_connections);
+        let total = i64::from(state.connections);
+
+        self.pool_connections.with_label_values(&["idle"]).set(idle);
+        self.pool_connections
+            .with_label_values(&["in_use"])
+            .set(total - idle);
+    }
+
+    /// Prometheus text exposition of every registered metric.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("metrics are always encodable as text");
+        String::from_utf8(buffer).expect("text exposition is UTF-8")
+    }
+}
+
+/// Runs a bcrypt operation, recording how long it took.
+pub fn time_bcrypt<T>(operation: &str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    METRICS
+        .bcrypt_duration
+        .with_label_values(&[operation])
+        .observe(started.elapsed().as_secs_f64());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_metrics_are_rendered_in_text_format() {
+        METRICS.record_login(false);
+        time_bcrypt("verify", || ());
+        let text = METRICS.render();
+
+        for name in [
+            "# TYPE login_attempts_total counter",
+            "# TYPE bcrypt_duration_seconds histogram",
+            r#"login_attempts_total{outcome="failure"}"#,
+            r#"bcrypt_duration_seconds_count{operation="verify"}"#,
+        ] {
+            assert!(text.contains(name), "{name} missing from:\n{text}");
+        }
+    }
+
+    #[test]
+    fn logins_are_counted_by_outcome() {
+        let successes = || METRICS.login_attempts.with_label_values(&["success"]).get();
+        let before = successes();
+
+        METRICS.record_login(true);
+
+        assert!(successes() > before);
+    }
+
+    #[test]
+    fn bcrypt_timing_passes_the_result_through() {
+        let count = || {
+            METRICS
+                .bcrypt_duration
+                .with_label_values(&["hash"])
+                .get_sample_count()
+        };
+        let before = count();
+
+        assert_eq!(time_bcrypt("hash", || 42), 42);
+        assert!(count() > before);
+    }
+}
@@ -0,0 +1,21 @@
+use diesel::r2d2::{
+    HandleEvent,
+    event::{CheckoutEvent, TimeoutEvent},
+};
+
+use crate::metrics::METRICS;
+
+/// Feeds r2d2 checkout events into the pool metrics.
+#[derive(Debug)]
+pub struct PoolEventHandler;
+
+impl HandleEvent for PoolEventHandler {
+    fn handle_checkout(&self, event: CheckoutEvent) {
+        METRICS.pool_wait.observe(event.duration().as_secs_f64());
+    }
+
+    fn handle_timeout(&self, event: TimeoutEvent) {
+        METRICS.pool_timeouts.inc();
+        METRICS.pool_wait.observe(event.timeout().as_secs_f64());
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde_json::{Map, Value, json};
+use uuid::Uuid;
+
+use crate::{
+    model::{audit_action::AuditAction, entity_type::EntityType},
+    schema::audit_events,
+};
+
+/// Placeholder written instead of secret values, e.g. password hashes.
+pub const REDACTED: &str = "[redacted]";
+
+/// A record that can appear in the audit trail.
+pub trait Auditable {
+    const ENTITY_TYPE: EntityType;
+
+    fn entity_id(&self) -> Uuid;
+
+    /// Fields compared between versions. Bookkeeping columns such as
+    /// `updated_at` are left out so they do not show up in every diff.
+    fn audit_snapshot(&self) -> Value;
+}
+
+#[derive(Debug, Queryable, Selectable)]
+#[diesel(table_name = audit_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AuditEvent {
+    id: Uuid,
+    actor_id: Option<Uuid>,
+    action: AuditAction,
+    entity_type: EntityType,
+    entity_id: Uuid,
+    changes: Value,
+    request_id: Option<String>,
+    ip: Option<String>,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = audit_events)]
+pub struct NewAuditEvent {
+    id: Uuid,
+    actor_id: Option<Uuid>,
+    action: AuditAction,
+    entity_type: EntityType,
+    entity_id: Uuid,
+    changes: Value,
+    request_id: Option<String>,
+    ip: Option<String>,
+}
+
+impl NewAuditEvent {
+    pub fn new(
+        actor_id: Option<Uuid>,
+        action: AuditAction,
+        entity_type: EntityType,
+        entity_id: Uuid,
+        changes: Value,
+        request_id: Option<String>,
+        ip: Option<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            actor_id,
+            action,
+            entity_type,
+            entity_id,
+            changes,
+            request_id,
+            ip,
+        }
+    }
+}
+
+impl AuditEvent {
+    pub fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    pub fn actor_id(&self) -> Option<Uuid> {
+        self.actor_id
+    }
+
+    pub fn action(&self) -> AuditAction {
+        self.action
+    }
+
+    pub fn entity_type(&self) -> EntityType {
+        self.entity_type
+    }
+
+    pub fn entity_id(&self) -> &Uuid {
+        &self.entity_id
+    }
+
+    pub fn changes(&self) -> &Value {
+        &self.changes
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    pub fn ip(&self) -> Option<&str> {
+        self.ip.as_deref()
+    }
+
+    pub fn created_at(&self) -> &NaiveDateTime {
+        &self.created_at
+    }
+}
+
+/// Field-level diff between two snapshots: `{"field": {"before": .., "after": ..}}`
+/// for every field whose value differs. A missing snapshot (creation or
+/// deletion) contributes no side, so creations only carry `after` values.
+pub fn diff(before: Option<&Value>, after: Option<&Value>) -> Map<String, Value> {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut changes = Map::new();
+    for key in before
+        .keys()
+        .chain(after.keys().filter(|k| !before.contains_key(*k)))
+    {
+        let (old, new) = (before.get(key), after.get(key));
+        if old == new {
+            continue;
+        }
+
+        let mut change = Map::new();
+        if let Some(old) = old {
+            change.insert("before".into(), old.clone());
+        }
+        if let Some(new) = new {
+            change.insert("after".into(), new.clone());
+        }
+        changes.insert(key.clone(), Value::Object(change));
+    }
+
+    changes
+}
+
+/// Change entry for a secret field: records that it changed, but not how.
+pub fn redacted_change() -> Value {
+    json!({ "before": REDACTED, "after": REDACTED })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_changed_fields_are_recorded() {
+        let before = json!({ "name": "Ana", "cpf": "52998224725", "phone": null });
+        let after = json!({ "name": "Ana Souza", "cpf": "52998224725", "email": "a@x.io" });
+
+        assert_eq!(
+            Value::Object(diff(Some(&before), Some(&after))),
+            json!({
+                "name": { "before": "Ana", "after": "Ana Souza" },
+                "phone": { "before": null },
+                "email": { "after": "a@x.io" },
+            })
+        );
+    }
+
+    #[test]
+    fn creations_and_deletions_carry_one_side() {
+        let person = json!({ "name": "Ana" });
+
+        assert_eq!(
+            Value::Object(diff(None, Some(&person))),
+            json!({ "name": { "after": "Ana" } })
+        );
+        assert_eq!(
+            Value::Object(diff(Some(&person), None)),
+            json!({ "name": { "before": "Ana" } })
+        );
+        assert!(diff(Some(&person), Some(&person)).is_empty());
+    }
+}
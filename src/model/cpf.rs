@@ -0,0 +1,171 @@
+use diesel::deserialize::{self, FromSql};
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::{AsExpression, FromSqlRow};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::str::FromStr;
+
+/// A valid CPF, always held as its 11 digits.
+///
+/// Digits punctuated with `.`, `-` or whitespace are accepted on
+/// construction; the punctuated
+/// `000.000.000-00` form is only produced for responses via [`Cpf::formatted`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+pub struct Cpf(String);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidCpf;
+
+impl Display for InvalidCpf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Invalid CPF")
+    }
+}
+
+impl std::error::Error for InvalidCpf {}
+
+impl Cpf {
+    pub fn parse(raw: &str) -> Result<Self, InvalidCpf> {
+        // `cpf_util` takes any Unicode digit for a number and panics parsing
+        // it, so nothing but the usual punctuation gets that far.
+        if !raw
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == '-' || c.is_whitespace())
+        {
+            return Err(InvalidCpf);
+        }
+        if !cpf_util::is_valid(raw) {
+            return Err(InvalidCpf);
+        }
+
+        Ok(Self(raw.chars().filter(char::is_ascii_digit).collect()))
+    }
+
+    /// The 11 digits, as stored in the database.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn formatted(&self) -> String {
+        cpf_util::format(&self.0)
+    }
+
+    /// Hides all but the middle digits: `***.982.247-**`.
+    pub fn masked(&self) -> String {
+        format!("***.{}.{}-**", &self.0[3..6], &self.0[6..9])
+    }
+
+    /// [`Cpf::masked`] if `mask`, otherwise [`Cpf::formatted`].
+    pub fn for_display(&self, mask: bool) -> String {
+        if mask {
+            self.masked()
+        } else {
+            self.formatted()
+        }
+    }
+}
+
+impl Display for Cpf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Cpf {
+    type Err = InvalidCpf;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Cpf::parse(s)
+    }
+}
+
+impl Serialize for Cpf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cpf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        Cpf::parse(&raw).map_err(serde::de::Error::custom)
+    }
+}
+
+impl ToSql<Text, Pg> for Cpf {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.0.as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for Cpf {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        // `persons_cpf_digits_check` guarantees the shape; the check digits
+        // are not re-validated so legacy rows can still be read.
+        match std::str::from_utf8(bytes.as_bytes()) {
+            Ok(digits) if digits.len() == 11 && digits.bytes().all(|b| b.is_ascii_digit()) => {
+                Ok(Cpf(digits.to_string()))
+            }
+            _ => Err("invalid CPF (expected 11 digits)".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_punctuated_and_bare_digits() {
+        assert_eq!(
+            Cpf::parse("529.982.247-25").unwrap().as_str(),
+            "52998224725"
+        );
+        assert_eq!(Cpf::parse("52998224725").unwrap().as_str(), "52998224725");
+    }
+
+    #[test]
+    fn parse_accepts_whitespace_between_digits() {
+        assert_eq!(
+            Cpf::parse("529 982 247 25").unwrap().as_str(),
+            "52998224725"
+        );
+        assert_eq!(
+            Cpf::parse(" 529.982.247 - 25\t").unwrap().as_str(),
+            "52998224725"
+        );
+    }
+
+    #[test]
+    fn parse_rejects_wrong_check_digits() {
+        assert_eq!(Cpf::parse("529.982.247-26"), Err(InvalidCpf));
+        assert_eq!(Cpf::parse("111.111.111-11"), Err(InvalidCpf));
+    }
+
+    #[test]
+    fn parse_rejects_other_characters() {
+        assert_eq!(Cpf::parse("529.982.247/25"), Err(InvalidCpf));
+        assert_eq!(Cpf::parse("5299822472a"), Err(InvalidCpf));
+        assert_eq!(Cpf::parse(""), Err(InvalidCpf));
+    }
+
+    #[test]
+    fn parse_rejects_non_ascii_digits_without_panicking() {
+        // Arabic-Indic digits for 52998224725
+        assert_eq!(Cpf::parse("٥٢٩٩٨٢٢٤٧٢٥"), Err(InvalidCpf));
+        // eleven bytes, which `cpf_util` takes for eleven digits
+        assert_eq!(Cpf::parse("529982247٥"), Err(InvalidCpf));
+    }
+
+    #[test]
+    fn formats_and_masks_the_digits() {
+        let cpf = Cpf::parse("52998224725").unwrap();
+        assert_eq!(cpf.formatted(), "529.982.247-25");
+        assert_eq!(cpf.masked(), "***.982.247-**");
+    }
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// How a bulk import treats rows that fail validation or collide with
+/// existing data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Checks every row and reports, writing nothing.
+    DryRun,
+    /// Imports every row or, if any row fails, none.
+    #[default]
+    AllOrNothing,
+    /// Imports the rows that pass and reports the rest.
+    BestEffort,
+}
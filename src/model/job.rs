@@ -0,0 +1,113 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    model::{job_kind::JobKind, job_status::JobStatus},
+    schema::jobs,
+};
+
+#[derive(Debug, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = jobs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Job {
+    id: Uuid,
+    kind: JobKind,
+    payload: Value,
+    status: JobStatus,
+    attempts: i32,
+    max_attempts: i32,
+    run_at: NaiveDateTime,
+    locked_at: Option<NaiveDateTime>,
+    last_error: Option<String>,
+    result: Option<Value>,
+    created_by: Option<Uuid>,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    finished_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = jobs)]
+pub struct NewJob {
+    id: Uuid,
+    kind: JobKind,
+    payload: Value,
+    max_attempts: i32,
+    created_by: Option<Uuid>,
+}
+
+impl NewJob {
+    pub fn new(kind: JobKind, payload: Value, max_attempts: i32, created_by: Option<Uuid>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            payload,
+            max_attempts,
+            created_by,
+        }
+    }
+}
+
+impl Job {
+    pub fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    pub fn kind(&self) -> JobKind {
+        self.kind
+    }
+
+    pub fn payload(&self) -> &Value {
+        &self.payload
+    }
+
+    pub fn status(&self) -> JobStatus {
+        self.status
+    }
+
+    /// Attempts started so far, including the one running.
+    pub fn attempts(&self) -> i32 {
+        self.attempts
+    }
+
+    pub fn max_attempts(&self) -> i32 {
+        self.max_attempts
+    }
+
+    /// When the job becomes due; for a queued retry, when it is retried.
+    pub fn run_at(&self) -> &NaiveDateTime {
+        &self.run_at
+    }
+
+    /// When the running attempt started; `None` unless running.
+    pub fn locked_at(&self) -> Option<&NaiveDateTime> {
+        self.locked_at.as_ref()
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    pub fn result(&self) -> Option<&Value> {
+        self.result.as_ref()
+    }
+
+    /// The user who enqueued the job; `None` for system jobs.
+    pub fn created_by(&self) -> Option<Uuid> {
+        self.created_by
+    }
+
+    pub fn created_at(&self) -> &NaiveDateTime {
+        &self.created_at
+    }
+
+    pub fn updated_at(&self) -> &NaiveDateTime {
+        &self.updated_at
+    }
+
+    pub fn finished_at(&self) -> Option<&NaiveDateTime> {
+        self.finished_at.as_ref()
+    }
+}
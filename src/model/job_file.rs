@@ -0,0 +1,90 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    model::job_file_purpose::JobFilePurpose,
+    schema::{job_file_chunks, job_files},
+};
+
+/// A file's metadata; its content is stored in chunks, see
+/// [`NewJobFileChunk`].
+#[derive(Debug, Queryable, Selectable)]
+#[diesel(table_name = job_files)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobFile {
+    job_id: Uuid,
+    purpose: JobFilePurpose,
+    file_name: String,
+    content_type: String,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = job_files)]
+pub struct NewJobFile {
+    job_id: Uuid,
+    purpose: JobFilePurpose,
+    file_name: String,
+    content_type: String,
+}
+
+impl NewJobFile {
+    pub fn new(
+        job_id: Uuid,
+        purpose: JobFilePurpose,
+        file_name: String,
+        content_type: String,
+    ) -> Self {
+        Self {
+            job_id,
+            purpose,
+            file_name,
+            content_type,
+        }
+    }
+}
+
+/// Chunk `seq` of a file's content, counted from 0.
+#[derive(Insertable)]
+#[diesel(table_name = job_file_chunks)]
+pub struct NewJobFileChunk<'a> {
+    job_id: Uuid,
+    purpose: JobFilePurpose,
+    seq: i32,
+    data: &'a [u8],
+}
+
+impl<'a> NewJobFileChunk<'a> {
+    pub fn new(job_id: Uuid, purpose: JobFilePurpose, seq: i32, data: &'a [u8]) -> Self {
+        Self {
+            job_id,
+            purpose,
+            seq,
+            data,
+        }
+    }
+}
+
+impl JobFile {
+    pub fn job_id(&self) -> &Uuid {
+        &self.job_id
+    }
+
+    pub fn purpose(&self) -> JobFilePurpose {
+        self.purpose
+    }
+
+    /// Name the file is offered under when downloaded.
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    pub fn created_at(&self) -> &NaiveDateTime {
+        &self.created_at
+    }
+}
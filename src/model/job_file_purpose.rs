@@ -0,0 +1,49 @@
+use diesel::deserialize::{self, FromSql};
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::VarChar;
+use diesel::{AsExpression, FromSqlRow};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+/// What a job does with one of its files; a job has at most one of each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow)]
+#[diesel(sql_type = VarChar)]
+pub enum JobFilePurpose {
+    /// Read by the job; dropped once the job succeeds or dies.
+    Input,
+    /// Written by the job for its owner to download.
+    Output,
+}
+
+impl JobFilePurpose {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobFilePurpose::Input => "input",
+            JobFilePurpose::Output => "output",
+        }
+    }
+}
+
+impl Display for JobFilePurpose {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ToSql<VarChar, Pg> for JobFilePurpose {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<VarChar, Pg> for JobFilePurpose {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"input" => Ok(JobFilePurpose::Input),
+            b"output" => Ok(JobFilePurpose::Output),
+            _ => Err("invalid job file purpose".into()),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use diesel::deserialize::{self, FromSql};
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::VarChar;
+use diesel::{AsExpression, FromSqlRow};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow, Serialize, Deserialize, ToSchema,
+)]
+#[diesel(sql_type = VarChar)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Purges people soft-deleted longer than the retention period.
+    PersonPurge,
+    /// Imports people from an uploaded CSV file.
+    PersonImport,
+    /// Writes the people matching a filter to a file.
+    PersonExport,
+}
+
+impl JobKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobKind::PersonPurge => "person_purge",
+            JobKind::PersonImport => "person_import",
+            JobKind::PersonExport => "person_export",
+        }
+    }
+}
+
+impl Display for JobKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for JobKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "person_purge" => Ok(JobKind::PersonPurge),
+            "person_import" => Ok(JobKind::PersonImport),
+            "person_export" => Ok(JobKind::PersonExport),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ToSql<VarChar, Pg> for JobKind {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<VarChar, Pg> for JobKind {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"person_purge" => Ok(JobKind::PersonPurge),
+            b"person_import" => Ok(JobKind::PersonImport),
+            b"person_export" => Ok(JobKind::PersonExport),
+            _ => Err("invalid job kind".into()),
+        }
+    }
+}
@@ -1,3 +1,15 @@
+pub mod audit_action;
+pub mod audit_event;
+pub mod cpf;
+pub mod entity_type;
+pub mod import_mode;
+pub mod job;
+pub mod job_file;
+pub mod job_file_purpose;
+pub mod job_kind;
+pub mod job_status;
 pub mod person;
+pub mod person_version;
 pub mod role;
 pub mod user;
+pub mod user_status;
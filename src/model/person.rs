@@ -1,14 +1,29 @@
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel::prelude::*;
+use serde_json::{Value, json};
 use uuid::Uuid;
 
-use crate::schema::persons;
+use crate::{
+    model::{audit_event::Auditable, cpf::Cpf, entity_type::EntityType},
+    schema::persons,
+};
 
-#[derive(Queryable, Identifiable)]
+#[derive(Queryable, QueryableByName, Identifiable)]
 #[diesel(table_name = persons)]
 pub struct Person {
     id: Uuid,
     name: String,
-    cpf: String,
+    cpf: Cpf,
+    birth_date: Option<NaiveDate>,
+    email: Option<String>,
+    phone: Option<String>,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    created_by: Option<Uuid>,
+    updated_by: Option<Uuid>,
+    deleted_at: Option<NaiveDateTime>,
+    deleted_by: Option<Uuid>,
+    version: i32,
 }
 
 #[derive(Insertable)]
@@ -16,53 +31,97 @@ pub struct Person {
 pub struct NewPerson {
     id: Uuid,
     name: String,
-    cpf: String,
+    cpf: Cpf,
+    birth_date: Option<NaiveDate>,
+    email: Option<String>,
+    phone: Option<String>,
+    created_by: Option<Uuid>,
+    updated_by: Option<Uuid>,
 }
 
+/// Full replacement (PUT): optional fields sent as `None` are cleared.
 #[derive(AsChangeset)]
 #[diesel(table_name = persons)]
+#[diesel(treat_none_as_null = true)]
 pub struct UpdatePerson {
     name: String,
-    cpf: String,
+    cpf: Cpf,
+    birth_date: Option<NaiveDate>,
+    email: Option<String>,
+    phone: Option<String>,
+    updated_by: Option<Uuid>,
 }
 
 #[derive(AsChangeset)]
 #[diesel(table_name = persons)]
 pub struct UpdateCpf {
-    cpf: String,
+    cpf: Cpf,
+    updated_by: Option<Uuid>,
 }
 
 #[derive(AsChangeset)]
 #[diesel(table_name = persons)]
 pub struct UpdateName {
     name: String,
+    updated_by: Option<Uuid>,
 }
 
 impl UpdateCpf {
-    pub fn new(cpf: String) -> Self {
-        Self { cpf }
+    pub fn new(cpf: Cpf, updated_by: Option<Uuid>) -> Self {
+        Self { cpf, updated_by }
     }
 }
 
 impl UpdateName {
-    pub fn new(name: String) -> Self {
-        Self { name }
+    pub fn new(name: String, updated_by: Option<Uuid>) -> Self {
+        Self { name, updated_by }
     }
 }
 
 impl UpdatePerson {
-    pub fn new(name: String, cpf: String) -> Self {
-        Self { name, cpf }
+    pub fn new(
+        name: String,
+        cpf: Cpf,
+        birth_date: Option<NaiveDate>,
+        email: Option<String>,
+        phone: Option<String>,
+        updated_by: Option<Uuid>,
+    ) -> Self {
+        Self {
+            name,
+            cpf,
+            birth_date,
+            email,
+            phone,
+            updated_by,
+        }
     }
 }
+
 impl NewPerson {
-    pub fn new(name: String, cpf: String) -> Self {
+    pub fn new(
+        name: String,
+        cpf: Cpf,
+        birth_date: Option<NaiveDate>,
+        email: Option<String>,
+        phone: Option<String>,
+        created_by: Option<Uuid>,
+    ) -> Self {
         Self {
             id: Uuid::new_v4(),
             name,
             cpf,
+            birth_date,
+            email,
+            phone,
+            created_by,
+            updated_by: created_by,
         }
     }
+
+    pub fn cpf(&self) -> &Cpf {
+        &self.cpf
+    }
 }
 
 impl Person {
@@ -70,11 +129,74 @@ impl Person {
         &self.name
     }
 
-    pub fn cpf(&self) -> &str {
+    pub fn cpf(&self) -> &Cpf {
         &self.cpf
     }
 
     pub fn id(&self) -> &uuid::Uuid {
         &self.id
     }
+
+    pub fn birth_date(&self) -> Option<NaiveDate> {
+        self.birth_date
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    pub fn phone(&self) -> Option<&str> {
+        self.phone.as_deref()
+    }
+
+    pub fn created_at(&self) -> &NaiveDateTime {
+        &self.created_at
+    }
+
+    pub fn updated_at(&self) -> &NaiveDateTime {
+        &self.updated_at
+    }
+
+    pub fn created_by(&self) -> Option<Uuid> {
+        self.created_by
+    }
+
+    pub fn updated_by(&self) -> Option<Uuid> {
+        self.updated_by
+    }
+
+    pub fn deleted_at(&self) -> Option<&NaiveDateTime> {
+        self.deleted_at.as_ref()
+    }
+
+    pub fn deleted_by(&self) -> Option<Uuid> {
+        self.deleted_by
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Incremented on every write; see [`crate::util::if_match`].
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+impl Auditable for Person {
+    const ENTITY_TYPE: EntityType = EntityType::Person;
+
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn audit_snapshot(&self) -> Value {
+        json!({
+            "name": self.name,
+            "cpf": self.cpf,
+            "birth_date": self.birth_date,
+            "email": self.email,
+            "phone": self.phone,
+        })
+    }
 }
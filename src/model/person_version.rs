@@ -0,0 +1,62 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::{model::cpf::Cpf, schema::persons_history};
+
+/// A past or current version of a person, kept by the `persons_history`
+/// trigger on every change.
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = persons_history)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PersonVersion {
+    version: i32,
+    name: String,
+    cpf: Cpf,
+    birth_date: Option<NaiveDate>,
+    email: Option<String>,
+    phone: Option<String>,
+    updated_by: Option<Uuid>,
+    valid_from: NaiveDateTime,
+    valid_to: Option<NaiveDateTime>,
+}
+
+impl PersonVersion {
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cpf(&self) -> &Cpf {
+        &self.cpf
+    }
+
+    pub fn birth_date(&self) -> Option<NaiveDate> {
+        self.birth_date
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    pub fn phone(&self) -> Option<&str> {
+        self.phone.as_deref()
+    }
+
+    /// Who made the change that produced this version.
+    pub fn changed_by(&self) -> Option<Uuid> {
+        self.updated_by
+    }
+
+    pub fn valid_from(&self) -> &NaiveDateTime {
+        &self.valid_from
+    }
+
+    /// `None` while this is the live version.
+    pub fn valid_to(&self) -> Option<&NaiveDateTime> {
+        self.valid_to.as_ref()
+    }
+}
@@ -1,8 +1,12 @@
 use chrono::NaiveDateTime;
 use diesel::{AsChangeset, Insertable, Queryable, Selectable};
+use serde_json::{Value, json};
 use uuid::Uuid;
 
-use crate::{model::role::Role, schema::users};
+use crate::{
+    model::{audit_event::Auditable, entity_type::EntityType, role::Role, user_status::UserStatus},
+    schema::users,
+};
 
 #[derive(Debug, Queryable, Selectable)]
 #[diesel(table_name = users)]
@@ -13,6 +17,8 @@ pub struct User {
     password_hash: String,
     created_at: NaiveDateTime,
     role: Role,
+    status: UserStatus,
+    version: i32,
 }
 
 #[derive(Insertable)]
@@ -32,6 +38,16 @@ pub struct UpdateUser {
     password_hash: String,
 }
 
+/// All editable fields at once; the password hash is left untouched when
+/// `None`.
+#[derive(AsChangeset)]
+#[diesel(table_name = users)]
+pub struct PatchUser {
+    email: String,
+    role: Role,
+    password_hash: Option<String>,
+}
+
 #[derive(AsChangeset)]
 #[diesel(table_name = users)]
 pub struct UpdatePassword {
@@ -50,6 +66,12 @@ pub struct UpdateRole {
     role: Role,
 }
 
+#[derive(AsChangeset)]
+#[diesel(table_name = users)]
+pub struct UpdateStatus {
+    status: UserStatus,
+}
+
 impl UpdatePassword {
     pub fn new(password_hash: String) -> Self {
         Self { password_hash }
@@ -68,6 +90,22 @@ impl UpdateRole {
     }
 }
 
+impl UpdateStatus {
+    pub fn new(status: UserStatus) -> Self {
+        Self { status }
+    }
+}
+
+impl PatchUser {
+    pub fn new(email: String, role: Role, password_hash: Option<String>) -> Self {
+        Self {
+            email,
+            role,
+            password_hash,
+        }
+    }
+}
+
 impl UpdateUser {
     pub fn new(email: String, role: Role, password_hash: String) -> Self {
         Self {
@@ -89,6 +127,15 @@ impl User {
     pub fn role(&self) -> Role {
         self.role
     }
+    /// Only active users can log in.
+    pub fn status(&self) -> UserStatus {
+        self.status
+    }
+
+    /// Incremented on every write; see [`crate::util::if_match`].
+    pub fn version(&self) -> i32 {
+        self.version
+    }
     pub fn password_hash(&self) -> &str {
         &self.password_hash
     }
@@ -97,3 +144,20 @@ impl User {
         &self.created_at
     }
 }
+
+impl Auditable for User {
+    const ENTITY_TYPE: EntityType = EntityType::User;
+
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+
+    /// The password hash is never copied into the audit trail.
+    fn audit_snapshot(&self) -> Value {
+        json!({
+            "email": self.email,
+            "role": self.role,
+            "status": self.status,
+        })
+    }
+}
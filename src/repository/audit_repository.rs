@@ -0,0 +1,88 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::count_star;
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        audit_action::AuditAction,
+        audit_event::{AuditEvent, NewAuditEvent},
+        entity_type::EntityType,
+    },
+    schema::audit_events::{self, dsl::*},
+};
+
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<AuditAction>,
+    pub entity_type: Option<EntityType>,
+    pub entity_id: Option<Uuid>,
+    pub created_after: Option<NaiveDateTime>,
+    pub created_before: Option<NaiveDateTime>,
+}
+
+impl AuditFilter {
+    fn apply<'a>(
+        &self,
+        mut query: audit_events::BoxedQuery<'a, Pg>,
+    ) -> audit_events::BoxedQuery<'a, Pg> {
+        if let Some(actor) = self.actor_id {
+            query = query.filter(actor_id.eq(actor));
+        }
+        if let Some(a) = self.action {
+            query = query.filter(action.eq(a));
+        }
+        if let Some(t) = self.entity_type {
+            query = query.filter(entity_type.eq(t));
+        }
+        if let Some(entity) = self.entity_id {
+            query = query.filter(entity_id.eq(entity));
+        }
+        if let Some(after) = self.created_after {
+            query = query.filter(created_at.ge(after));
+        }
+        if let Some(before) = self.created_before {
+            query = query.filter(created_at.lt(before));
+        }
+        query
+    }
+}
+
+pub struct AuditRepository;
+
+impl AuditRepository {
+    pub fn insert(conn: &mut PgConnection, event: NewAuditEvent) -> QueryResult<usize> {
+        diesel::insert_into(audit_events)
+            .values(event)
+            .execute(conn)
+    }
+
+    /// Newest events first.
+    pub fn find_page(
+        conn: &mut PgConnection,
+        filter: &AuditFilter,
+        page: i64,
+        size: i64,
+    ) -> QueryResult<(i64, Vec<AuditEvent>)> {
+        let page = page.max(1);
+        let size = size.max(1);
+        let offset = (page - 1) * size;
+
+        let total: i64 = filter
+            .apply(audit_events.into_boxed())
+            .select(count_star())
+            .first(conn)?;
+
+        let items = filter
+            .apply(audit_events.into_boxed())
+            .order((created_at.desc(), id.desc()))
+            .limit(size)
+            .offset(offset)
+            .select(AuditEvent::as_select())
+            .load(conn)?;
+
+        Ok((total, items))
+    }
+}
@@ -0,0 +1,184 @@
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        job_file::{JobFile, NewJobFile, NewJobFileChunk},
+        job_file_purpose::JobFilePurpose,
+    },
+    schema::{job_file_chunks, job_files::dsl::*},
+};
+
+pub struct JobFileRepository;
+
+impl JobFileRepository {
+    /// Stores the file's metadata with no content, replacing a file a failed
+    /// attempt left behind. Must run inside a transaction.
+    pub fn save(conn: &mut PgConnection, file: NewJobFile) -> QueryResult<()> {
+        let saved = diesel::insert_into(job_files)
+            .values(&file)
+            .on_conflict((job_id, purpose))
+            .do_update()
+            .set((
+                file_name.eq(excluded(file_name)),
+                content_type.eq(excluded(content_type)),
+                created_at.eq(diesel::dsl::now),
+            ))
+            .returning((job_id, purpose))
+            .get_result::<(Uuid, JobFilePurpose)>(conn)?;
+
+        diesel::delete(
+            job_file_chunks::table
+                .filter(job_file_chunks::job_id.eq(saved.0))
+                .filter(job_file_chunks::purpose.eq(saved.1)),
+        )
+        .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn append_chunk(conn: &mut PgConnection, chunk: NewJobFileChunk) -> QueryResult<usize> {
+        diesel::insert_into(job_file_chunks::table)
+            .values(&chunk)
+            .execute(conn)
+    }
+
+    pub fn find(
+        conn: &mut PgConnection,
+        job: Uuid,
+        file_purpose: JobFilePurpose,
+    ) -> QueryResult<JobFile> {
+        job_files.find((job, file_purpose)).first(conn)
+    }
+
+    /// Chunk `seq` of the file's content, `None` past the last one.
+    pub fn find_chunk(
+        conn: &mut PgConnection,
+        job: Uuid,
+        file_purpose: JobFilePurpose,
+        chunk_seq: i32,
+    ) -> QueryResult<Option<Vec<u8>>> {
+        job_file_chunks::table
+            .find((job, file_purpose, chunk_seq))
+            .select(job_file_chunks::data)
+            .first(conn)
+            .optional()
+    }
+
+    /// The file's whole content.
+    pub fn read(
+        conn: &mut PgConnection,
+        job: Uuid,
+        file_purpose: JobFilePurpose,
+    ) -> QueryResult<Vec<u8>> {
+        let chunks: Vec<Vec<u8>> = job_file_chunks::table
+            .filter(job_file_chunks::job_id.eq(job))
+            .filter(job_file_chunks::purpose.eq(file_purpose))
+            .order(job_file_chunks::seq.asc())
+            .select(job_file_chunks::data)
+            .load(conn)?;
+        Ok(chunks.concat())
+    }
+
+    /// Deletes the file with its content.
+    pub fn delete(
+        conn: &mut PgConnection,
+        job: Uuid,
+        file_purpose: JobFilePurpose,
+    ) -> QueryResult<usize> {
+        diesel::delete(job_files.find((job, file_purpose))).execute(conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::prelude::*;
+    use diesel::result::Error;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::JobFileRepository;
+    use crate::{
+        model::{
+            job::NewJob,
+            job_file::{NewJobFile, NewJobFileChunk},
+            job_file_purpose::JobFilePurpose,
+            job_kind::JobKind,
+        },
+        repository::job_repository::JobRepository,
+        service::db::test_connection,
+    };
+
+    const OUTPUT: JobFilePurpose = JobFilePurpose::Output;
+
+    fn save(conn: &mut PgConnection, job_id: Uuid, chunks: &[&[u8]]) {
+        JobFileRepository::save(
+            conn,
+            NewJobFile::new(job_id, OUTPUT, "people.csv".into(), "text/csv".into()),
+        )
+        .unwrap();
+        for (seq, data) in (0..).zip(chunks) {
+            JobFileRepository::append_chunk(conn, NewJobFileChunk::new(job_id, OUTPUT, seq, data))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn a_later_attempt_replaces_the_file() {
+        let mut conn = test_connection();
+        let job = JobRepository::create(
+            &mut conn,
+            NewJob::new(JobKind::PersonExport, json!({}), 1, None),
+        )
+        .unwrap();
+
+        save(&mut conn, *job.id(), &[b"fir", b"st"]);
+        save(&mut conn, *job.id(), &[b"second"]);
+
+        assert_eq!(
+            JobFileRepository::read(&mut conn, *job.id(), OUTPUT).unwrap(),
+            b"second"
+        );
+        assert!(matches!(
+            JobFileRepository::find(&mut conn, *job.id(), JobFilePurpose::Input),
+            Err(Error::NotFound)
+        ));
+
+        JobFileRepository::delete(&mut conn, *job.id(), OUTPUT).unwrap();
+        assert!(matches!(
+            JobFileRepository::find(&mut conn, *job.id(), OUTPUT),
+            Err(Error::NotFound)
+        ));
+        assert_eq!(
+            JobFileRepository::find_chunk(&mut conn, *job.id(), OUTPUT, 0).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn chunks_are_read_back_in_order() {
+        let mut conn = test_connection();
+        let job = JobRepository::create(
+            &mut conn,
+            NewJob::new(JobKind::PersonExport, json!({}), 1, None),
+        )
+        .unwrap();
+
+        save(&mut conn, *job.id(), &[b"id,", b"name\n", b"1,Ana\n"]);
+
+        assert_eq!(
+            JobFileRepository::read(&mut conn, *job.id(), OUTPUT).unwrap(),
+            b"id,name\n1,Ana\n"
+        );
+        assert_eq!(
+            JobFileRepository::find_chunk(&mut conn, *job.id(), OUTPUT, 1).unwrap(),
+            Some(b"name\n".to_vec())
+        );
+        assert_eq!(
+            JobFileRepository::find_chunk(&mut conn, *job.id(), OUTPUT, 3).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use diesel::dsl::{IntervalDsl, exists, now};
+use diesel::prelude::*;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        job::{Job, NewJob},
+        job_kind::JobKind,
+        job_status::JobStatus,
+    },
+    schema::jobs::dsl::*,
+};
+
+/// `last_error` of a job buried because its final attempt never reported back.
+const ABANDONED_ERROR: &str = "the worker running the final attempt stopped before it finished";
+
+pub struct JobRepository;
+
+impl JobRepository {
+    pub fn create(conn: &mut PgConnection, new_job: NewJob) -> QueryResult<Job> {
+        diesel::insert_into(jobs).values(&new_job).get_result(conn)
+    }
+
+    pub fn find_by_id(conn: &mut PgConnection, job_id: Uuid) -> QueryResult<Job> {
+        jobs.find(job_id).first(conn)
+    }
+
+    /// Whether a job of `job_kind` is waiting or running.
+    pub fn has_pending(conn: &mut PgConnection, job_kind: JobKind) -> QueryResult<bool> {
+        diesel::select(exists(
+            jobs.filter(kind.eq(job_kind))
+                .filter(status.eq_any([JobStatus::Queued, JobStatus::Running])),
+        ))
+        .get_result(conn)
+    }
+
+    /// Buries jobs left running longer than `lease_seconds` whose last
+    /// attempt was the final one, so [`JobRepository::claim_next`] does not
+    /// hand them out again. Returns how many were buried.
+    pub fn bury_abandoned(conn: &mut PgConnection, lease_seconds: i32) -> QueryResult<usize> {
+        diesel::update(
+            jobs.filter(status.eq(JobStatus::Running))
+                .filter(
+                    locked_at
+                        .assume_not_null()
+                        .lt(now - lease_seconds.seconds()),
+                )
+                .filter(attempts.ge(max_attempts)),
+        )
+        .set((
+            status.eq(JobStatus::Dead),
+            last_error.eq(ABANDONED_ERROR),
+            locked_at.eq(None::<chrono::NaiveDateTime>),
+            updated_at.eq(now),
+            finished_at.eq(now),
+        ))
+        .execute(conn)
+    }
+
+    /// Marks the next due job as running and starts its next attempt. Jobs
+    /// locked by another worker are skipped rather than waited for; a job
+    /// left running longer than `lease_seconds` is taken to belong to a
+    /// worker that died and is claimed again while it has attempts left.
+    /// Must run inside a transaction.
+    pub fn claim_next(conn: &mut PgConnection, lease_seconds: i32) -> QueryResult<Option<Job>> {
+        let due = status.eq(JobStatus::Queued).and(run_at.le(now));
+        let abandoned = status
+            .eq(JobStatus::Running)
+            .and(
+                locked_at
+                    .assume_not_null()
+                    .lt(now - lease_seconds.seconds()),
+            )
+            .and(attempts.lt(max_attempts));
+
+        let next = jobs
+            .filter(due.or(abandoned))
+            .order(run_at.asc())
+            .select(id)
+            .for_update()
+            .skip_locked()
+            .first::<Uuid>(conn)
+            .optional()?;
+
+        let Some(next) = next else {
+            return Ok(None);
+        };
+
+        diesel::update(jobs.find(next))
+            .set((
+                status.eq(JobStatus::Running),
+                attempts.eq(attempts + 1),
+                locked_at.eq(now),
+                updated_at.eq(now),
+            ))
+            .get_result(conn)
+            .map(Some)
+    }
+
+    /// Restarts the lease of `attempt` so the job is not taken for
+    /// abandoned while it is still being worked on. Returns 0 once the lease
+    /// was lost to another worker.
+    pub fn renew_lease(conn: &mut PgConnection, job_id: Uuid, attempt: i32) -> QueryResult<usize> {
+        diesel::update(jobs.find(job_id))
+            .filter(status.eq(JobStatus::Running))
+            .filter(attempts.eq(attempt))
+            .set((locked_at.eq(now), updated_at.eq(now)))
+            .execute(conn)
+    }
+
+    /// Like [`JobRepository::retry`] and [`JobRepository::bury`], only
+    /// applies while `attempt` still holds the job, returning `NotFound` once
+    /// its lease was lost to another worker.
+    pub fn complete(
+        conn: &mut PgConnection,
+        job_id: Uuid,
+        attempt: i32,
+        output: Value,
+    ) -> QueryResult<Job> {
+        diesel::update(jobs.find(job_id))
+            .filter(status.eq(JobStatus::Running))
+            .filter(attempts.eq(attempt))
+            .set((
+                status.eq(JobStatus::Succeeded),
+                result.eq(output),
+                locked_at.eq(None::<chrono::NaiveDateTime>),
+                updated_at.eq(now),
+                finished_at.eq(now),
+            ))
+            .get_result(conn)
+    }
+
+    /// Puts the job back in the queue, due in `delay_seconds`.
+    pub fn retry(
+        conn: &mut PgConnection,
+        job_id: Uuid,
+        attempt: i32,
+        error: &str,
+        delay_seconds: i32,
+    ) -> QueryResult<Job> {
+        diesel::update(jobs.find(job_id))
+            .filter(status.eq(JobStatus::Running))
+            .filter(attempts.eq(attempt))
+            .set((
+                status.eq(JobStatus::Queued),
+                run_at.eq(now + delay_seconds.seconds()),
+                last_error.eq(error),
+                locked_at.eq(None::<chrono::NaiveDateTime>),
+                updated_at.eq(now),
+            ))
+            .get_result(conn)
+    }
+
+    /// Gives up on the job, leaving it in the dead-letter state.
+    pub fn bury(
+        conn: &mut PgConnection,
+        job_id: Uuid,
+        attempt: i32,
+        error: &str,
+    ) -> QueryResult<Job> {
+        diesel::update(jobs.find(job_id))
+            .filter(status.eq(JobStatus::Running))
+            .filter(attempts.eq(attempt))
+            .set((
+                status.eq(JobStatus::Dead),
+                last_error.eq(error),
+                locked_at.eq(None::<chrono::NaiveDateTime>),
+                updated_at.eq(now),
+                finished_at.eq(now),
+            ))
+            .get_result(conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use diesel::prelude::*;
+    use serde_json::json;
+
+    use super::{ABANDONED_ERROR, JobRepository};
+    use crate::{
+        model::{
+            job::{Job, NewJob},
+            job_kind::JobKind,
+            job_status::JobStatus,
+        },
+        schema::jobs::dsl::{attempts, jobs, locked_at, status},
+        service::db::test_connection,
+    };
+
+    const LEASE: i32 = 60;
+
+    /// A connection on an empty queue.
+    fn queue() -> PgConnection {
+        let mut conn = test_connection();
+        diesel::delete(jobs).execute(&mut conn).unwrap();
+        conn
+    }
+
+    fn enqueue(conn: &mut PgConnection, max: i32) -> Job {
+        JobRepository::create(
+            conn,
+            NewJob::new(JobKind::PersonPurge, json!({}), max, None),
+        )
+        .unwrap()
+    }
+
+    /// Leaves `job` as if a worker had started attempt `attempt` two leases
+    /// ago and died.
+    fn abandon(conn: &mut PgConnection, job: &Job, attempt: i32) {
+        let started = Utc::now().naive_utc() - Duration::seconds(2 * i64::from(LEASE));
+        diesel::update(jobs.find(job.id()))
+            .set((
+                status.eq(JobStatus::Running),
+                attempts.eq(attempt),
+                locked_at.eq(started),
+            ))
+            .execute(conn)
+            .unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn claims_a_due_job_once() {
+        let mut conn = queue();
+        let job = enqueue(&mut conn, 3);
+
+        let claimed = JobRepository::claim_next(&mut conn, LEASE)
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.id(), job.id());
+        assert_eq!(claimed.status(), JobStatus::Running);
+        assert_eq!(claimed.attempts(), 1);
+
+        assert!(
+            JobRepository::claim_next(&mut conn, LEASE)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn retried_jobs_wait_for_their_delay() {
+        let mut conn = queue();
+        let job = enqueue(&mut conn, 3);
+        JobRepository::claim_next(&mut conn, LEASE).unwrap();
+
+        let retried = JobRepository::retry(&mut conn, *job.id(), 1, "boom", 30).unwrap();
+        assert_eq!(retried.status(), JobStatus::Queued);
+        assert_eq!(retried.last_error(), Some("boom"));
+        assert!(retried.locked_at().is_none());
+
+        assert!(
+            JobRepository::claim_next(&mut conn, LEASE)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn buried_jobs_are_dead_and_finished() {
+        let mut conn = queue();
+        let job = enqueue(&mut conn, 1);
+        JobRepository::claim_next(&mut conn, LEASE).unwrap();
+
+        let buried = JobRepository::bury(&mut conn, *job.id(), 1, "boom").unwrap();
+        assert_eq!(buried.status(), JobStatus::Dead);
+        assert!(buried.finished_at().is_some());
+
+        assert!(
+            JobRepository::claim_next(&mut conn, LEASE)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn a_lost_lease_cannot_finish_the_job() {
+        let mut conn = queue();
+        let job = enqueue(&mut conn, 3);
+        abandon(&mut conn, &job, 1);
+        JobRepository::claim_next(&mut conn, LEASE).unwrap();
+
+        let stale = JobRepository::complete(&mut conn, *job.id(), 1, json!({}));
+        assert!(matches!(stale, Err(diesel::result::Error::NotFound)));
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn a_renewed_lease_keeps_the_job_from_being_claimed_again() {
+        let mut conn = queue();
+        let job = enqueue(&mut conn, 3);
+        abandon(&mut conn, &job, 1);
+
+        assert_eq!(
+            JobRepository::renew_lease(&mut conn, *job.id(), 1).unwrap(),
+            1
+        );
+        assert!(
+            JobRepository::claim_next(&mut conn, LEASE)
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            JobRepository::renew_lease(&mut conn, *job.id(), 2).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn abandoned_jobs_with_attempts_left_are_claimed_again() {
+        let mut conn = queue();
+        let job = enqueue(&mut conn, 3);
+        abandon(&mut conn, &job, 2);
+
+        assert_eq!(JobRepository::bury_abandoned(&mut conn, LEASE).unwrap(), 0);
+        let claimed = JobRepository::claim_next(&mut conn, LEASE)
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.id(), job.id());
+        assert_eq!(claimed.attempts(), 3);
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn abandoned_jobs_on_their_final_attempt_are_buried() {
+        let mut conn = queue();
+        let job = enqueue(&mut conn, 3);
+        abandon(&mut conn, &job, 3);
+
+        assert!(
+            JobRepository::claim_next(&mut conn, LEASE)
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(JobRepository::bury_abandoned(&mut conn, LEASE).unwrap(), 1);
+
+        let buried = JobRepository::find_by_id(&mut conn, *job.id()).unwrap();
+        assert_eq!(buried.status(), JobStatus::Dead);
+        assert_eq!(buried.attempts(), 3);
+        assert_eq!(buried.last_error(), Some(ABANDONED_ERROR));
+    }
+}
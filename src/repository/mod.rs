@@ -1,2 +1,6 @@
+pub mod audit_repository;
+pub mod job_file_repository;
+pub mod job_repository;
 pub mod person_repository;
+pub mod query;
 pub mod user_repository;
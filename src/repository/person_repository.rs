@@ -1,48 +1,155 @@
-use diesel::dsl::count_star;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use diesel::dsl::{IntervalDsl, count_star, now};
+use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::sql_types::{Nullable, Timestamp};
 use uuid::Uuid;
 
 use crate::{
-    model::person::{NewPerson, Person, UpdateCpf, UpdateName, UpdatePerson},
-    schema::persons::dsl::*,
-    service::db::DbPool,
+    model::{
+        cpf::Cpf,
+        person::{NewPerson, Person, UpdateCpf, UpdateName, UpdatePerson},
+        person_version::PersonVersion,
+    },
+    repository::query::{
+        SortDirection, SortOrder, WordSimilarTo, contains_pattern, declare_cursor,
+        immutable_unaccent, word_similarity,
+    },
+    schema::persons::{self, dsl::*},
+    schema::persons_history,
 };
 
-pub struct PersonRepository;
+/// Columns accepted by `?sort=` on the person listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersonSortColumn {
+    Name,
+    Cpf,
+    BirthDate,
+    CreatedAt,
+    UpdatedAt,
+}
 
-impl PersonRepository {
-    pub fn create(
-        pool: &DbPool,
-        name_value: String,
-        cpf_value: String,
-    ) -> Result<Person, diesel::result::Error> {
-        let mut conn = pool.get().expect("DB connection");
+impl FromStr for PersonSortColumn {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(PersonSortColumn::Name),
+            "cpf" => Ok(PersonSortColumn::Cpf),
+            "birth_date" => Ok(PersonSortColumn::BirthDate),
+            "created_at" => Ok(PersonSortColumn::CreatedAt),
+            "updated_at" => Ok(PersonSortColumn::UpdatedAt),
+            _ => Err(()),
+        }
+    }
+}
 
-        let new_person = NewPerson::new(name_value, cpf_value);
+#[derive(Debug, Default)]
+pub struct PersonFilter {
+    /// Matched anywhere in the name, ignoring case and accents.
+    pub name: Option<String>,
+    pub cpf: Option<Cpf>,
+    pub created_after: Option<NaiveDateTime>,
+    /// Also list soft-deleted people (admins only).
+    pub include_deleted: bool,
+    pub sort: Vec<SortOrder<PersonSortColumn>>,
+}
+
+impl PersonFilter {
+    fn apply<'a>(&self, mut query: persons::BoxedQuery<'a, Pg>) -> persons::BoxedQuery<'a, Pg> {
+        if !self.include_deleted {
+            query = query.filter(deleted_at.is_null());
+        }
+        if let Some(fragment) = &self.name {
+            query = query.filter(
+                immutable_unaccent(name).ilike(immutable_unaccent(contains_pattern(fragment))),
+            );
+        }
+        if let Some(value) = &self.cpf {
+            query = query.filter(cpf.eq(value.clone()));
+        }
+        if let Some(after) = self.created_after {
+            query = query.filter(created_at.ge(after));
+        }
+        query
+    }
 
+    fn order<'a>(&self, mut query: persons::BoxedQuery<'a, Pg>) -> persons::BoxedQuery<'a, Pg> {
+        for order in &self.sort {
+            query = match (order.column, order.direction) {
+                (PersonSortColumn::Name, SortDirection::Asc) => query.then_order_by(name.asc()),
+                (PersonSortColumn::Name, SortDirection::Desc) => query.then_order_by(name.desc()),
+                (PersonSortColumn::Cpf, SortDirection::Asc) => query.then_order_by(cpf.asc()),
+                (PersonSortColumn::Cpf, SortDirection::Desc) => query.then_order_by(cpf.desc()),
+                (PersonSortColumn::BirthDate, SortDirection::Asc) => {
+                    query.then_order_by(birth_date.asc())
+                }
+                (PersonSortColumn::BirthDate, SortDirection::Desc) => {
+                    query.then_order_by(birth_date.desc())
+                }
+                (PersonSortColumn::CreatedAt, SortDirection::Asc) => {
+                    query.then_order_by(created_at.asc())
+                }
+                (PersonSortColumn::CreatedAt, SortDirection::Desc) => {
+                    query.then_order_by(created_at.desc())
+                }
+                (PersonSortColumn::UpdatedAt, SortDirection::Asc) => {
+                    query.then_order_by(updated_at.asc())
+                }
+                (PersonSortColumn::UpdatedAt, SortDirection::Desc) => {
+                    query.then_order_by(updated_at.desc())
+                }
+            };
+        }
+        // keeps pages stable when the requested columns have ties
+        query.then_order_by(id.asc())
+    }
+}
+
+/// The live person `person_id`, only while it is at `expected_version`.
+#[diesel::dsl::auto_type]
+fn live_at_version(person_id: Uuid, expected_version: i32) -> _ {
+    persons::table
+        .find(person_id)
+        .filter(deleted_at.is_null())
+        .filter(version.eq(expected_version))
+}
+
+/// Soft-deleted people are invisible to every query unless it says otherwise.
+pub struct PersonRepository;
+
+impl PersonRepository {
+    pub fn create(conn: &mut PgConnection, new_person: NewPerson) -> QueryResult<Person> {
         diesel::insert_into(persons)
             .values(&new_person)
-            .get_result(&mut conn)
+            .get_result(conn)
     }
 
     pub fn find_all(conn: &mut PgConnection) -> Result<Vec<Person>, diesel::result::Error> {
-        persons.load::<Person>(conn)
+        persons.filter(deleted_at.is_null()).load::<Person>(conn)
     }
 
     pub fn find_page(
         conn: &mut PgConnection,
+        filter: &PersonFilter,
         page: i64,
         per_page: i64,
     ) -> QueryResult<(i64, Vec<Person>)> {
         let page = page.max(1);
         let per_page = per_page.max(1);
 
-        let total: i64 = persons.select(count_star()).first(conn)?;
+        let total: i64 = filter
+            .apply(persons.into_boxed())
+            .select(count_star())
+            .first(conn)?;
 
         let offset = (page - 1) * per_page;
 
-        let items = persons
-            .order(id.asc())
+        let items = filter
+            .order(filter.apply(persons.into_boxed()))
             .limit(per_page)
             .offset(offset)
             .load::<Person>(conn)?;
@@ -50,48 +157,454 @@ impl PersonRepository {
         Ok((total, items))
     }
 
-    pub fn find_by_id(conn: &mut PgConnection, person_id: Uuid) -> QueryResult<Person> {
-        persons.find(person_id).first::<Person>(conn)
+    /// Walks every person matching `filter`, in the listing's order, through a
+    /// server-side cursor so only `batch_size` rows are held at a time. Stops
+    /// early when `f` breaks. Must run inside a transaction.
+    pub fn for_each_batch<F, E>(
+        conn: &mut PgConnection,
+        filter: &PersonFilter,
+        batch_size: i64,
+        mut f: F,
+    ) -> Result<(), E>
+    where
+        F: FnMut(Vec<Person>) -> Result<ControlFlow<()>, E>,
+        E: From<diesel::result::Error>,
+    {
+        const CURSOR: &str = "person_export";
+
+        declare_cursor(CURSOR, filter.order(filter.apply(persons.into_boxed()))).execute(conn)?;
+        let fetch = format!("FETCH FORWARD {batch_size} FROM {CURSOR}");
+
+        loop {
+            let batch = diesel::sql_query(&fetch).load::<Person>(conn)?;
+            let last = batch.len() < batch_size as usize;
+            if f(batch)?.is_break() || last {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Fuzzy, accent-insensitive name search ranked by trigram word similarity.
+    pub fn search(
+        conn: &mut PgConnection,
+        term: &str,
+        page: i64,
+        per_page: i64,
+    ) -> QueryResult<(i64, Vec<(Person, f32)>)> {
+        let page = page.max(1);
+        let per_page = per_page.max(1);
+        let offset = (page - 1) * per_page;
+
+        let needle = || immutable_unaccent(term.to_string());
+        let matches = || WordSimilarTo::new(needle(), immutable_unaccent(name));
+        let score = || word_similarity(needle(), immutable_unaccent(name));
+
+        let total: i64 = persons
+            .filter(deleted_at.is_null())
+            .filter(matches())
+            .select(count_star())
+            .first(conn)?;
+
+        let items = persons
+            .filter(deleted_at.is_null())
+            .filter(matches())
+            .select((persons::all_columns, score()))
+            .order((score().desc(), name.asc(), id.asc()))
+            .limit(per_page)
+            .offset(offset)
+            .load::<(Person, f32)>(conn)?;
+
+        Ok((total, items))
+    }
+
+    pub fn find_by_id(
+        conn: &mut PgConnection,
+        person_id: Uuid,
+        include_deleted: bool,
+    ) -> QueryResult<Person> {
+        let mut query = persons.find(person_id).into_boxed();
+        if !include_deleted {
+            query = query.filter(deleted_at.is_null());
+        }
+        query.first::<Person>(conn)
     }
 
-    pub fn delete_person(conn: &mut PgConnection, person_id: Uuid) -> QueryResult<Person> {
-        diesel::delete(persons.find(person_id)).get_result(conn)
+    /// Loads the live row and locks it until the surrounding transaction ends.
+    pub fn lock_by_id(conn: &mut PgConnection, person_id: Uuid) -> QueryResult<Person> {
+        persons
+            .find(person_id)
+            .filter(deleted_at.is_null())
+            .for_update()
+            .first::<Person>(conn)
     }
 
-    pub fn update_person(
+    /// Every recorded version, newest first. Empty if the person never existed.
+    pub fn history(conn: &mut PgConnection, person_id: Uuid) -> QueryResult<Vec<PersonVersion>> {
+        persons_history::table
+            .filter(persons_history::person_id.eq(person_id))
+            .order(persons_history::version.desc())
+            .select(PersonVersion::as_select())
+            .load(conn)
+    }
+
+    /// The person as it was at `at`, rebuilt from the version current then.
+    pub fn find_as_of(
         conn: &mut PgConnection,
         person_id: Uuid,
-        new_name: String,
-        new_cpf: String,
+        at: NaiveDateTime,
+    ) -> QueryResult<Person> {
+        use persons_history::dsl as h;
+
+        h::persons_history
+            .filter(h::person_id.eq(person_id))
+            .filter(h::valid_from.le(at))
+            .filter(h::valid_to.is_null().or(h::valid_to.gt(at)))
+            .order(h::version.desc())
+            .select((
+                h::person_id,
+                h::name,
+                h::cpf,
+                h::birth_date,
+                h::email,
+                h::phone,
+                h::created_at,
+                h::updated_at,
+                h::created_by,
+                h::updated_by,
+                // versions only exist while the person is live
+                None::<NaiveDateTime>.into_sql::<Nullable<Timestamp>>(),
+                None::<Uuid>.into_sql::<Nullable<diesel::sql_types::Uuid>>(),
+                // history numbering, not the row version; as-of reads carry no ETag
+                h::version,
+            ))
+            .first::<Person>(conn)
+    }
+
+    /// With `include_deleted`, a live person wins over deleted ones sharing
+    /// the CPF, then the most recently deleted.
+    pub fn find_by_cpf(
+        conn: &mut PgConnection,
+        person_cpf: &Cpf,
+        include_deleted: bool,
+    ) -> QueryResult<Person> {
+        let mut query = persons.filter(cpf.eq(person_cpf.clone())).into_boxed();
+        if !include_deleted {
+            query = query.filter(deleted_at.is_null());
+        }
+        query
+            .order(deleted_at.desc().nulls_first())
+            .first::<Person>(conn)
+    }
+
+    /// The subset of `cpfs` already held by a live person, i.e. the ones
+    /// `persons_cpf_unique` would reject.
+    pub fn existing_cpfs(conn: &mut PgConnection, cpfs: &[Cpf]) -> QueryResult<Vec<Cpf>> {
+        let mut existing = Vec::new();
+        // stays well below Postgres' limit of 65535 bind parameters
+        for chunk in cpfs.chunks(10_000) {
+            existing.extend(
+                persons
+                    .filter(deleted_at.is_null())
+                    .filter(cpf.eq_any(chunk))
+                    .select(cpf)
+                    .load::<Cpf>(conn)?,
+            );
+        }
+        Ok(existing)
+    }
+
+    /// Soft-deletes the person if it is still at `expected_version`;
+    /// `NotFound` otherwise.
+    pub fn delete_person(
+        conn: &mut PgConnection,
+        person_id: Uuid,
+        expected_version: i32,
+        actor: Option<Uuid>,
+    ) -> QueryResult<Person> {
+        diesel::update(live_at_version(person_id, expected_version))
+            .set((
+                deleted_at.eq(now),
+                deleted_by.eq(actor),
+                version.eq(version + 1),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn restore_person(
+        conn: &mut PgConnection,
+        person_id: Uuid,
+        actor: Option<Uuid>,
     ) -> QueryResult<Person> {
-        let changes = UpdatePerson::new(new_name, new_cpf);
+        diesel::update(persons.find(person_id).filter(deleted_at.is_not_null()))
+            .set((
+                deleted_at.eq(None::<NaiveDateTime>),
+                deleted_by.eq(None::<Uuid>),
+                updated_by.eq(actor),
+                version.eq(version + 1),
+            ))
+            .get_result(conn)
+    }
 
-        diesel::update(persons.find(person_id))
-            .set(&changes)
+    /// Permanently removes people soft-deleted more than `retention_days` ago,
+    /// measured by the database clock like `deleted_at` itself.
+    pub fn purge_deleted(conn: &mut PgConnection, retention_days: i32) -> QueryResult<Vec<Person>> {
+        diesel::delete(persons.filter(deleted_at.lt((now - retention_days.days()).nullable())))
+            .get_results(conn)
+    }
+
+    /// Like every `update_*` below, only applies while the person is still at
+    /// `expected_version`, returning `NotFound` otherwise, and bumps the version.
+    pub fn update_person(
+        conn: &mut PgConnection,
+        person_id: Uuid,
+        expected_version: i32,
+        changes: UpdatePerson,
+    ) -> QueryResult<Person> {
+        diesel::update(live_at_version(person_id, expected_version))
+            .set((&changes, version.eq(version + 1)))
             .get_result::<Person>(conn)
     }
 
     pub fn update_name(
         conn: &mut PgConnection,
         person_id: Uuid,
+        expected_version: i32,
         new_name: String,
+        actor: Option<Uuid>,
     ) -> QueryResult<Person> {
-        let changes = UpdateName::new(new_name);
+        let changes = UpdateName::new(new_name, actor);
 
-        diesel::update(persons.find(person_id))
-            .set(&changes)
+        diesel::update(live_at_version(person_id, expected_version))
+            .set((&changes, version.eq(version + 1)))
             .get_result::<Person>(conn)
     }
 
     pub fn update_cpf(
         conn: &mut PgConnection,
         person_id: Uuid,
-        new_cpf: String,
+        expected_version: i32,
+        new_cpf: Cpf,
+        actor: Option<Uuid>,
     ) -> QueryResult<Person> {
-        let changes = UpdateCpf::new(new_cpf);
+        let changes = UpdateCpf::new(new_cpf, actor);
 
-        diesel::update(persons.find(person_id))
-            .set(&changes)
+        diesel::update(live_at_version(person_id, expected_version))
+            .set((&changes, version.eq(version + 1)))
             .get_result::<Person>(conn)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use diesel::prelude::*;
+    use uuid::Uuid;
+
+    use super::PersonRepository;
+    use crate::{
+        bootstrap::http_server::testing::fresh_cpf,
+        model::person::{NewPerson, Person},
+        schema::users,
+        service::db::test_connection,
+    };
+
+    fn named(conn: &mut PgConnection, person_name: &str) -> Person {
+        let new_person = NewPerson::new(person_name.into(), fresh_cpf(), None, None, None, None);
+        PersonRepository::create(conn, new_person).unwrap()
+    }
+
+    fn found(conn: &mut PgConnection, term: &str, person: &Person) -> Option<f32> {
+        let (_, matches) = PersonRepository::search(conn, term, 1, 100).unwrap();
+        matches
+            .into_iter()
+            .find(|(p, _)| p.id() == person.id())
+            .map(|(_, score)| score)
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn search_ignores_accents_and_case() {
+        let mut conn = test_connection();
+        let person = named(&mut conn, "Zuleica Quixadá Conceição");
+
+        let score = found(&mut conn, "QUIXADA CONCEICAO", &person).unwrap();
+        assert!(score > 0.9, "{score}");
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn search_tolerates_typos_and_ranks_closer_names_first() {
+        let mut conn = test_connection();
+        let exact = named(&mut conn, "Zuleica Quixadá");
+        let other = named(&mut conn, "Zuleide Quintas");
+
+        let exact_score = found(&mut conn, "zuleika quixada", &exact).unwrap();
+        let other_score = found(&mut conn, "zuleika quixada", &other).unwrap_or(0.0);
+        assert!(exact_score > other_score, "{exact_score} <= {other_score}");
+        assert_eq!(found(&mut conn, "Wagner", &exact), None);
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn search_skips_deleted_people() {
+        let mut conn = test_connection();
+        let person = named(&mut conn, "Zuleica Quixadá");
+        PersonRepository::delete_person(&mut conn, *person.id(), person.version(), None).unwrap();
+
+        assert_eq!(found(&mut conn, "zuleica quixada", &person), None);
+    }
+
+    /// Moves the start of every recorded version back, as if the person had
+    /// been created `hours` ago; `NOW()` is fixed inside the test transaction.
+    fn backdate_history(conn: &mut PgConnection, person: &Person, hours: i32) {
+        diesel::sql_query(
+            "UPDATE persons_history SET valid_from = valid_from - make_interval(hours => $1) \
+             WHERE person_id = $2",
+        )
+        .bind::<diesel::sql_types::Integer, _>(hours)
+        .bind::<diesel::sql_types::Uuid, _>(person.id())
+        .execute(conn)
+        .unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn every_change_is_kept_as_a_version() {
+        let mut conn = test_connection();
+        let person = named(&mut conn, "Ana");
+        let renamed = PersonRepository::update_name(
+            &mut conn,
+            *person.id(),
+            person.version(),
+            "Ana Souza".into(),
+            None,
+        )
+        .unwrap();
+        // unchanged values do not make a new version
+        PersonRepository::update_name(
+            &mut conn,
+            *person.id(),
+            renamed.version(),
+            "Ana Souza".into(),
+            None,
+        )
+        .unwrap();
+
+        let versions = PersonRepository::history(&mut conn, *person.id()).unwrap();
+        let names: Vec<_> = versions.iter().map(|v| (v.version(), v.name())).collect();
+        assert_eq!(names, [(2, "Ana Souza"), (1, "Ana")]);
+        assert!(versions[0].valid_to().is_none());
+        assert!(versions[1].valid_to().is_some());
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn as_of_returns_the_version_current_then() {
+        let mut conn = test_connection();
+        let person = named(&mut conn, "Ana");
+        backdate_history(&mut conn, &person, 2);
+        PersonRepository::update_name(
+            &mut conn,
+            *person.id(),
+            person.version(),
+            "Ana Souza".into(),
+            None,
+        )
+        .unwrap();
+        let history = PersonRepository::history(&mut conn, *person.id()).unwrap();
+        // the database clock, which stamped the rename
+        let renamed_at = *history[0].valid_from();
+        let at = |hours_ago| renamed_at - chrono::Duration::hours(hours_ago);
+
+        let then = PersonRepository::find_as_of(&mut conn, *person.id(), at(1)).unwrap();
+        assert_eq!(then.name(), "Ana");
+        let current = PersonRepository::find_as_of(&mut conn, *person.id(), at(-1)).unwrap();
+        assert_eq!(current.name(), "Ana Souza");
+        assert!(matches!(
+            PersonRepository::find_as_of(&mut conn, *person.id(), at(3)),
+            Err(diesel::result::Error::NotFound)
+        ));
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn deleted_people_are_hidden_until_restored() {
+        let mut conn = test_connection();
+        let person = named(&mut conn, "Ana");
+        // the seeded admin
+        let actor = users::table
+            .select(users::id)
+            .first::<Uuid>(&mut conn)
+            .optional()
+            .unwrap();
+
+        let deleted =
+            PersonRepository::delete_person(&mut conn, *person.id(), person.version(), actor)
+                .unwrap();
+        assert_eq!(deleted.deleted_by(), actor);
+        assert!(PersonRepository::find_by_id(&mut conn, *person.id(), false).is_err());
+        assert!(PersonRepository::find_by_id(&mut conn, *person.id(), true).is_ok());
+
+        let restored = PersonRepository::restore_person(&mut conn, *person.id(), actor).unwrap();
+        assert!(restored.deleted_at().is_none());
+        assert_eq!(restored.version(), person.version() + 2);
+        assert!(PersonRepository::find_by_id(&mut conn, *person.id(), false).is_ok());
+
+        // only deleted people can be restored
+        assert!(PersonRepository::restore_person(&mut conn, *person.id(), actor).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn purge_only_removes_people_deleted_past_the_retention() {
+        let mut conn = test_connection();
+        let recent = named(&mut conn, "Ana");
+        let old = named(&mut conn, "Bia");
+        let live = named(&mut conn, "Caio");
+        for person in [&recent, &old] {
+            PersonRepository::delete_person(&mut conn, *person.id(), person.version(), None)
+                .unwrap();
+        }
+        diesel::sql_query(
+            "UPDATE persons SET deleted_at = deleted_at - interval '31 days' WHERE id = $1",
+        )
+        .bind::<diesel::sql_types::Uuid, _>(old.id())
+        .execute(&mut conn)
+        .unwrap();
+
+        let purged = PersonRepository::purge_deleted(&mut conn, 30).unwrap();
+
+        assert!(purged.iter().any(|p| p.id() == old.id()));
+        assert!(purged.iter().all(|p| p.id() != recent.id()));
+        assert!(PersonRepository::find_by_id(&mut conn, *old.id(), true).is_err());
+        assert!(PersonRepository::find_by_id(&mut conn, *recent.id(), true).is_ok());
+        assert!(PersonRepository::find_by_id(&mut conn, *live.id(), false).is_ok());
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn updates_only_apply_at_the_expected_version() {
+        let mut conn = test_connection();
+        let person = named(&mut conn, "Ana");
+
+        let renamed = PersonRepository::update_name(
+            &mut conn,
+            *person.id(),
+            person.version(),
+            "Bia".into(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(renamed.version(), person.version() + 1);
+
+        let stale = PersonRepository::update_name(
+            &mut conn,
+            *person.id(),
+            person.version(),
+            "Caio".into(),
+            None,
+        );
+        assert!(matches!(stale, Err(diesel::result::Error::NotFound)));
+        let current = PersonRepository::find_by_id(&mut conn, *person.id(), false).unwrap();
+        assert_eq!(current.name(), "Bia");
+    }
+}
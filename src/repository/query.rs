@@ -0,0 +1,159 @@
+use std::str::FromStr;
+
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::query_builder::{AstPass, QueryFragment, QueryId};
+use diesel::sql_types::Text;
+
+diesel::define_sql_function! {
+    /// `unaccent()` wrapper declared `IMMUTABLE` so it can back indexes.
+    fn immutable_unaccent(x: Text) -> Text;
+}
+
+diesel::define_sql_function! {
+    /// pg_trgm similarity between `needle` and the best matching part of `haystack`.
+    fn word_similarity(needle: Text, haystack: Text) -> Float;
+}
+
+// pg_trgm `needle <% haystack`: word_similarity above `pg_trgm.word_similarity_threshold`
+diesel::infix_operator!(WordSimilarTo, " <% ", backend: diesel::pg::Pg);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// One `column` / `-column` entry from a `?sort=` parameter.
+#[derive(Debug, Clone, Copy)]
+pub struct SortOrder<C> {
+    pub column: C,
+    pub direction: SortDirection,
+}
+
+/// Parses `?sort=created_at,-email` into sort orders.
+///
+/// Only columns accepted by `C::from_str` are allowed, so every repository
+/// keeps its own whitelist of sortable columns. The error is the first
+/// unknown column name.
+pub fn parse_sort<C: FromStr>(sort: &str) -> Result<Vec<SortOrder<C>>, String> {
+    sort.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let (direction, name) = match s.strip_prefix('-') {
+                Some(name) => (SortDirection::Desc, name),
+                None => (SortDirection::Asc, s.strip_prefix('+').unwrap_or(s)),
+            };
+
+            name.parse::<C>()
+                .map(|column| SortOrder { column, direction })
+                .map_err(|_| name.to_string())
+        })
+        .collect()
+}
+
+/// Builds an `ILIKE` pattern matching `value` anywhere, escaping `%`, `_` and `\`.
+pub fn contains_pattern(value: &str) -> String {
+    let mut pattern = String::with_capacity(value.len() + 2);
+    pattern.push('%');
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// `DECLARE <name> NO SCROLL CURSOR FOR <query>`, keeping the query's bind
+/// parameters. Rows are then read with `FETCH`; the cursor only lives until
+/// the surrounding transaction ends.
+#[derive(QueryId)]
+pub struct DeclareCursor<Q> {
+    name: &'static str,
+    query: Q,
+}
+
+pub fn declare_cursor<Q>(name: &'static str, query: Q) -> DeclareCursor<Q> {
+    DeclareCursor { name, query }
+}
+
+impl<Q: QueryFragment<Pg>> QueryFragment<Pg> for DeclareCursor<Q> {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+        out.push_sql("DECLARE ");
+        out.push_identifier(self.name)?;
+        out.push_sql(" NO SCROLL CURSOR FOR ");
+        self.query.walk_ast(out.reborrow())
+    }
+}
+
+impl<Q> RunQueryDsl<PgConnection> for DeclareCursor<Q> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Column {
+        Name,
+        Email,
+    }
+
+    impl FromStr for Column {
+        type Err = ();
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "name" => Ok(Column::Name),
+                "email" => Ok(Column::Email),
+                _ => Err(()),
+            }
+        }
+    }
+
+    fn orders(sort: &str) -> Result<Vec<(Column, SortDirection)>, String> {
+        parse_sort::<Column>(sort).map(|orders| {
+            orders
+                .into_iter()
+                .map(|o| (o.column, o.direction))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn parses_directions_in_order() {
+        assert_eq!(
+            orders("-email,name").unwrap(),
+            [
+                (Column::Email, SortDirection::Desc),
+                (Column::Name, SortDirection::Asc)
+            ]
+        );
+        assert_eq!(
+            orders("+name").unwrap(),
+            [(Column::Name, SortDirection::Asc)]
+        );
+    }
+
+    #[test]
+    fn skips_blank_entries() {
+        assert_eq!(
+            orders(" name , ,").unwrap(),
+            [(Column::Name, SortDirection::Asc)]
+        );
+        assert!(orders("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn names_the_first_unknown_column() {
+        assert_eq!(orders("name,-password,cpf"), Err("password".to_string()));
+    }
+
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(contains_pattern("50%_a\\b"), "%50\\%\\_a\\\\b%");
+    }
+}
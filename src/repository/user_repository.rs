@@ -1,15 +1,111 @@
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
 use diesel::dsl::count_star;
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use uuid::Uuid;
 
 use crate::{
     model::{
         role::Role,
-        user::{NewUser, UpdateEmail, UpdatePassword, UpdateRole, UpdateUser, User},
+        user::{
+            NewUser, PatchUser, UpdateEmail, UpdatePassword, UpdateRole, UpdateStatus, UpdateUser,
+            User,
+        },
+        user_status::UserStatus,
     },
-    schema::users::dsl::*,
+    repository::query::{SortDirection, SortOrder, contains_pattern},
+    schema::users::{self, dsl::*},
 };
 
+/// Columns accepted by `?sort=` on the user listing.
+#[derive(Debug, Clone, Copy)]
+pub enum UserSortColumn {
+    Id,
+    Email,
+    Role,
+    Status,
+    CreatedAt,
+}
+
+impl FromStr for UserSortColumn {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(UserSortColumn::Id),
+            "email" => Ok(UserSortColumn::Email),
+            "role" => Ok(UserSortColumn::Role),
+            "status" => Ok(UserSortColumn::Status),
+            "created_at" => Ok(UserSortColumn::CreatedAt),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct UserFilter {
+    pub role: Option<Role>,
+    pub email_contains: Option<String>,
+    pub created_after: Option<NaiveDateTime>,
+    pub created_before: Option<NaiveDateTime>,
+    pub status: Option<UserStatus>,
+    pub sort: Vec<SortOrder<UserSortColumn>>,
+}
+
+impl UserFilter {
+    fn apply<'a>(&self, mut query: users::BoxedQuery<'a, Pg>) -> users::BoxedQuery<'a, Pg> {
+        if let Some(r) = self.role {
+            query = query.filter(role.eq(r));
+        }
+        if let Some(fragment) = &self.email_contains {
+            query = query.filter(email.ilike(contains_pattern(fragment)));
+        }
+        if let Some(after) = self.created_after {
+            query = query.filter(created_at.ge(after));
+        }
+        if let Some(before) = self.created_before {
+            query = query.filter(created_at.lt(before));
+        }
+        if let Some(s) = self.status {
+            query = query.filter(status.eq(s));
+        }
+        query
+    }
+
+    fn order<'a>(&self, mut query: users::BoxedQuery<'a, Pg>) -> users::BoxedQuery<'a, Pg> {
+        for order in &self.sort {
+            query = match (order.column, order.direction) {
+                (UserSortColumn::Id, SortDirection::Asc) => query.then_order_by(id.asc()),
+                (UserSortColumn::Id, SortDirection::Desc) => query.then_order_by(id.desc()),
+                (UserSortColumn::Email, SortDirection::Asc) => query.then_order_by(email.asc()),
+                (UserSortColumn::Email, SortDirection::Desc) => query.then_order_by(email.desc()),
+                (UserSortColumn::Role, SortDirection::Asc) => query.then_order_by(role.asc()),
+                (UserSortColumn::Role, SortDirection::Desc) => query.then_order_by(role.desc()),
+                (UserSortColumn::Status, SortDirection::Asc) => query.then_order_by(status.asc()),
+                (UserSortColumn::Status, SortDirection::Desc) => query.then_order_by(status.desc()),
+                (UserSortColumn::CreatedAt, SortDirection::Asc) => {
+                    query.then_order_by(created_at.asc())
+                }
+                (UserSortColumn::CreatedAt, SortDirection::Desc) => {
+                    query.then_order_by(created_at.desc())
+                }
+            };
+        }
+        // keeps pages stable when the requested columns have ties
+        query.then_order_by(id.asc())
+    }
+}
+
+/// The user `user_id`, only while it is at `expected_version`.
+#[diesel::dsl::auto_type]
+fn at_version(user_id: Uuid, expected_version: i32) -> _ {
+    users::table
+        .find(user_id)
+        .filter(version.eq(expected_version))
+}
+
 pub struct UserRepository;
 
 impl UserRepository {
@@ -17,6 +113,15 @@ impl UserRepository {
         users.find(user_id).first::<User>(conn)
     }
 
+    pub fn find_status(conn: &mut PgConnection, user_id: Uuid) -> QueryResult<UserStatus> {
+        users.find(user_id).select(status).first(conn)
+    }
+
+    /// Loads the row and locks it until the surrounding transaction ends.
+    pub fn lock_by_id(conn: &mut PgConnection, user_id: Uuid) -> QueryResult<User> {
+        users.find(user_id).for_update().first::<User>(conn)
+    }
+
     pub fn find_by_email(conn: &mut PgConnection, user_email: &str) -> QueryResult<User> {
         users
             .filter(email.eq(user_email))
@@ -24,12 +129,21 @@ impl UserRepository {
             .first(conn)
     }
 
-    pub fn insert(conn: &mut PgConnection, new_user: NewUser) -> QueryResult<usize> {
-        diesel::insert_into(users).values(new_user).execute(conn)
+    pub fn insert(conn: &mut PgConnection, new_user: NewUser) -> QueryResult<User> {
+        diesel::insert_into(users)
+            .values(new_user)
+            .returning(User::as_returning())
+            .get_result(conn)
     }
 
-    pub fn delete_user(conn: &mut PgConnection, user_id: Uuid) -> QueryResult<User> {
-        diesel::delete(users.find(user_id)).get_result(conn)
+    /// Deletes the user if it is still at `expected_version`; `NotFound`
+    /// otherwise.
+    pub fn delete_user(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        expected_version: i32,
+    ) -> QueryResult<User> {
+        diesel::delete(at_version(user_id, expected_version)).get_result(conn)
     }
 
     pub fn find_all(conn: &mut PgConnection) -> Result<Vec<User>, diesel::result::Error> {
@@ -38,6 +152,7 @@ impl UserRepository {
 
     pub fn find_page(
         conn: &mut PgConnection,
+        filter: &UserFilter,
         page: i64,
         size: i64,
     ) -> QueryResult<(i64, Vec<User>)> {
@@ -45,10 +160,13 @@ impl UserRepository {
         let size = size.max(1);
         let offset = (page - 1) * size;
 
-        let total: i64 = users.select(count_star()).first(conn)?;
+        let total: i64 = filter
+            .apply(users.into_boxed())
+            .select(count_star())
+            .first(conn)?;
 
-        let items = users
-            .order(id.asc())
+        let items = filter
+            .order(filter.apply(users.into_boxed()))
             .limit(size)
             .offset(offset)
             .load::<User>(conn)?;
@@ -56,53 +174,83 @@ impl UserRepository {
         Ok((total, items))
     }
 
+    /// Like every `update_*` below, only applies while the user is still at
+    /// `expected_version`, returning `NotFound` otherwise, and bumps the version.
     pub fn update_user(
         conn: &mut PgConnection,
         user_id: Uuid,
+        expected_version: i32,
         new_email: String,
         new_role: Role,
         new_password_hash: String,
     ) -> QueryResult<User> {
         let changes = UpdateUser::new(new_email, new_role, new_password_hash);
 
-        diesel::update(users.find(user_id))
-            .set(&changes)
+        diesel::update(at_version(user_id, expected_version))
+            .set((&changes, version.eq(version + 1)))
+            .get_result::<User>(conn)
+    }
+
+    pub fn patch_user(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        expected_version: i32,
+        changes: PatchUser,
+    ) -> QueryResult<User> {
+        diesel::update(at_version(user_id, expected_version))
+            .set((&changes, version.eq(version + 1)))
             .get_result::<User>(conn)
     }
 
     pub fn update_email(
         conn: &mut PgConnection,
         user_id: Uuid,
+        expected_version: i32,
         new_email: String,
     ) -> QueryResult<User> {
         let changes = UpdateEmail::new(new_email);
 
-        diesel::update(users.find(user_id))
-            .set(&changes)
+        diesel::update(at_version(user_id, expected_version))
+            .set((&changes, version.eq(version + 1)))
             .get_result::<User>(conn)
     }
 
     pub fn update_role(
         conn: &mut PgConnection,
         user_id: Uuid,
+        expected_version: i32,
         new_role: Role,
     ) -> QueryResult<User> {
         let changes = UpdateRole::new(new_role);
 
-        diesel::update(users.find(user_id))
-            .set(&changes)
+        diesel::update(at_version(user_id, expected_version))
+            .set((&changes, version.eq(version + 1)))
+            .get_result::<User>(conn)
+    }
+
+    pub fn update_status(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        expected_version: i32,
+        new_status: UserStatus,
+    ) -> QueryResult<User> {
+        let changes = UpdateStatus::new(new_status);
+
+        diesel::update(at_version(user_id, expected_version))
+            .set((&changes, version.eq(version + 1)))
             .get_result::<User>(conn)
     }
 
     pub fn update_password(
         conn: &mut PgConnection,
         user_id: Uuid,
+        expected_version: i32,
         new_password_hash: String,
     ) -> QueryResult<User> {
         let changes = UpdatePassword::new(new_password_hash);
 
-        diesel::update(users.find(user_id))
-            .set(&changes)
+        diesel::update(at_version(user_id, expected_version))
+            .set((&changes, version.eq(version + 1)))
             .get_result::<User>(conn)
     }
 }
@@ -1,10 +1,91 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    audit_events (id) {
+        id -> Uuid,
+        actor_id -> Nullable<Uuid>,
+        action -> Varchar,
+        entity_type -> Varchar,
+        entity_id -> Uuid,
+        changes -> Jsonb,
+        request_id -> Nullable<Varchar>,
+        ip -> Nullable<Varchar>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    job_file_chunks (job_id, purpose, seq) {
+        job_id -> Uuid,
+        purpose -> Varchar,
+        seq -> Int4,
+        data -> Bytea,
+    }
+}
+
+diesel::table! {
+    job_files (job_id, purpose) {
+        job_id -> Uuid,
+        purpose -> Varchar,
+        file_name -> Varchar,
+        content_type -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    jobs (id) {
+        id -> Uuid,
+        kind -> Varchar,
+        payload -> Jsonb,
+        status -> Varchar,
+        attempts -> Int4,
+        max_attempts -> Int4,
+        run_at -> Timestamp,
+        locked_at -> Nullable<Timestamp>,
+        last_error -> Nullable<Text>,
+        result -> Nullable<Jsonb>,
+        created_by -> Nullable<Uuid>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     persons (id) {
         id -> Uuid,
         name -> Text,
         cpf -> Text,
+        birth_date -> Nullable<Date>,
+        email -> Nullable<Varchar>,
+        phone -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_by -> Nullable<Uuid>,
+        updated_by -> Nullable<Uuid>,
+        deleted_at -> Nullable<Timestamp>,
+        deleted_by -> Nullable<Uuid>,
+        version -> Int4,
+    }
+}
+
+diesel::table! {
+    persons_history (id) {
+        id -> Int8,
+        person_id -> Uuid,
+        version -> Int4,
+        name -> Text,
+        cpf -> Text,
+        birth_date -> Nullable<Date>,
+        email -> Nullable<Varchar>,
+        phone -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_by -> Nullable<Uuid>,
+        updated_by -> Nullable<Uuid>,
+        valid_from -> Timestamp,
+        valid_to -> Nullable<Timestamp>,
     }
 }
 
@@ -15,7 +96,20 @@ diesel::table! {
         password_hash -> Varchar,
         created_at -> Timestamp,
         role -> Varchar,
+        status -> Varchar,
+        version -> Int4,
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(persons, users,);
+diesel::joinable!(job_files -> jobs (job_id));
+diesel::joinable!(jobs -> users (created_by));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    audit_events,
+    job_file_chunks,
+    job_files,
+    jobs,
+    persons,
+    persons_history,
+    users,
+);
@@ -0,0 +1,74 @@
+use diesel::prelude::*;
+use serde_json::Value;
+
+use crate::{
+    error::app_error::AppError,
+    model::{
+        audit_action::AuditAction,
+        audit_event::{AuditEvent, Auditable, NewAuditEvent, diff, redacted_change},
+    },
+    repository::audit_repository::{AuditFilter, AuditRepository},
+    service::db::DbPool,
+    util::audit_context::AuditContext,
+};
+
+pub struct AuditService;
+
+impl AuditService {
+    /// Records a change to `T`. Call it on the connection, and inside the
+    /// transaction, that made the change so both commit or roll back together.
+    pub fn record<T: Auditable>(
+        conn: &mut PgConnection,
+        ctx: &AuditContext,
+        action: AuditAction,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> QueryResult<()> {
+        Self::record_redacted(conn, ctx, action, before, after, &[])
+    }
+
+    /// Like [`AuditService::record`], also listing `secret_fields` as changed
+    /// without their values (e.g. a new password).
+    pub fn record_redacted<T: Auditable>(
+        conn: &mut PgConnection,
+        ctx: &AuditContext,
+        action: AuditAction,
+        before: Option<&T>,
+        after: Option<&T>,
+        secret_fields: &[&str],
+    ) -> QueryResult<()> {
+        let Some(entity) = after.or(before) else {
+            return Ok(());
+        };
+
+        let mut changes = diff(
+            before.map(T::audit_snapshot).as_ref(),
+            after.map(T::audit_snapshot).as_ref(),
+        );
+        for field in secret_fields {
+            changes.insert(field.to_string(), redacted_change());
+        }
+
+        let event = NewAuditEvent::new(
+            ctx.actor,
+            action,
+            T::ENTITY_TYPE,
+            entity.entity_id(),
+            Value::Object(changes),
+            ctx.request_id.clone(),
+            ctx.ip.clone(),
+        );
+
+        AuditRepository::insert(conn, event).map(|_| ())
+    }
+
+    pub fn find_page(
+        pool: &DbPool,
+        filter: &AuditFilter,
+        page: i64,
+        size: i64,
+    ) -> Result<(i64, Vec<AuditEvent>), AppError> {
+        let mut conn = pool.get()?;
+        Ok(AuditRepository::find_page(&mut conn, filter, page, size)?)
+    }
+}
@@ -1,55 +1,86 @@
-use crate::model::role::Role;
-use crate::{
-    auth::jwt::generate_token, model::user::NewUser, repository::user_repository::UserRepository,
-};
+use crate::error::{app_error::AppError, conflict_error::ConflictError};
+use crate::i18n::Message;
+use crate::metrics::time_bcrypt;
+use crate::model::{role::Role, user_status::UserStatus};
+use crate::service::{db::DbPool, user_service::UserService};
+use crate::util::audit_context::AuditContext;
+use crate::util::validation::PASSWORD_MIN_LEN;
+use crate::{auth::jwt::generate_token, model::user::NewUser};
 use bcrypt::verify;
 use bcrypt::{DEFAULT_COST, hash};
-use diesel::PgConnection;
+use std::sync::LazyLock;
 use uuid::Uuid;
 
+/// Checked against on logins for unknown emails.
+static UNKNOWN_USER_HASH: LazyLock<String> =
+    LazyLock::new(|| hash("unknown user", DEFAULT_COST).unwrap_or_default());
+
 pub struct AuthService;
 
 impl AuthService {
     pub fn login(
-        conn: &mut PgConnection,
+        pool: &DbPool,
+        users: &UserService,
         email: String,
         password: String,
         secret: &str,
-    ) -> Result<(Uuid, String), &'static str> {
-        let user = UserRepository::find_by_email(conn, &email).map_err(|_| "User not found")?;
+    ) -> Result<(Uuid, String), AppError> {
+        let user = match users.find_by_email(pool, &email) {
+            Ok(user) => user,
+            Err(AppError::NotFound(_)) => {
+                // takes as long as a wrong password, so the answer gives
+                // away neither whether the email is registered
+                let _ = time_bcrypt("verify", || verify(password, &UNKNOWN_USER_HASH));
+                return Err(AppError::Unauthorized(Message::InvalidCredentials));
+            }
+            Err(e) => return Err(e),
+        };
 
-        let valid =
-            verify(password, &user.password_hash()).map_err(|_| "Password verification error")?;
+        let valid = time_bcrypt("verify", || verify(password, user.password_hash()))?;
 
         if !valid {
-            return Err("Invalid credentials");
+            return Err(AppError::Unauthorized(Message::InvalidCredentials));
+        }
+
+        // only told once the password is known to be right
+        if user.status() != UserStatus::Active {
+            return Err(AppError::Forbidden(Message::AccountDisabled));
         }
 
-        let res = (
-            *user.id(),
-            generate_token(*user.id(), user.role().to_string(), secret, 24)
-                .map_err(|_| "Token generation error")
-                .unwrap(),
-        );
-        Ok(res)
+        let token = generate_token(*user.id(), user.role().to_string(), secret, 24)
+            .map_err(|e| AppError::internal(format!("token generation error: {}", e)))?;
+
+        Ok((*user.id(), token))
     }
 
     pub fn register(
-        conn: &mut PgConnection,
+        pool: &DbPool,
+        users: &UserService,
         email: String,
         role: Role,
         password: String,
-    ) -> Result<Uuid, &'static str> {
-        if password.len() < 6 {
-            return Err("Password is too short");
+        ctx: &AuditContext,
+    ) -> Result<Uuid, AppError> {
+        if password.len() < PASSWORD_MIN_LEN {
+            return Err(AppError::invalid_field(
+                "password",
+                Message::TooShort {
+                    min: PASSWORD_MIN_LEN,
+                },
+            ));
         }
 
-        if UserRepository::find_by_email(conn, &email).is_ok() {
-            return Err("User already exists");
+        match users.find_by_email(pool, &email) {
+            Ok(_) => {
+                return Err(AppError::Conflict(ConflictError::for_constraint(Some(
+                    "users_email_key",
+                ))));
+            }
+            Err(AppError::NotFound(_)) => {}
+            Err(e) => return Err(e),
         }
 
-        let password_hash =
-            hash(password, DEFAULT_COST).map_err(|_| "Error generating password hash")?;
+        let password_hash = time_bcrypt("hash", || hash(password, DEFAULT_COST))?;
 
         let new_user = NewUser {
             id: Uuid::new_v4(),
@@ -58,10 +89,8 @@ impl AuthService {
             password_hash,
         };
 
-        let id = new_user.id;
-
-        UserRepository::insert(conn, new_user).map_err(|_| "User creation error")?;
-
-        Ok(id)
+        // a concurrent registration can still trip `users_email_key` here
+        let user = users.create_user(pool, new_user, ctx)?;
+        Ok(*user.id())
     }
 }
@@ -1,12 +1,60 @@
 use diesel::pg::PgConnection;
 use diesel::r2d2::{self, ConnectionManager};
+use diesel_migrations::{EmbeddedMigrations, embed_migrations};
+
+use crate::{metrics::pool_events::PoolEventHandler, telemetry::query_tracing::QueryTracing};
 
 pub type DbPool = r2d2::Pool<ConnectionManager<PgConnection>>;
 
+/// Migrations compiled into the binary, used to detect schema drift.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
 pub fn create_pool(database_url: &str) -> DbPool {
     let manager = ConnectionManager::<PgConnection>::new(database_url);
 
     r2d2::Pool::builder()
+        .event_handler(Box::new(PoolEventHandler))
+        .connection_customizer(Box::new(QueryTracing))
         .build(manager)
         .expect("Failed to create DB pool")
 }
+
+/// Connection for tests that need Postgres, to the migrated database at
+/// `DATABASE_URL`. Everything done on it is rolled back when it is dropped.
+#[cfg(test)]
+pub(crate) fn test_connection() -> PgConnection {
+    use diesel::Connection;
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).expect("Failed to connect to the DB");
+    conn.begin_test_transaction()
+        .expect("Failed to start a test transaction");
+    conn
+}
+
+/// Pool for tests that need Postgres: a single connection to the migrated
+/// database at `DATABASE_URL`, inside a transaction that is never committed.
+#[cfg(test)]
+pub(crate) fn test_pool() -> DbPool {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+
+    r2d2::Pool::builder()
+        .max_size(1)
+        .connection_customizer(Box::new(TestTransaction))
+        .build(ConnectionManager::<PgConnection>::new(database_url))
+        .expect("Failed to create DB pool")
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct TestTransaction;
+
+#[cfg(test)]
+impl r2d2::CustomizeConnection<PgConnection, r2d2::Error> for TestTransaction {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), r2d2::Error> {
+        use diesel::Connection;
+
+        conn.begin_test_transaction()
+            .map_err(r2d2::Error::QueryError)
+    }
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use diesel::prelude::*;
+use diesel_migrations::MigrationHarness;
+
+use crate::{
+    error::app_error::AppError,
+    service::db::{DbPool, MIGRATIONS},
+};
+
+pub struct HealthService;
+
+impl HealthService {
+    /// Checks a connection out of the pool within `timeout` and runs `SELECT 1`.
+    pub fn ping_database(pool: &DbPool, timeout: Duration) -> Result<(), AppError> {
+        let mut conn = pool.get_timeout(timeout)?;
+        diesel::sql_query("SELECT 1").execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Names of embedded migrations not yet applied to the database.
+    pub fn pending_migrations(pool: &DbPool, timeout: Duration) -> Result<Vec<String>, AppError> {
+        let mut conn = pool.get_timeout(timeout)?;
+        let pending = conn
+            .pending_migrations(MIGRATIONS)
+            .map_err(|e| AppError::internal(format!("migration check error: {}", e)))?;
+
+        Ok(pending.iter().map(|m| m.name().to_string()).collect())
+    }
+}
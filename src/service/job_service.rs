@@ -0,0 +1,418 @@
+use std::time::Duration;
+
+use actix_web::web::Bytes;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    error::app_error::AppError,
+    i18n::Message,
+    model::{
+        import_mode::ImportMode,
+        job::{Job, NewJob},
+        job_file::{JobFile, NewJobFile, NewJobFileChunk},
+        job_file_purpose::JobFilePurpose,
+        job_kind::JobKind,
+        job_status::JobStatus,
+    },
+    repository::{job_file_repository::JobFileRepository, job_repository::JobRepository},
+    service::db::DbPool,
+    util::{audit_context::AuditContext, export_writer::ExportFormat},
+};
+
+/// Attempts before a job is left dead.
+const MAX_ATTEMPTS: i32 = 5;
+
+/// How long an attempt may go without renewing its lease before the job is
+/// considered abandoned and handed to another worker.
+const LEASE_SECONDS: i32 = 5 * 60;
+
+/// How often a running attempt renews its lease; well inside
+/// [`LEASE_SECONDS`] so a slow renewal does not cost the lease.
+pub const LEASE_RENEWAL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Size of the chunks job files are stored in.
+const FILE_CHUNK_BYTES: usize = 1 << 20;
+
+/// Delay before the first retry; doubled on every further one.
+const RETRY_BASE_SECONDS: i32 = 30;
+const RETRY_MAX_SECONDS: i32 = 60 * 60;
+
+/// Payload of [`JobKind::PersonPurge`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersonPurgePayload {
+    pub retention_days: i32,
+}
+
+/// Payload of [`JobKind::PersonImport`]; the CSV file is the job's input.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersonImportPayload {
+    pub mode: ImportMode,
+    /// Language of the row errors in the report.
+    pub locale: String,
+    /// The request that uploaded the file, recorded with each person created.
+    pub audit: AuditContext,
+}
+
+/// Payload of [`JobKind::PersonExport`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersonExportPayload {
+    pub format: ExportFormat,
+    /// Query string of the `PersonFilterQuery` the people are picked by.
+    pub filters: String,
+    pub mask_cpf: bool,
+}
+
+/// A file handed to a job.
+pub struct FileContent {
+    pub file_name: String,
+    pub content_type: String,
+    pub data: Bytes,
+}
+
+/// Why an attempt failed, which decides whether the job is tried again.
+#[derive(Debug)]
+pub enum JobFailure {
+    /// Database or pool trouble, or a crash; a later attempt may succeed.
+    Transient(String),
+    /// A bad payload or input; every attempt would fail the same way.
+    Permanent(String),
+}
+
+impl JobFailure {
+    pub fn error(&self) -> &str {
+        match self {
+            JobFailure::Transient(error) | JobFailure::Permanent(error) => error,
+        }
+    }
+}
+
+impl From<AppError> for JobFailure {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::Internal(detail) => JobFailure::Transient(detail),
+            AppError::PoolExhausted => JobFailure::Transient(error.to_string()),
+            error => JobFailure::Permanent(error.to_string()),
+        }
+    }
+}
+
+/// Postgres-backed job queue. Jobs are enqueued by requests or timers and
+/// run by the workers in [`crate::bootstrap::jobs`], possibly in another
+/// process.
+pub struct JobService;
+
+impl JobService {
+    pub fn enqueue(
+        pool: &DbPool,
+        kind: JobKind,
+        payload: Value,
+        owner: Option<Uuid>,
+    ) -> Result<Job, AppError> {
+        let mut conn = pool.get()?;
+        Ok(JobRepository::create(
+            &mut conn,
+            NewJob::new(kind, payload, MAX_ATTEMPTS, owner),
+        )?)
+    }
+
+    /// Enqueues a job together with the file it reads.
+    pub fn enqueue_with_input(
+        pool: &DbPool,
+        kind: JobKind,
+        payload: Value,
+        owner: Option<Uuid>,
+        input: FileContent,
+    ) -> Result<Job, AppError> {
+        let mut conn = pool.get()?;
+        Ok(conn.transaction(|conn| {
+            let job = JobRepository::create(conn, NewJob::new(kind, payload, MAX_ATTEMPTS, owner))?;
+            let file = NewJobFile::new(
+                *job.id(),
+                JobFilePurpose::Input,
+                input.file_name,
+                input.content_type,
+            );
+            JobFileRepository::save(conn, file)?;
+            for (seq, data) in (0..).zip(input.data.chunks(FILE_CHUNK_BYTES)) {
+                let chunk = NewJobFileChunk::new(*job.id(), JobFilePurpose::Input, seq, data);
+                JobFileRepository::append_chunk(conn, chunk)?;
+            }
+            Ok::<_, diesel::result::Error>(job)
+        })?)
+    }
+
+    /// Enqueues a system job unless one of the same kind is already waiting
+    /// or running. Instances racing each other may still both enqueue one.
+    pub fn enqueue_unless_pending(
+        pool: &DbPool,
+        kind: JobKind,
+        payload: Value,
+    ) -> Result<Option<Job>, AppError> {
+        let mut conn = pool.get()?;
+        Ok(conn.transaction(|conn| {
+            if JobRepository::has_pending(conn, kind)? {
+                return Ok(None);
+            }
+            JobRepository::create(conn, NewJob::new(kind, payload, MAX_ATTEMPTS, None)).map(Some)
+        })?)
+    }
+
+    pub fn find_by_id(pool: &DbPool, id: Uuid) -> Result<Job, AppError> {
+        let mut conn = pool.get()?;
+        JobRepository::find_by_id(&mut conn, id)
+            .map_err(|e| AppError::from(e).or_not_found(Message::JobNotFound))
+    }
+
+    pub fn find_file(
+        pool: &DbPool,
+        id: Uuid,
+        purpose: JobFilePurpose,
+    ) -> Result<JobFile, AppError> {
+        let mut conn = pool.get()?;
+        JobFileRepository::find(&mut conn, id, purpose)
+            .map_err(|e| AppError::from(e).or_not_found(Message::JobFileNotFound))
+    }
+
+    /// The whole content of a file of the job `id`.
+    pub fn read_file(
+        pool: &DbPool,
+        id: Uuid,
+        purpose: JobFilePurpose,
+    ) -> Result<Vec<u8>, AppError> {
+        let mut conn = pool.get()?;
+        Ok(JobFileRepository::read(&mut conn, id, purpose)?)
+    }
+
+    /// Chunk `seq` of a file of the job `id`, `None` past the last one.
+    pub fn find_file_chunk(
+        pool: &DbPool,
+        id: Uuid,
+        purpose: JobFilePurpose,
+        seq: i32,
+    ) -> Result<Option<Vec<u8>>, AppError> {
+        let mut conn = pool.get()?;
+        Ok(JobFileRepository::find_chunk(&mut conn, id, purpose, seq)?)
+    }
+
+    /// Starts the file `attempt` of the job `id` produces, replacing one
+    /// from an earlier attempt.
+    pub fn create_output<'a>(
+        pool: &'a DbPool,
+        id: Uuid,
+        attempt: i32,
+        file_name: String,
+        content_type: String,
+    ) -> Result<OutputFile<'a>, AppError> {
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            hold_lease(conn, id, attempt)?;
+            let file = NewJobFile::new(id, JobFilePurpose::Output, file_name, content_type);
+            Ok::<_, AppError>(JobFileRepository::save(conn, file)?)
+        })?;
+
+        Ok(OutputFile {
+            pool,
+            job_id: id,
+            attempt,
+            next_seq: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Claims the next due job for this worker, if any. Abandoned jobs
+    /// with no attempts left are buried on the way.
+    pub fn claim_next(pool: &DbPool) -> Result<Option<Job>, AppError> {
+        let mut conn = pool.get()?;
+        Ok(conn.transaction(|conn| {
+            let buried = JobRepository::bury_abandoned(conn, LEASE_SECONDS)?;
+            if buried > 0 {
+                tracing::warn!(buried, "buried jobs abandoned on their final attempt");
+            }
+            JobRepository::claim_next(conn, LEASE_SECONDS)
+        })?)
+    }
+
+    /// Renews the lease of `attempt` of the job. Returns `false` once the
+    /// lease was lost, e.g. after the worker stalled past it.
+    pub fn renew_lease(pool: &DbPool, id: Uuid, attempt: i32) -> Result<bool, AppError> {
+        let mut conn = pool.get()?;
+        Ok(JobRepository::renew_lease(&mut conn, id, attempt)? > 0)
+    }
+
+    /// Records how the current attempt of `job` ended: its result, a retry
+    /// with exponential backoff, or, for a permanent failure or once
+    /// attempts run out, a dead job. The input file of a job that will not
+    /// run again is dropped. Returns `None` if the attempt had already lost
+    /// its lease.
+    pub fn finish(
+        pool: &DbPool,
+        job: &Job,
+        outcome: Result<Value, JobFailure>,
+    ) -> Result<Option<Job>, AppError> {
+        let mut conn = pool.get()?;
+        let attempt = job.attempts();
+
+        let updated = conn.transaction(|conn| {
+            let updated = match outcome {
+                Ok(output) => JobRepository::complete(conn, *job.id(), attempt, output),
+                Err(JobFailure::Transient(error)) if attempt < job.max_attempts() => {
+                    JobRepository::retry(conn, *job.id(), attempt, &error, retry_delay(attempt))
+                }
+                Err(failure) => JobRepository::bury(conn, *job.id(), attempt, failure.error()),
+            }
+            .optional()?;
+
+            if updated
+                .as_ref()
+                .is_some_and(|job| job.status() != JobStatus::Queued)
+            {
+                JobFileRepository::delete(conn, *job.id(), JobFilePurpose::Input)?;
+            }
+            Ok::<_, diesel::result::Error>(updated)
+        })?;
+
+        Ok(updated)
+    }
+}
+
+/// The output file of a running attempt, stored a chunk at a time as it is
+/// written so that it is never held in memory whole.
+pub struct OutputFile<'a> {
+    pool: &'a DbPool,
+    job_id: Uuid,
+    attempt: i32,
+    next_seq: i32,
+    buffer: Vec<u8>,
+}
+
+impl OutputFile<'_> {
+    pub fn write(&mut self, data: &[u8]) -> Result<(), AppError> {
+        self.buffer.extend_from_slice(data);
+        while self.buffer.len() >= FILE_CHUNK_BYTES {
+            let rest = self.buffer.split_off(FILE_CHUNK_BYTES);
+            let chunk = std::mem::replace(&mut self.buffer, rest);
+            self.store(&chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Stores what is left of the file.
+    pub fn finish(mut self) -> Result<(), AppError> {
+        if !self.buffer.is_empty() {
+            let chunk = std::mem::take(&mut self.buffer);
+            self.store(&chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Appends a chunk while the attempt still holds its lease, so an attempt
+    /// that lost it cannot mix its output into that of the next one.
+    fn store(&mut self, data: &[u8]) -> Result<(), AppError> {
+        let mut conn = self.pool.get()?;
+        conn.transaction(|conn| {
+            hold_lease(conn, self.job_id, self.attempt)?;
+            let chunk =
+                NewJobFileChunk::new(self.job_id, JobFilePurpose::Output, self.next_seq, data);
+            Ok::<_, AppError>(JobFileRepository::append_chunk(conn, chunk)?)
+        })?;
+        self.next_seq += 1;
+        Ok(())
+    }
+}
+
+/// Renews the lease of `attempt`, failing once it was lost.
+fn hold_lease(conn: &mut PgConnection, id: Uuid, attempt: i32) -> Result<(), AppError> {
+    if JobRepository::renew_lease(conn, id, attempt)? == 0 {
+        return Err(AppError::internal("the job lease was lost"));
+    }
+    Ok(())
+}
+
+/// 30s, 1m, 2m, 4m, ... capped at an hour.
+fn retry_delay(attempt: i32) -> i32 {
+    let doublings = (attempt - 1).clamp(0, 16) as u32;
+    RETRY_BASE_SECONDS
+        .saturating_mul(1 << doublings)
+        .min(RETRY_MAX_SECONDS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::db::test_pool;
+
+    #[test]
+    fn retry_delay_doubles_from_the_base() {
+        assert_eq!(retry_delay(1), RETRY_BASE_SECONDS);
+        assert_eq!(retry_delay(2), 2 * RETRY_BASE_SECONDS);
+        assert_eq!(retry_delay(4), 8 * RETRY_BASE_SECONDS);
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(retry_delay(8), RETRY_MAX_SECONDS);
+        assert_eq!(retry_delay(i32::MAX), RETRY_MAX_SECONDS);
+    }
+
+    #[test]
+    fn retry_delay_treats_unstarted_jobs_as_the_first_attempt() {
+        assert_eq!(retry_delay(0), RETRY_BASE_SECONDS);
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn output_is_stored_in_chunks_while_the_lease_is_held() {
+        let pool = test_pool();
+        diesel::delete(crate::schema::jobs::table)
+            .execute(&mut pool.get().unwrap())
+            .unwrap();
+        JobService::enqueue(&pool, JobKind::PersonExport, Value::Null, None).unwrap();
+        let job = JobService::claim_next(&pool).unwrap().unwrap();
+        let output = |attempt| {
+            JobService::create_output(&pool, *job.id(), attempt, "a.csv".into(), "text/csv".into())
+        };
+
+        assert!(output(job.attempts() + 1).is_err());
+
+        let mut file = output(job.attempts()).unwrap();
+        file.write(&vec![b'x'; FILE_CHUNK_BYTES + 10]).unwrap();
+        file.write(b"yz").unwrap();
+        file.finish().unwrap();
+
+        let chunk = |seq| {
+            JobService::find_file_chunk(&pool, *job.id(), JobFilePurpose::Output, seq)
+                .unwrap()
+                .map(|data| data.len())
+        };
+        assert_eq!(chunk(0), Some(FILE_CHUNK_BYTES));
+        assert_eq!(chunk(1), Some(12));
+        assert_eq!(chunk(2), None);
+    }
+
+    #[test]
+    fn database_and_pool_errors_are_transient() {
+        assert!(matches!(
+            JobFailure::from(AppError::internal("connection reset")),
+            JobFailure::Transient(error) if error == "connection reset"
+        ));
+        assert!(matches!(
+            JobFailure::from(AppError::PoolExhausted),
+            JobFailure::Transient(_)
+        ));
+    }
+
+    #[test]
+    fn client_errors_are_permanent() {
+        assert!(matches!(
+            JobFailure::from(AppError::validation(Message::InvalidCpf)),
+            JobFailure::Permanent(_)
+        ));
+        assert!(matches!(
+            JobFailure::from(AppError::NotFound(Message::JobNotFound)),
+            JobFailure::Permanent(_)
+        ));
+    }
+}
@@ -1,4 +1,7 @@
+pub mod audit_service;
 pub mod auth_service;
 pub mod db;
+pub mod health_service;
+pub mod job_service;
 pub mod person_service;
 pub mod user_service;
@@ -1,11 +1,95 @@
 use crate::{
-    model::person::Person, repository::person_repository::PersonRepository, service::db::DbPool,
+    error::{app_error::AppError, problem::FieldError},
+    i18n::Message,
+    model::{
+        audit_action::AuditAction,
+        cpf::Cpf,
+        import_mode::ImportMode,
+        person::{NewPerson, Person, UpdatePerson},
+        person_version::PersonVersion,
+    },
+    repository::person_repository::{PersonFilter, PersonRepository},
+    service::{audit_service::AuditService, db::DbPool},
+    util::{
+        audit_context::AuditContext,
+        csv_upload::FILE_FIELD,
+        if_match::IfMatch,
+        validation::{FieldErrors, Validate},
+    },
 };
 
-use diesel::result::QueryResult;
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::ops::ControlFlow;
 use uuid::Uuid;
 
-#[derive(Clone)]
+/// Columns of an import file; the rules are those of `POST /person`.
+#[derive(Deserialize)]
+struct ImportRecord {
+    name: String,
+    cpf: String,
+    birth_date: Option<NaiveDate>,
+    email: Option<String>,
+    phone: Option<String>,
+}
+
+impl Validate for ImportRecord {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+        errors.cpf("cpf", &self.cpf);
+        errors.birth_date("birth_date", self.birth_date);
+        errors.optional_email("email", self.email.as_deref());
+        errors.optional_phone("phone", self.phone.as_deref());
+    }
+}
+
+/// One data row of an import file; `person` holds the row's field errors
+/// when it did not validate.
+struct ImportRow {
+    line: u64,
+    person: Result<NewPerson, Vec<FieldError>>,
+}
+
+pub struct ImportFailure {
+    pub line: u64,
+    pub errors: Vec<FieldError>,
+}
+
+pub struct ImportOutcome {
+    pub total: usize,
+    /// Rows that passed validation and the duplicate checks.
+    pub valid: usize,
+    pub imported: usize,
+    /// Ordered by line.
+    pub failures: Vec<ImportFailure>,
+}
+
+/// Ends the import transaction: `Rejected` rolls back an all-or-nothing
+/// import that had failures, still reporting them.
+enum ImportAbort {
+    Rejected(ImportOutcome),
+    Failed(AppError),
+}
+
+impl From<AppError> for ImportAbort {
+    fn from(err: AppError) -> Self {
+        ImportAbort::Failed(err)
+    }
+}
+
+impl From<diesel::result::Error> for ImportAbort {
+    fn from(err: diesel::result::Error) -> Self {
+        ImportAbort::Failed(err.into())
+    }
+}
+
+/// Rows fetched from the export cursor per round trip.
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+#[derive(Clone, Default)]
 pub struct PersonService;
 
 impl PersonService {
@@ -16,47 +100,282 @@ impl PersonService {
     pub fn create_person(
         &self,
         pool: &DbPool,
-        name: String,
-        cpf: String,
-    ) -> Result<Person, diesel::result::Error> {
-        PersonRepository::create(pool, name, cpf)
+        new_person: NewPerson,
+        ctx: &AuditContext,
+    ) -> Result<Person, AppError> {
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            let person = PersonRepository::create(conn, new_person)?;
+            AuditService::record(conn, ctx, AuditAction::Create, None, Some(&person))?;
+            Ok(person)
+        })
+    }
+
+    /// Fails like [`PersonService::import_people`] would on a file that
+    /// cannot be imported at all, so that is reported before the import
+    /// is queued. Only the header is read.
+    pub fn check_import_file(&self, file: impl Read) -> Result<(), AppError> {
+        import_reader(file).map(|_| ())
+    }
+
+    /// Creates the people of a CSV file according to `mode`, checking each
+    /// row like `POST /person` does. Rows repeating an earlier row's CPF, or a
+    /// live person's, fail like invalid ones; a CPF taken concurrently fails
+    /// its row at insert time. Only an unreadable header or a missing
+    /// required column fails the whole file.
+    pub fn import_people(
+        &self,
+        pool: &DbPool,
+        file: impl Read,
+        mode: ImportMode,
+        ctx: &AuditContext,
+    ) -> Result<ImportOutcome, AppError> {
+        let rows = read_import_rows(file, ctx.actor)?;
+        let total = rows.len();
+        let mut failures = Vec::new();
+        let mut first_line: HashMap<Cpf, u64> = HashMap::new();
+        let mut candidates = Vec::new();
+
+        for row in rows {
+            let person = match row.person {
+                Ok(person) => person,
+                Err(errors) => {
+                    failures.push(ImportFailure {
+                        line: row.line,
+                        errors,
+                    });
+                    continue;
+                }
+            };
+            match first_line.get(person.cpf()) {
+                Some(&line) => failures.push(ImportFailure {
+                    line: row.line,
+                    errors: vec![FieldError::new("cpf", Message::DuplicateCpfInFile { line })],
+                }),
+                None => {
+                    first_line.insert(person.cpf().clone(), row.line);
+                    candidates.push((row.line, person));
+                }
+            }
+        }
+
+        let mut conn = pool.get()?;
+        let result = conn.transaction(|conn| {
+            let cpfs: Vec<Cpf> = candidates.iter().map(|(_, p)| p.cpf().clone()).collect();
+            let taken: HashSet<Cpf> = PersonRepository::existing_cpfs(conn, &cpfs)?
+                .into_iter()
+                .collect();
+
+            let (clashing, fresh): (Vec<_>, Vec<_>) = candidates
+                .into_iter()
+                .partition(|(_, p)| taken.contains(p.cpf()));
+            failures.extend(clashing.into_iter().map(|(line, _)| ImportFailure {
+                line,
+                errors: vec![FieldError::new("cpf", Message::CpfTaken)],
+            }));
+
+            let valid = fresh.len();
+            let mut imported = 0;
+            let write = match mode {
+                ImportMode::DryRun => false,
+                ImportMode::AllOrNothing => failures.is_empty(),
+                ImportMode::BestEffort => true,
+            };
+
+            if write {
+                for (line, new_person) in fresh {
+                    // a savepoint per row, so a failed insert leaves the rest usable
+                    let created = conn.transaction(|conn| {
+                        let person = PersonRepository::create(conn, new_person)?;
+                        AuditService::record(conn, ctx, AuditAction::Create, None, Some(&person))?;
+                        Ok::<_, AppError>(person)
+                    });
+                    match created {
+                        Ok(_) => imported += 1,
+                        Err(AppError::Conflict(conflict)) => failures.push(ImportFailure {
+                            line,
+                            errors: vec![FieldError::new(
+                                conflict.field.unwrap_or("cpf"),
+                                conflict.message,
+                            )],
+                        }),
+                        Err(err) => return Err(ImportAbort::Failed(err)),
+                    }
+                }
+            }
+
+            failures.sort_by_key(|failure| failure.line);
+            let outcome = ImportOutcome {
+                total,
+                valid,
+                imported,
+                failures: std::mem::take(&mut failures),
+            };
+
+            if mode == ImportMode::AllOrNothing && !outcome.failures.is_empty() {
+                return Err(ImportAbort::Rejected(ImportOutcome {
+                    imported: 0,
+                    ..outcome
+                }));
+            }
+            Ok(outcome)
+        });
+
+        match result {
+            Ok(outcome) | Err(ImportAbort::Rejected(outcome)) => Ok(outcome),
+            Err(ImportAbort::Failed(err)) => Err(err),
+        }
     }
 
-    pub fn find_all(&self, pool: &DbPool) -> Result<Vec<Person>, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
-        PersonRepository::find_all(&mut conn)
+    pub fn find_all(&self, pool: &DbPool) -> Result<Vec<Person>, AppError> {
+        let mut conn = pool.get()?;
+        Ok(PersonRepository::find_all(&mut conn)?)
     }
 
     pub fn find_page(
         &self,
         pool: &DbPool,
+        filter: &PersonFilter,
         page: i64,
         per_page: i64,
-    ) -> QueryResult<(i64, Vec<Person>)> {
-        let mut conn = pool.get().map_err(|_| diesel::result::Error::NotFound)?;
+    ) -> Result<(i64, Vec<Person>), AppError> {
+        let mut conn = pool.get()?;
+        Ok(PersonRepository::find_page(
+            &mut conn, filter, page, per_page,
+        )?)
+    }
 
-        PersonRepository::find_page(&mut conn, page, per_page)
+    /// Hands every person matching `filter` to `f`, a batch at a time, from
+    /// a single read-only snapshot, until `f` breaks.
+    pub fn export<F>(&self, pool: &DbPool, filter: &PersonFilter, f: F) -> Result<(), AppError>
+    where
+        F: FnMut(Vec<Person>) -> Result<ControlFlow<()>, AppError>,
+    {
+        let mut conn = pool.get()?;
+        conn.build_transaction()
+            .read_only()
+            .repeatable_read()
+            .run(|conn| PersonRepository::for_each_batch(conn, filter, EXPORT_BATCH_SIZE, f))
     }
 
-    pub fn find_by_id(&self, pool: &DbPool, id: Uuid) -> Result<Person, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
-        PersonRepository::find_by_id(&mut conn, id)
+    pub fn search(
+        &self,
+        pool: &DbPool,
+        term: &str,
+        page: i64,
+        per_page: i64,
+    ) -> Result<(i64, Vec<(Person, f32)>), AppError> {
+        let mut conn = pool.get()?;
+        Ok(PersonRepository::search(&mut conn, term, page, per_page)?)
     }
 
-    pub fn delete_person(&self, pool: &DbPool, id: Uuid) -> Result<Person, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
-        PersonRepository::delete_person(&mut conn, id)
+    pub fn find_by_id(
+        &self,
+        pool: &DbPool,
+        id: Uuid,
+        include_deleted: bool,
+    ) -> Result<Person, AppError> {
+        let mut conn = pool.get()?;
+        PersonRepository::find_by_id(&mut conn, id, include_deleted)
+            .map_err(|e| AppError::from(e).or_not_found(Message::PersonNotFound))
+    }
+
+    pub fn history(&self, pool: &DbPool, id: Uuid) -> Result<Vec<PersonVersion>, AppError> {
+        let mut conn = pool.get()?;
+        let versions = PersonRepository::history(&mut conn, id)?;
+        if versions.is_empty() {
+            return Err(AppError::NotFound(Message::PersonNotFound));
+        }
+        Ok(versions)
+    }
+
+    /// The person as it was at `at`. The past of a soft-deleted person is
+    /// hidden like the person itself, unless `include_deleted`.
+    pub fn find_as_of(
+        &self,
+        pool: &DbPool,
+        id: Uuid,
+        at: NaiveDateTime,
+        include_deleted: bool,
+    ) -> Result<Person, AppError> {
+        let mut conn = pool.get()?;
+        PersonRepository::find_by_id(&mut conn, id, include_deleted)
+            .and_then(|_| PersonRepository::find_as_of(&mut conn, id, at))
+            .map_err(|e| AppError::from(e).or_not_found(Message::PersonNotFound))
+    }
+
+    pub fn find_by_cpf(
+        &self,
+        pool: &DbPool,
+        cpf: &Cpf,
+        include_deleted: bool,
+    ) -> Result<Person, AppError> {
+        let mut conn = pool.get()?;
+        PersonRepository::find_by_cpf(&mut conn, cpf, include_deleted)
+            .map_err(|e| AppError::from(e).or_not_found(Message::PersonNotFound))
+    }
+
+    pub fn delete_person(
+        &self,
+        pool: &DbPool,
+        id: Uuid,
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+    ) -> Result<Person, AppError> {
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            let before = PersonRepository::lock_by_id(conn, id)?;
+            if_match.check(before.version())?;
+            let person = PersonRepository::delete_person(conn, id, before.version(), ctx.actor)?;
+            AuditService::record(conn, ctx, AuditAction::Delete, Some(&person), None)?;
+            Ok(person)
+        })
+        .map_err(|e: AppError| e.or_not_found(Message::PersonNotFound))
+    }
+
+    /// Undoes a soft delete. Fails with `PersonNotFound` unless the person is
+    /// currently deleted.
+    pub fn restore_person(
+        &self,
+        pool: &DbPool,
+        id: Uuid,
+        ctx: &AuditContext,
+    ) -> Result<Person, AppError> {
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            let person = PersonRepository::restore_person(conn, id, ctx.actor)?;
+            AuditService::record(conn, ctx, AuditAction::Restore, None, Some(&person))?;
+            Ok(person)
+        })
+        .map_err(|e: AppError| e.or_not_found(Message::PersonNotFound))
+    }
+
+    /// Hard-deletes people soft-deleted more than `retention_days` ago,
+    /// returning how many were removed. Runs without a request, so the audit
+    /// events have no actor.
+    pub fn purge_deleted(&self, pool: &DbPool, retention_days: i32) -> Result<usize, AppError> {
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            let purged = PersonRepository::purge_deleted(conn, retention_days)?;
+            let ctx = AuditContext::default();
+            for person in &purged {
+                AuditService::record(conn, &ctx, AuditAction::Purge, Some(person), None)?;
+            }
+            Ok(purged.len())
+        })
     }
 
     pub fn update_person(
         &self,
         pool: &DbPool,
         id: Uuid,
-        name: String,
-        cpf: String,
-    ) -> Result<Person, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
-        PersonRepository::update_person(&mut conn, id, name, cpf)
+        changes: UpdatePerson,
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+    ) -> Result<Person, AppError> {
+        self.update_audited(pool, id, if_match, ctx, |conn, before| {
+            PersonRepository::update_person(conn, id, before.version(), changes)
+        })
     }
 
     pub fn update_name(
@@ -64,18 +383,237 @@ impl PersonService {
         pool: &DbPool,
         id: Uuid,
         name: String,
-    ) -> Result<Person, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
-        PersonRepository::update_name(&mut conn, id, name)
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+    ) -> Result<Person, AppError> {
+        self.update_audited(pool, id, if_match, ctx, |conn, before| {
+            PersonRepository::update_name(conn, id, before.version(), name, ctx.actor)
+        })
     }
 
     pub fn update_cpf(
         &self,
         pool: &DbPool,
         id: Uuid,
-        cpf: String,
-    ) -> Result<Person, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
-        PersonRepository::update_cpf(&mut conn, id, cpf)
+        cpf: Cpf,
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+    ) -> Result<Person, AppError> {
+        self.update_audited(pool, id, if_match, ctx, |conn, before| {
+            PersonRepository::update_cpf(conn, id, before.version(), cpf, ctx.actor)
+        })
+    }
+
+    /// Applies `patch` to the person's current state and saves the result in
+    /// a single update. `patch` runs with the row locked, so it sees the
+    /// version that gets written over.
+    pub fn patch_person<P>(
+        &self,
+        pool: &DbPool,
+        id: Uuid,
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+        patch: P,
+    ) -> Result<Person, AppError>
+    where
+        P: FnOnce(&Person) -> Result<UpdatePerson, AppError>,
+    {
+        self.update_audited(pool, id, if_match, ctx, |conn, before| {
+            let changes = patch(before)?;
+            Ok::<_, AppError>(PersonRepository::update_person(
+                conn,
+                id,
+                before.version(),
+                changes,
+            )?)
+        })
+    }
+
+    /// Runs `update` with the row locked, once `if_match` accepts its
+    /// current version, and audits the before/after diff in the same
+    /// transaction. `update` gets the locked row, whose version it must
+    /// still find.
+    fn update_audited<F, E>(
+        &self,
+        pool: &DbPool,
+        id: Uuid,
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+        update: F,
+    ) -> Result<Person, AppError>
+    where
+        F: FnOnce(&mut PgConnection, &Person) -> Result<Person, E>,
+        AppError: From<E>,
+    {
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            let before = PersonRepository::lock_by_id(conn, id)?;
+            if_match.check(before.version())?;
+            let after = update(conn, &before)?;
+            AuditService::record(conn, ctx, AuditAction::Update, Some(&before), Some(&after))?;
+            Ok(after)
+        })
+        .map_err(|e: AppError| e.or_not_found(Message::PersonNotFound))
+    }
+}
+
+fn unreadable_file(err: csv::Error) -> AppError {
+    AppError::invalid_field(FILE_FIELD, Message::MalformedCsvRow(err.to_string()))
+}
+
+/// Opens an import file, failing if its header lacks a required column.
+fn import_reader<R: Read>(file: R) -> Result<(csv::Reader<R>, csv::StringRecord), AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(file);
+    let headers = reader.headers().map_err(unreadable_file)?.clone();
+    for column in ["name", "cpf"] {
+        if !headers.iter().any(|header| header == column) {
+            return Err(AppError::invalid_field(
+                FILE_FIELD,
+                Message::MissingCsvColumn(column.into()),
+            ));
+        }
+    }
+    Ok((reader, headers))
+}
+
+fn read_import_rows(file: impl Read, actor: Option<Uuid>) -> Result<Vec<ImportRow>, AppError> {
+    let (mut reader, headers) = import_reader(file)?;
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let (line, person) = match record {
+            Ok(record) => {
+                let line = record.position().map_or(0, |p| p.line());
+                (line, import_person(&record, &headers, actor))
+            }
+            Err(err) => match err.position() {
+                Some(position) => (
+                    position.line(),
+                    Err(vec![FieldError::new(
+                        "row",
+                        Message::MalformedCsvRow(err.to_string()),
+                    )]),
+                ),
+                None => return Err(unreadable_file(err)),
+            },
+        };
+        rows.push(ImportRow { line, person });
+    }
+    Ok(rows)
+}
+
+fn import_person(
+    record: &csv::StringRecord,
+    headers: &csv::StringRecord,
+    actor: Option<Uuid>,
+) -> Result<NewPerson, Vec<FieldError>> {
+    let row: ImportRecord = record.deserialize(Some(headers)).map_err(|err| {
+        let (field, detail) = match err.kind() {
+            csv::ErrorKind::Deserialize { err, .. } => (
+                err.field().and_then(|i| headers.get(i as usize)),
+                err.kind().to_string(),
+            ),
+            _ => (None, err.to_string()),
+        };
+        vec![FieldError::new(
+            field.unwrap_or("row"),
+            Message::MalformedCsvRow(detail),
+        )]
+    })?;
+
+    let mut errors = FieldErrors::default();
+    row.validate(&mut errors);
+    let errors = errors.into_vec();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let optional = |value: Option<String>| {
+        value
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let cpf =
+        Cpf::parse(&row.cpf).map_err(|_| vec![FieldError::new("cpf", Message::InvalidCpf)])?;
+    Ok(NewPerson::new(
+        row.name.trim().to_string(),
+        cpf,
+        row.birth_date,
+        optional(row.email),
+        optional(row.phone),
+        actor,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(file: &str) -> Result<Vec<ImportRow>, AppError> {
+        read_import_rows(file.as_bytes(), None)
+    }
+
+    /// `(field, code)` of every error of a row.
+    fn errors(row: &ImportRow) -> Vec<(&str, &'static str)> {
+        match &row.person {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors
+                .iter()
+                .map(|e| (e.field.as_str(), e.message.code()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn reads_valid_rows_with_their_lines() {
+        let rows = read(
+            "cpf,name,email,phone\n\
+             529.982.247-25, Ana Lima ,ana@example.com,\n\
+             11144477735,Bia,, \n",
+        )
+        .unwrap();
+
+        assert_eq!(rows.iter().map(|r| r.line).collect::<Vec<_>>(), [2, 3]);
+        let ana = rows[0].person.as_ref().unwrap();
+        assert_eq!(ana.cpf().as_str(), "52998224725");
+        assert!(rows[1].person.is_ok());
+    }
+
+    #[test]
+    fn invalid_rows_carry_their_field_errors() {
+        let rows = read(
+            "name,cpf,birth_date,email\n\
+             ,529.982.247-26,,not-an-email\n\
+             Ana,52998224725,1990-13-40,\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            errors(&rows[0]),
+            [
+                ("name", "validation.blank"),
+                ("cpf", "validation.invalid_cpf"),
+                ("email", "validation.invalid_email"),
+            ]
+        );
+        assert_eq!(errors(&rows[1]), [("row", "validation.malformed_csv_row")]);
+    }
+
+    #[test]
+    fn rows_with_the_wrong_number_of_fields_fail_alone() {
+        let rows = read("name,cpf\nAna\nBia,52998224725\n").unwrap();
+        assert_eq!(errors(&rows[0]), [("row", "validation.malformed_csv_row")]);
+        assert!(rows[1].person.is_ok());
+    }
+
+    #[test]
+    fn missing_required_column_fails_the_file() {
+        let Err(AppError::Validation { errors, .. }) = read("nome,cpf\nAna,52998224725\n") else {
+            panic!("expected a validation error");
+        };
+        assert_eq!(errors[0].field, FILE_FIELD);
+        assert_eq!(errors[0].message, Message::MissingCsvColumn("name".into()));
     }
 }
@@ -3,15 +3,28 @@ use diesel::prelude::*;
 use uuid::Uuid;
 
 use crate::{
-    error::user_service_error::UserServiceError,
+    error::app_error::AppError,
+    i18n::Message,
+    metrics::time_bcrypt,
     model::{
+        audit_action::AuditAction,
         role::Role,
-        user::{NewUser, User},
+        user::{NewUser, PatchUser, User},
+        user_status::UserStatus,
     },
-    repository::user_repository::UserRepository,
-    service::db::DbPool,
+    repository::user_repository::{UserFilter, UserRepository},
+    service::{audit_service::AuditService, db::DbPool},
+    util::{audit_context::AuditContext, if_match::IfMatch},
 };
 
+/// Editable fields of a user after a PATCH. `password` is only set when the
+/// patch changes it, since the current one is never exposed.
+pub struct UserPatch {
+    pub email: String,
+    pub role: Role,
+    pub password: Option<String>,
+}
+
 #[derive(Clone, Default)]
 pub struct UserService;
 
@@ -20,35 +33,81 @@ impl UserService {
         Self
     }
 
-    pub fn find_by_id(&self, pool: &DbPool, id: Uuid) -> Result<User, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
+    pub fn find_by_id(&self, pool: &DbPool, id: Uuid) -> Result<User, AppError> {
+        let mut conn = pool.get()?;
         UserRepository::find_by_id(&mut conn, id)
+            .map_err(|e| AppError::from(e).or_not_found(Message::UserNotFound))
     }
 
-    pub fn find_by_email(&self, conn: &mut PgConnection, user_email: &str) -> QueryResult<User> {
-        UserRepository::find_by_email(conn, user_email)
+    /// Fails unless the user behind a token may still use the API: a
+    /// deleted user's token is invalid and a disabled one's refused.
+    pub fn require_active(&self, pool: &DbPool, id: Uuid) -> Result<(), AppError> {
+        let mut conn = pool.get()?;
+        match UserRepository::find_status(&mut conn, id) {
+            Ok(UserStatus::Active) => Ok(()),
+            Ok(_) => Err(AppError::Forbidden(Message::AccountDisabled)),
+            Err(diesel::result::Error::NotFound) => {
+                Err(AppError::Unauthorized(Message::InvalidToken))
+            }
+            Err(e) => Err(e.into()),
+        }
     }
 
-    pub fn create_user(&self, conn: &mut PgConnection, new_user: NewUser) -> QueryResult<()> {
-        UserRepository::insert(conn, new_user).map(|_| ())
+    pub fn find_by_email(&self, pool: &DbPool, user_email: &str) -> Result<User, AppError> {
+        let mut conn = pool.get()?;
+        UserRepository::find_by_email(&mut conn, user_email)
+            .map_err(|e| AppError::from(e).or_not_found(Message::UserNotFound))
     }
 
-    pub fn delete_user(&self, pool: &DbPool, id: Uuid) -> Result<User, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
-        UserRepository::delete_user(&mut conn, id)
+    pub fn create_user(
+        &self,
+        pool: &DbPool,
+        new_user: NewUser,
+        ctx: &AuditContext,
+    ) -> Result<User, AppError> {
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            let user = UserRepository::insert(conn, new_user)?;
+            AuditService::record(conn, ctx, AuditAction::Create, None, Some(&user))?;
+            Ok(user)
+        })
     }
 
-    pub fn find_all(&self, pool: &DbPool) -> Result<Vec<User>, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
-        UserRepository::find_all(&mut conn)
+    pub fn delete_user(
+        &self,
+        pool: &DbPool,
+        id: Uuid,
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+    ) -> Result<User, AppError> {
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            let before = UserRepository::lock_by_id(conn, id)?;
+            if_match.check(before.version())?;
+            let user = UserRepository::delete_user(conn, id, before.version())?;
+            AuditService::record(conn, ctx, AuditAction::Delete, Some(&user), None)?;
+            Ok(user)
+        })
+        .map_err(|e: AppError| e.or_not_found(Message::UserNotFound))
     }
 
-    pub fn find_page(&self, pool: &DbPool, page: i64, size: i64) -> QueryResult<(i64, Vec<User>)> {
-        let mut conn = pool.get().map_err(|_| diesel::result::Error::NotFound)?;
+    pub fn find_all(&self, pool: &DbPool) -> Result<Vec<User>, AppError> {
+        let mut conn = pool.get()?;
+        Ok(UserRepository::find_all(&mut conn)?)
+    }
 
-        UserRepository::find_page(&mut conn, page, size)
+    pub fn find_page(
+        &self,
+        pool: &DbPool,
+        filter: &UserFilter,
+        page: i64,
+        size: i64,
+    ) -> Result<(i64, Vec<User>), AppError> {
+        let mut conn = pool.get()?;
+        Ok(UserRepository::find_page(&mut conn, filter, page, size)?)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_user(
         &self,
         pool: &DbPool,
@@ -56,12 +115,28 @@ impl UserService {
         new_email: String,
         new_role: Role,
         new_password: String,
-    ) -> QueryResult<User> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+    ) -> Result<User, AppError> {
+        let new_password_hash = time_bcrypt("hash", || hash(new_password, DEFAULT_COST))?;
 
-        let new_password_hash = hash(new_password, DEFAULT_COST)
-            .map_err(|_e| diesel::result::Error::RollbackTransaction)?;
-        UserRepository::update_user(&mut conn, user_id, new_email, new_role, new_password_hash)
+        self.update_audited(
+            pool,
+            user_id,
+            if_match,
+            ctx,
+            &["password"],
+            |conn, version| {
+                UserRepository::update_user(
+                    conn,
+                    user_id,
+                    version,
+                    new_email,
+                    new_role,
+                    new_password_hash,
+                )
+            },
+        )
     }
 
     pub fn update_email(
@@ -69,9 +144,12 @@ impl UserService {
         pool: &DbPool,
         id: Uuid,
         email: String,
-    ) -> Result<User, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
-        UserRepository::update_email(&mut conn, id, email)
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+    ) -> Result<User, AppError> {
+        self.update_audited(pool, id, if_match, ctx, &[], |conn, version| {
+            UserRepository::update_email(conn, id, version, email)
+        })
     }
 
     pub fn update_role(
@@ -79,9 +157,25 @@ impl UserService {
         pool: &DbPool,
         id: Uuid,
         role: Role,
-    ) -> Result<User, diesel::result::Error> {
-        let mut conn = pool.get().expect("Failed to get DB connection");
-        UserRepository::update_role(&mut conn, id, role)
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+    ) -> Result<User, AppError> {
+        self.update_audited(pool, id, if_match, ctx, &[], |conn, version| {
+            UserRepository::update_role(conn, id, version, role)
+        })
+    }
+
+    pub fn update_status(
+        &self,
+        pool: &DbPool,
+        id: Uuid,
+        status: UserStatus,
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+    ) -> Result<User, AppError> {
+        self.update_audited(pool, id, if_match, ctx, &[], |conn, version| {
+            UserRepository::update_status(conn, id, version, status)
+        })
     }
 
     pub fn update_password(
@@ -89,17 +183,120 @@ impl UserService {
         pool: &DbPool,
         user_id: Uuid,
         new_password: String,
-    ) -> Result<User, UserServiceError> {
-        let password_hash =
-            hash(new_password, DEFAULT_COST).map_err(|_| UserServiceError::HashError)?;
-
-        let mut conn = pool.get().map_err(|e| {
-            UserServiceError::DatabaseError(diesel::result::Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::UnableToSendCommand,
-                Box::new(e.to_string()),
-            ))
-        })?;
-
-        UserRepository::update_password(&mut conn, user_id, password_hash).map_err(|e| e.into())
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+    ) -> Result<User, AppError> {
+        let password_hash = time_bcrypt("hash", || hash(new_password, DEFAULT_COST))?;
+
+        self.update_audited(
+            pool,
+            user_id,
+            if_match,
+            ctx,
+            &["password"],
+            |conn, version| UserRepository::update_password(conn, user_id, version, password_hash),
+        )
+    }
+
+    /// Applies `patch` to the user's current state and saves the result in a
+    /// single update. `patch` runs with the row locked, so it sees the
+    /// version that gets written over.
+    ///
+    /// A new password is hashed from a first, unlocked run of `patch`, so
+    /// the slow hash does not hold the lock. It is only hashed again in the
+    /// lock if the patch comes out with another password there.
+    pub fn patch_user<P>(
+        &self,
+        pool: &DbPool,
+        id: Uuid,
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+        patch: P,
+    ) -> Result<User, AppError>
+    where
+        P: Fn(&User) -> Result<UserPatch, AppError>,
+    {
+        let mut conn = pool.get()?;
+
+        let current = UserRepository::find_by_id(&mut conn, id)
+            .map_err(|e| AppError::from(e).or_not_found(Message::UserNotFound))?;
+        if_match.check(current.version())?;
+        let hashed = patch(&current)?
+            .password
+            .map(|password| {
+                let password_hash = time_bcrypt("hash", || hash(&password, DEFAULT_COST))?;
+                Ok::<_, AppError>((password, password_hash))
+            })
+            .transpose()?;
+
+        conn.transaction(|conn| {
+            let before = UserRepository::lock_by_id(conn, id)?;
+            if_match.check(before.version())?;
+
+            let UserPatch {
+                email,
+                role,
+                password,
+            } = patch(&before)?;
+            let password_hash = match (password, hashed) {
+                (None, _) => None,
+                (Some(password), Some((plain, password_hash))) if password == plain => {
+                    Some(password_hash)
+                }
+                (Some(password), _) => Some(time_bcrypt("hash", || hash(password, DEFAULT_COST))?),
+            };
+            let secret_fields: &[&str] = if password_hash.is_some() {
+                &["password"]
+            } else {
+                &[]
+            };
+
+            let changes = PatchUser::new(email, role, password_hash);
+            let after = UserRepository::patch_user(conn, id, before.version(), changes)?;
+            AuditService::record_redacted(
+                conn,
+                ctx,
+                AuditAction::Update,
+                Some(&before),
+                Some(&after),
+                secret_fields,
+            )?;
+            Ok(after)
+        })
+        .map_err(|e: AppError| e.or_not_found(Message::UserNotFound))
+    }
+
+    /// Runs `update` with the row locked, once `if_match` accepts its
+    /// current version, and audits the before/after diff in the same
+    /// transaction. `update` receives the version it must still find;
+    /// `secret_fields` are recorded as changed without their values.
+    fn update_audited<F>(
+        &self,
+        pool: &DbPool,
+        id: Uuid,
+        if_match: &IfMatch,
+        ctx: &AuditContext,
+        secret_fields: &[&str],
+        update: F,
+    ) -> Result<User, AppError>
+    where
+        F: FnOnce(&mut PgConnection, i32) -> QueryResult<User>,
+    {
+        let mut conn = pool.get()?;
+        conn.transaction(|conn| {
+            let before = UserRepository::lock_by_id(conn, id)?;
+            if_match.check(before.version())?;
+            let after = update(conn, before.version())?;
+            AuditService::record_redacted(
+                conn,
+                ctx,
+                AuditAction::Update,
+                Some(&before),
+                Some(&after),
+                secret_fields,
+            )?;
+            Ok(after)
+        })
+        .map_err(|e: AppError| e.or_not_found(Message::UserNotFound))
     }
 }
@@ -0,0 +1,270 @@
+use std::time::Instant;
+
+use actix_web::{
+    Error, HttpMessage,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    http::header::{HeaderMap, HeaderName, HeaderValue},
+};
+use futures_util::future::{LocalBoxFuture, Ready, ok};
+use opentelemetry::{global, propagation::Extractor};
+use tracing::{Instrument, field::Empty};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::util::request_id::RequestId;
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Opens a span for every request and echoes its id in `X-Request-Id`.
+///
+/// A well-formed incoming `X-Request-Id` is kept so calls can be correlated
+/// across services; otherwise a new one is generated. The id is stored in the
+/// request extensions for the problem responses. `user_id` is recorded on the
+/// span by the auth middleware, `route` and `status` once the request is done.
+///
+/// When trace export is enabled the span becomes the OpenTelemetry server
+/// span, continuing the trace of an incoming W3C `traceparent` header.
+pub struct TracingMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for TracingMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = TracingMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(TracingMiddlewareService { service })
+    }
+}
+
+pub struct TracingMiddlewareService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for TracingMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let started = Instant::now();
+        let request_id = req
+            .headers()
+            .get(&X_REQUEST_ID)
+            .and_then(|value| value.to_str().ok())
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(RequestId::generate);
+        req.extensions_mut().insert(request_id.clone());
+
+        let span = tracing::info_span!(
+            "http_request",
+            request_id = %request_id,
+            method = %req.method(),
+            path = %req.path(),
+            route = Empty,
+            user_id = Empty,
+            status = Empty,
+            otel.name = %format_args!("{} {}", req.method(), req.path()),
+            otel.kind = "server",
+            otel.status_code = Empty,
+        );
+        let parent = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(req.headers()))
+        });
+        // fails only when no OpenTelemetry layer is installed
+        let _ = span.set_parent(parent);
+
+        // entered while calling so inner middleware can record on `Span::current()`
+        let fut = span.in_scope(|| self.service.call(req));
+
+        Box::pin(
+            async move {
+                let mut res = fut.await?;
+
+                let span = tracing::Span::current();
+                let request = res.request();
+                if let Some(route) = request.match_name() {
+                    span.record("route", tracing::field::display(route));
+                }
+                // spans are named after the route pattern rather than the raw path
+                if let Some(pattern) = request.match_pattern() {
+                    if request.match_name().is_none() {
+                        span.record("route", tracing::field::display(&pattern));
+                    }
+                    span.record(
+                        "otel.name",
+                        tracing::field::display(format_args!("{} {}", request.method(), pattern)),
+                    );
+                }
+                let status = res.status();
+                span.record("status", status.as_u16());
+                if status.is_server_error() {
+                    span.record("otel.status_code", "ERROR");
+                }
+
+                let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+                if status.is_server_error() {
+                    tracing::error!(elapsed_ms, "request failed");
+                } else {
+                    tracing::info!(elapsed_ms, "request completed");
+                }
+
+                if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+                    res.headers_mut().insert(X_REQUEST_ID, value);
+                }
+                Ok(res)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App, HttpRequest, HttpResponse,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web,
+    };
+    use opentelemetry::trace::{TraceContextExt, TracerProvider};
+    use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider};
+    use serde_json::Value;
+    use tracing_subscriber::layer::SubscriberExt;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::error::middleware::ProblemMiddleware;
+
+    /// Echoes the id handlers see in the request extensions.
+    async fn seen_id(req: HttpRequest) -> HttpResponse {
+        let id = req
+            .extensions()
+            .get::<RequestId>()
+            .map(RequestId::to_string);
+        HttpResponse::Ok().body(id.unwrap_or_default())
+    }
+
+    async fn call(request_id: Option<&str>, uri: &str) -> ServiceResponse {
+        let app = test::init_service(
+            App::new()
+                .wrap(ProblemMiddleware)
+                .wrap(TracingMiddleware)
+                .route("/seen", web::get().to(seen_id)),
+        )
+        .await;
+        let mut req = TestRequest::get().uri(uri);
+        if let Some(id) = request_id {
+            req = req.insert_header((X_REQUEST_ID, id));
+        }
+        test::call_service(&app, req.to_request()).await
+    }
+
+    fn response_id(res: &ServiceResponse) -> String {
+        res.headers()
+            .get(X_REQUEST_ID)
+            .expect("X-Request-Id is always set")
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[actix_web::test]
+    async fn incoming_ids_reach_handlers_and_are_echoed() {
+        let res = call(Some("upstream-42"), "/seen").await;
+
+        assert_eq!(response_id(&res), "upstream-42");
+        assert_eq!(test::read_body(res).await, "upstream-42");
+    }
+
+    #[actix_web::test]
+    async fn missing_or_malformed_ids_are_replaced() {
+        for incoming in [None, Some("not allowed!")] {
+            let res = call(incoming, "/seen").await;
+            let id = response_id(&res);
+
+            assert!(Uuid::parse_str(&id).is_ok(), "{id}");
+            assert_eq!(test::read_body(res).await, id.as_str());
+        }
+    }
+
+    #[actix_web::test]
+    async fn problem_bodies_carry_the_same_id() {
+        let res = call(Some("trace-me"), "/missing").await;
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response_id(&res), "trace-me");
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["request_id"], "trace-me");
+    }
+
+    /// Answers with the OpenTelemetry trace id of the span handlers run in.
+    async fn trace_id() -> HttpResponse {
+        let context = tracing::Span::current().context();
+        HttpResponse::Ok().body(context.span().span_context().trace_id().to_string())
+    }
+
+    async fn traced(traceparent: Option<&str>) -> String {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        // the test runtime polls everything on this thread
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(TracingMiddleware)
+                .route("/traced", web::get().to(trace_id)),
+        )
+        .await;
+        let mut req = TestRequest::get().uri("/traced");
+        if let Some(traceparent) = traceparent {
+            req = req.insert_header(("traceparent", traceparent));
+        }
+        let body = test::call_and_read_body(&app, req.to_request()).await;
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn incoming_traceparent_is_continued() {
+        let trace_id = traced(Some(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ))
+        .await;
+
+        assert_eq!(trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    }
+
+    #[actix_web::test]
+    async fn requests_without_traceparent_start_a_new_trace() {
+        let first = traced(None).await;
+        let second = traced(Some("garbage")).await;
+
+        assert_ne!(first, "00000000000000000000000000000000");
+        assert_ne!(first, second);
+        assert_ne!(second, "00000000000000000000000000000000");
+    }
+}
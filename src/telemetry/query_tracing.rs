@@ -0,0 +1,111 @@
+use diesel::{
+    Connection,
+    connection::{Instrumentation, InstrumentationEvent},
+    pg::PgConnection,
+    r2d2::{CustomizeConnection, Error},
+};
+use tracing::{Span, field::Empty};
+
+/// Installs a [`QueryTracer`] on every connection the pool opens.
+#[derive(Debug, Clone, Copy)]
+pub struct QueryTracing;
+
+impl CustomizeConnection<PgConnection, Error> for QueryTracing {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        conn.set_instrumentation(QueryTracer::default());
+        Ok(())
+    }
+}
+
+/// Opens a client span per executed statement, as a child of whatever span
+/// is current on the calling thread (see `util::blocking::block`).
+///
+/// Only the SQL text is recorded; bind values may carry personal data.
+#[derive(Default)]
+pub struct QueryTracer {
+    current: Option<Span>,
+}
+
+impl Instrumentation for QueryTracer {
+    fn on_connection_event(&mut self, event: InstrumentationEvent<'_>) {
+        match event {
+            InstrumentationEvent::StartQuery { query, .. } => {
+                let text = query.to_string();
+                let sql = text
+                    .split_once(" -- binds: ")
+                    .map_or(text.as_str(), |(sql, _)| sql);
+                let operation = sql.split_whitespace().next().unwrap_or("QUERY");
+
+                self.current = Some(tracing::info_span!(
+                    "db.query",
+                    otel.name = operation,
+                    otel.kind = "client",
+                    otel.status_code = Empty,
+                    db.system.name = "postgresql",
+                    db.query.text = sql,
+                    error = Empty,
+                ));
+            }
+            InstrumentationEvent::FinishQuery { error, .. } => {
+                if let (Some(span), Some(err)) = (self.current.take(), error) {
+                    span.record("otel.status_code", "ERROR");
+                    span.record("error", tracing::field::display(err));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use diesel::{RunQueryDsl, sql_query};
+    use tracing::{Subscriber, span};
+    use tracing_subscriber::{Layer, layer::Context, layer::SubscriberExt, registry::LookupSpan};
+
+    use super::*;
+    use crate::service::db::test_connection;
+
+    /// A span's name and the name of its parent.
+    type SpanEdge = (String, Option<String>);
+
+    /// Remembers every span opened while it is installed.
+    #[derive(Clone, Default)]
+    struct SpanTree(Arc<Mutex<Vec<SpanEdge>>>);
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SpanTree {
+        fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+            let parent = ctx
+                .span(id)
+                .and_then(|span| span.parent())
+                .map(|parent| parent.name().to_string());
+            self.0
+                .lock()
+                .unwrap()
+                .push((attrs.metadata().name().to_string(), parent));
+        }
+    }
+
+    #[test]
+    #[ignore = "needs DATABASE_URL"]
+    fn each_statement_gets_a_child_span() {
+        let spans = SpanTree::default();
+        let subscriber = tracing_subscriber::registry().with(spans.clone());
+        let mut conn = test_connection();
+        conn.set_instrumentation(QueryTracer::default());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("http_request").in_scope(|| {
+                sql_query("SELECT 1").execute(&mut conn).unwrap();
+            });
+        });
+
+        let spans = spans.0.lock().unwrap();
+        assert!(
+            spans.contains(&("db.query".into(), Some("http_request".into()))),
+            "{spans:?}"
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use std::convert::Infallible;
+
+use actix_web::{FromRequest, HttpMessage, HttpRequest, dev::Payload};
+use futures_util::future::{Ready, ready};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{auth::claims::Claims, util::request_id::RequestId};
+
+/// Who is making a change and from where, as stored with each audit event.
+///
+/// `actor` comes from the `Claims` placed in the request by the auth
+/// middleware, so it is `None` on public routes such as registration. `ip`
+/// is the socket peer; forwarding headers are ignored since clients control
+/// them. Jobs carry the context of the request that enqueued them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditContext {
+    pub actor: Option<Uuid>,
+    pub request_id: Option<String>,
+    pub ip: Option<String>,
+}
+
+impl FromRequest for AuditContext {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let extensions = req.extensions();
+
+        ready(Ok(AuditContext {
+            actor: extensions.get::<Claims>().map(|claims| *claims.user_id()),
+            request_id: extensions.get::<RequestId>().map(RequestId::to_string),
+            ip: req.peer_addr().map(|addr| addr.ip().to_string()),
+        }))
+    }
+}
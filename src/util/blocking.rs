@@ -0,0 +1,23 @@
+use std::time::Instant;
+
+use actix_web::{error::BlockingError, web};
+
+use crate::metrics::METRICS;
+
+/// `web::block` that records how long the closure waited for a thread and
+/// runs it inside the caller's span, so database spans nest under the request.
+pub async fn block<F, R>(f: F) -> Result<R, BlockingError>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let queued = Instant::now();
+    let span = tracing::Span::current();
+    web::block(move || {
+        METRICS
+            .blocking_queue
+            .observe(queued.elapsed().as_secs_f64());
+        span.in_scope(f)
+    })
+    .await
+}
@@ -0,0 +1,69 @@
+use actix_multipart::{Multipart, MultipartError};
+use actix_web::{
+    FromRequest, HttpRequest,
+    dev::Payload,
+    web::{self, BytesMut},
+};
+use futures_util::{TryStreamExt, future::LocalBoxFuture};
+
+use crate::{error::app_error::AppError, i18n::Message};
+
+/// Multipart field carrying the file.
+pub const FILE_FIELD: &str = "file";
+
+/// Largest file accepted, in bytes.
+pub const MAX_CSV_BYTES: usize = 10 * 1024 * 1024;
+
+/// A CSV file sent as the `file` field of a `multipart/form-data` body.
+///
+/// The whole file is held in memory, which is what bounds it: the body is
+/// read chunk by chunk and rejected with 413 as soon as the file grows past
+/// [`MAX_CSV_BYTES`]. Other fields are skipped. A body that is not multipart
+/// is 415.
+pub struct CsvUpload(pub web::Bytes);
+
+impl FromRequest for CsvUpload {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let multipart = Multipart::from_request(req, payload);
+
+        Box::pin(async move {
+            let mut multipart = multipart
+                .await
+                .map_err(|e| AppError::validation(Message::MalformedBody(e.to_string())))?;
+            let mut file = None;
+
+            while let Some(mut field) = multipart.try_next().await.map_err(multipart_error)? {
+                if file.is_some() || field.name() != Some(FILE_FIELD) {
+                    while field.try_next().await.map_err(multipart_error)?.is_some() {}
+                    continue;
+                }
+
+                let mut buffer = BytesMut::new();
+                while let Some(chunk) = field.try_next().await.map_err(multipart_error)? {
+                    if buffer.len() + chunk.len() > MAX_CSV_BYTES {
+                        return Err(AppError::payload_too_large(MAX_CSV_BYTES));
+                    }
+                    buffer.extend_from_slice(&chunk);
+                }
+                file = Some(buffer.freeze());
+            }
+
+            file.map(CsvUpload)
+                .ok_or_else(|| AppError::invalid_field(FILE_FIELD, Message::Required))
+        })
+    }
+}
+
+fn multipart_error(err: MultipartError) -> AppError {
+    match err {
+        MultipartError::ContentTypeMissing
+        | MultipartError::ContentTypeParse
+        | MultipartError::ContentTypeIncompatible => {
+            AppError::unsupported_media_type(&["multipart/form-data"])
+        }
+        other => AppError::validation(Message::MalformedBody(other.to_string())),
+    }
+}
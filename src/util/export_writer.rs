@@ -0,0 +1,158 @@
+use actix_web::web::Bytes;
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::error::app_error::AppError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// One JSON object per line.
+    Ndjson,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// Encodes flat records, whose fields are `columns`, in an [`ExportFormat`].
+///
+/// CSV and NDJSON are produced incrementally: [`ExportWriter::take_chunk`]
+/// hands out what was encoded so far. An XLSX file is only readable once
+/// complete, so its rows are spooled to a temporary file and all of it comes
+/// out of [`ExportWriter::finish`].
+pub enum ExportWriter {
+    Csv(Box<csv::Writer<Vec<u8>>>),
+    Ndjson(Vec<u8>),
+    Xlsx {
+        workbook: Box<Workbook>,
+        columns: &'static [&'static str],
+        row: u32,
+    },
+}
+
+impl ExportWriter {
+    pub fn new(format: ExportFormat, columns: &'static [&'static str]) -> Result<Self, AppError> {
+        match format {
+            ExportFormat::Csv => {
+                let mut writer = csv_writer();
+                writer.write_record(columns).map_err(csv_error)?;
+                Ok(ExportWriter::Csv(Box::new(writer)))
+            }
+            ExportFormat::Ndjson => Ok(ExportWriter::Ndjson(Vec::new())),
+            ExportFormat::Xlsx => {
+                let mut workbook = Workbook::new();
+                let sheet = workbook.add_worksheet_with_constant_memory();
+                let bold = Format::new().set_bold();
+                for (col, name) in (0..).zip(columns) {
+                    sheet
+                        .write_string_with_format(0, col, *name, &bold)
+                        .map_err(xlsx_error)?;
+                }
+                sheet.set_freeze_panes(1, 0).map_err(xlsx_error)?;
+                Ok(ExportWriter::Xlsx {
+                    workbook: Box::new(workbook),
+                    columns,
+                    row: 1,
+                })
+            }
+        }
+    }
+
+    pub fn write<T: Serialize>(&mut self, record: &T) -> Result<(), AppError> {
+        match self {
+            ExportWriter::Csv(writer) => writer.serialize(record).map_err(csv_error),
+            ExportWriter::Ndjson(buffer) => {
+                serde_json::to_writer(&mut *buffer, record)
+                    .map_err(|e| AppError::internal(format!("ndjson export error: {e}")))?;
+                buffer.push(b'\n');
+                Ok(())
+            }
+            ExportWriter::Xlsx {
+                workbook,
+                columns,
+                row,
+            } => {
+                let Value::Object(fields) = serde_json::to_value(record)
+                    .map_err(|e| AppError::internal(format!("xlsx export error: {e}")))?
+                else {
+                    return Err(AppError::internal("xlsx export error: record is not flat"));
+                };
+                let sheet = workbook.worksheet_from_index(0).map_err(xlsx_error)?;
+                for (col, name) in (0..).zip(columns.iter()) {
+                    match fields.get(*name) {
+                        Some(Value::String(text)) => sheet.write_string(*row, col, text),
+                        Some(Value::Number(number)) => {
+                            sheet.write_number(*row, col, number.as_f64().unwrap_or_default())
+                        }
+                        Some(Value::Bool(flag)) => sheet.write_boolean(*row, col, *flag),
+                        _ => continue,
+                    }
+                    .map_err(xlsx_error)?;
+                }
+                *row += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Output encoded since the previous call; always empty for XLSX.
+    pub fn take_chunk(&mut self) -> Result<Bytes, AppError> {
+        match self {
+            ExportWriter::Csv(writer) => {
+                let encoded = std::mem::replace(&mut **writer, csv_writer())
+                    .into_inner()
+                    .map_err(|e| csv_error(e.into_error().into()))?;
+                Ok(Bytes::from(encoded))
+            }
+            ExportWriter::Ndjson(buffer) => Ok(Bytes::from(std::mem::take(buffer))),
+            ExportWriter::Xlsx { .. } => Ok(Bytes::new()),
+        }
+    }
+
+    /// The rest of the output.
+    pub fn finish(mut self) -> Result<Bytes, AppError> {
+        match &mut self {
+            ExportWriter::Xlsx { workbook, .. } => {
+                Ok(Bytes::from(workbook.save_to_buffer().map_err(xlsx_error)?))
+            }
+            _ => self.take_chunk(),
+        }
+    }
+}
+
+/// The header row is written once, by hand, so it is there even without rows.
+fn csv_writer() -> csv::Writer<Vec<u8>> {
+    csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new())
+}
+
+fn csv_error(err: csv::Error) -> AppError {
+    AppError::internal(format!("csv export error: {err}"))
+}
+
+fn xlsx_error(err: XlsxError) -> AppError {
+    AppError::internal(format!("xlsx export error: {err}"))
+}
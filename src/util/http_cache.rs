@@ -0,0 +1,168 @@
+use std::time::SystemTime;
+
+use actix_web::{
+    HttpRequest, HttpResponse, HttpResponseBuilder,
+    http::header::{
+        self, CacheControl, CacheDirective, EntityTag, Header, HttpDate, IfModifiedSince,
+        IfNoneMatch,
+    },
+};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// Validators of a representation, checked against the conditional headers
+/// of a GET.
+///
+/// Responses are `private, no-cache`: clients may store them but must
+/// revalidate, which costs a 304 when nothing changed. They also vary on
+/// `Authorization` since admins get more links than other users.
+pub struct Validators {
+    etag: EntityTag,
+    last_modified: Option<NaiveDateTime>,
+}
+
+impl Validators {
+    pub fn new(etag: EntityTag) -> Self {
+        Self {
+            etag,
+            last_modified: None,
+        }
+    }
+
+    /// `updated_at` of the resource, sent as `Last-Modified`.
+    pub fn last_modified(mut self, at: NaiveDateTime) -> Self {
+        self.last_modified = Some(at);
+        self
+    }
+
+    /// 304 if the client's copy is still current, else 200 with `body()`.
+    pub fn respond<T, F>(&self, req: &HttpRequest, body: F) -> HttpResponse
+    where
+        T: Serialize,
+        F: FnOnce() -> T,
+    {
+        if self.is_fresh(req) {
+            self.headers(HttpResponse::NotModified()).finish()
+        } else {
+            self.headers(HttpResponse::Ok()).json(body())
+        }
+    }
+
+    /// `If-None-Match` takes precedence; `If-Modified-Since` is only looked
+    /// at without it (RFC 9110, section 13.2.2).
+    fn is_fresh(&self, req: &HttpRequest) -> bool {
+        if req.headers().contains_key(header::IF_NONE_MATCH) {
+            return match IfNoneMatch::parse(req) {
+                Ok(IfNoneMatch::Any) => true,
+                Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&self.etag)),
+                Err(_) => false,
+            };
+        }
+
+        match (self.last_modified, IfModifiedSince::parse(req)) {
+            (Some(modified), Ok(IfModifiedSince(since))) => {
+                // HTTP dates have whole-second precision
+                let since = chrono::DateTime::<chrono::Utc>::from(SystemTime::from(since));
+                modified.and_utc().timestamp() <= since.timestamp()
+            }
+            _ => false,
+        }
+    }
+
+    fn headers(&self, mut builder: HttpResponseBuilder) -> HttpResponseBuilder {
+        builder
+            .insert_header(header::ETag(self.etag.clone()))
+            .insert_header(CacheControl(vec![
+                CacheDirective::Private,
+                CacheDirective::NoCache,
+            ]))
+            .insert_header((header::VARY, header::AUTHORIZATION.as_str()));
+
+        if let Some(modified) = self.last_modified {
+            let modified = SystemTime::from(modified.and_utc());
+            builder.insert_header(header::LastModified(HttpDate::from(modified)));
+        }
+
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test::TestRequest};
+    use chrono::NaiveDate;
+
+    fn updated_at() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_milli_opt(12, 30, 15, 250)
+            .unwrap()
+    }
+
+    fn validators() -> Validators {
+        Validators::new(EntityTag::new_strong("3-admin".into())).last_modified(updated_at())
+    }
+
+    fn status(req: TestRequest) -> StatusCode {
+        validators()
+            .respond(&req.to_http_request(), || "body")
+            .status()
+    }
+
+    #[test]
+    fn unconditional_get_is_200_with_validators() {
+        let res = validators().respond(&TestRequest::default().to_http_request(), || "body");
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"3-admin\"");
+        assert_eq!(
+            res.headers().get(header::LAST_MODIFIED).unwrap(),
+            "Mon, 19 Oct 2026 12:30:15 GMT"
+        );
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            "private, no-cache"
+        );
+    }
+
+    #[test]
+    fn matching_if_none_match_is_304() {
+        let req = TestRequest::default().insert_header((header::IF_NONE_MATCH, "\"3-admin\""));
+        assert_eq!(status(req), StatusCode::NOT_MODIFIED);
+
+        // compared weakly
+        let req = TestRequest::default().insert_header((header::IF_NONE_MATCH, "W/\"3-admin\""));
+        assert_eq!(status(req), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::default().insert_header((header::IF_NONE_MATCH, "*"));
+        assert_eq!(status(req), StatusCode::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn other_variant_or_version_is_200() {
+        let req = TestRequest::default().insert_header((header::IF_NONE_MATCH, "\"3-user\""));
+        assert_eq!(status(req), StatusCode::OK);
+
+        let req = TestRequest::default().insert_header((header::IF_NONE_MATCH, "\"2-admin\""));
+        assert_eq!(status(req), StatusCode::OK);
+    }
+
+    #[test]
+    fn if_modified_since_compares_whole_seconds() {
+        let req = TestRequest::default()
+            .insert_header((header::IF_MODIFIED_SINCE, "Mon, 19 Oct 2026 12:30:15 GMT"));
+        assert_eq!(status(req), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MODIFIED_SINCE, "Mon, 19 Oct 2026 12:30:14 GMT"));
+        assert_eq!(status(req), StatusCode::OK);
+    }
+
+    #[test]
+    fn if_none_match_takes_precedence_over_if_modified_since() {
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"2-admin\""))
+            .insert_header((header::IF_MODIFIED_SINCE, "Mon, 19 Oct 2026 12:30:15 GMT"));
+        assert_eq!(status(req), StatusCode::OK);
+    }
+}
@@ -0,0 +1,162 @@
+use actix_web::{
+    FromRequest, HttpRequest,
+    dev::Payload,
+    http::header::{self, EntityTag, Header},
+    web,
+};
+use futures_util::future::{Ready, ready};
+
+use crate::{config::AppConfig, error::app_error::AppError};
+
+/// Strong entity tag for a row version.
+pub fn version_tag(version: i32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+/// Strong entity tag for one of several representations of a row version,
+/// such as the one admins get. Accepted by [`IfMatch`] like the version's
+/// own tag.
+pub fn variant_tag(version: i32, variant: &str) -> EntityTag {
+    EntityTag::new_strong(format!("{version}-{variant}"))
+}
+
+/// `ETag` header for a row version.
+pub fn etag(version: i32) -> header::ETag {
+    header::ETag(version_tag(version))
+}
+
+/// `If-Match` precondition of a write, checked against the row version.
+///
+/// Without the header any version matches, unless `REQUIRE_IF_MATCH` is set
+/// and extraction fails with 428 instead. Tags are compared strongly, so
+/// weak or malformed ones never match.
+#[derive(Debug, Clone, Default)]
+pub enum IfMatch {
+    #[default]
+    Any,
+    Tags(Vec<EntityTag>),
+}
+
+impl IfMatch {
+    /// Fails with 412 unless `current` is one of the listed versions.
+    pub fn check(&self, current: i32) -> Result<(), AppError> {
+        match self {
+            IfMatch::Any => Ok(()),
+            IfMatch::Tags(tags) => {
+                let current = current.to_string();
+                if tags.iter().any(|tag| tag_version(tag) == Some(&current)) {
+                    Ok(())
+                } else {
+                    Err(AppError::PreconditionFailed)
+                }
+            }
+        }
+    }
+}
+
+/// The row version a strong tag was made for, with any variant stripped.
+fn tag_version(tag: &EntityTag) -> Option<&str> {
+    if tag.weak {
+        return None;
+    }
+    Some(
+        tag.tag()
+            .split_once('-')
+            .map_or(tag.tag(), |(version, _)| version),
+    )
+}
+
+impl FromRequest for IfMatch {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        if !req.headers().contains_key(header::IF_MATCH) {
+            let required = req
+                .app_data::<web::Data<AppConfig>>()
+                .is_some_and(|config| config.require_if_match());
+
+            return ready(if required {
+                Err(AppError::PreconditionRequired)
+            } else {
+                Ok(IfMatch::Any)
+            });
+        }
+
+        let if_match = match header::IfMatch::parse(req) {
+            Ok(header::IfMatch::Any) => IfMatch::Any,
+            Ok(header::IfMatch::Items(tags)) => IfMatch::Tags(tags),
+            Err(_) => IfMatch::Tags(Vec::new()),
+        };
+
+        ready(Ok(if_match))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test::TestRequest};
+
+    fn extract(req: TestRequest) -> Result<IfMatch, AppError> {
+        let (req, mut payload) = req.to_http_parts();
+        IfMatch::from_request(&req, &mut payload).into_inner()
+    }
+
+    fn status(result: Result<(), AppError>) -> Option<StatusCode> {
+        result
+            .err()
+            .map(|e| actix_web::ResponseError::status_code(&e))
+    }
+
+    #[test]
+    fn missing_header_matches_any_version() {
+        let if_match = extract(TestRequest::default()).unwrap();
+        assert!(if_match.check(7).is_ok());
+    }
+
+    #[test]
+    fn missing_header_is_428_when_required() {
+        let config = AppConfig::for_tests().with_require_if_match(true);
+        let result = extract(TestRequest::default().app_data(web::Data::new(config)));
+        assert!(matches!(result, Err(AppError::PreconditionRequired)));
+    }
+
+    #[test]
+    fn matches_the_current_version_only() {
+        let if_match =
+            extract(TestRequest::default().insert_header(("If-Match", "\"3\""))).unwrap();
+        assert!(if_match.check(3).is_ok());
+        assert_eq!(
+            status(if_match.check(4)),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+    }
+
+    #[test]
+    fn matches_any_listed_tag_and_star() {
+        let listed =
+            extract(TestRequest::default().insert_header(("If-Match", "\"1\", \"2\""))).unwrap();
+        assert!(listed.check(2).is_ok());
+
+        let star = extract(TestRequest::default().insert_header(("If-Match", "*"))).unwrap();
+        assert!(star.check(9).is_ok());
+    }
+
+    #[test]
+    fn accepts_variant_tags_of_the_version() {
+        let tag = variant_tag(5, "admin").to_string();
+        let if_match = extract(TestRequest::default().insert_header(("If-Match", tag))).unwrap();
+        assert!(if_match.check(5).is_ok());
+        assert!(if_match.check(6).is_err());
+    }
+
+    #[test]
+    fn weak_and_malformed_tags_never_match() {
+        let weak = extract(TestRequest::default().insert_header(("If-Match", "W/\"3\""))).unwrap();
+        assert!(weak.check(3).is_err());
+
+        let malformed = extract(TestRequest::default().insert_header(("If-Match", "3"))).unwrap();
+        assert!(malformed.check(3).is_err());
+    }
+}
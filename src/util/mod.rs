@@ -1 +1,11 @@
 pub mod app_state;
+pub mod audit_context;
+pub mod blocking;
+pub mod csv_upload;
+pub mod export_writer;
+pub mod http_cache;
+pub mod if_match;
+pub mod patch_document;
+pub mod request_id;
+pub mod validated_json;
+pub mod validation;
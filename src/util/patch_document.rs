@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+
+use actix_web::{FromRequest, HttpMessage, HttpRequest, dev::Payload, web};
+use futures_util::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    error::{app_error::AppError, extractor_config::body_error},
+    i18n::Message,
+    util::validation::{FieldErrors, Validate},
+};
+
+pub const MERGE_PATCH: &str = "application/merge-patch+json";
+pub const JSON_PATCH: &str = "application/json-patch+json";
+
+/// Largest body accepted, in bytes; actix's own default for buffered bodies.
+const MAX_PATCH_BYTES: usize = 256 * 1024;
+
+/// Body of a partial update, told apart by `Content-Type`: a JSON Merge
+/// Patch (RFC 7396) or a JSON Patch (RFC 6902). Anything else is 415.
+pub enum PatchDocument {
+    Merge(Value),
+    Json(json_patch::Patch),
+}
+
+impl PatchDocument {
+    /// Patches `target`, an object with the editable fields of a resource,
+    /// and reads the result back as `T` with its validation rules applied.
+    /// Fields absent from `target` cannot be added.
+    pub fn apply_to<T>(&self, mut target: Value) -> Result<T, AppError>
+    where
+        T: DeserializeOwned + Validate,
+    {
+        let editable: HashSet<String> = target
+            .as_object()
+            .map(|fields| fields.keys().cloned().collect())
+            .unwrap_or_default();
+
+        match self {
+            PatchDocument::Merge(patch) => json_patch::merge(&mut target, patch),
+            PatchDocument::Json(patch) => json_patch::patch(&mut target, patch)
+                .map_err(|e| AppError::validation(Message::PatchFailed(e.to_string())))?,
+        }
+
+        let Some(fields) = target.as_object() else {
+            return Err(AppError::validation(Message::PatchFailed(
+                "the result is not an object".into(),
+            )));
+        };
+
+        let mut errors = FieldErrors::default();
+        for field in fields.keys().filter(|field| !editable.contains(*field)) {
+            errors.add(field, Message::UnknownField);
+        }
+        errors.into_result()?;
+
+        let document: T = serde_json::from_value(target).map_err(|e| body_error(&e))?;
+
+        let mut errors = FieldErrors::default();
+        document.validate(&mut errors);
+        errors.into_result()?;
+
+        Ok(document)
+    }
+}
+
+impl FromRequest for PatchDocument {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let content_type = req.content_type().to_ascii_lowercase();
+        let body = web::Payload::from_request(req, payload);
+
+        Box::pin(async move {
+            if content_type != MERGE_PATCH && content_type != JSON_PATCH {
+                return Err(AppError::unsupported_media_type(&[MERGE_PATCH, JSON_PATCH]));
+            }
+
+            let malformed =
+                |e: actix_web::Error| AppError::validation(Message::MalformedBody(e.to_string()));
+            let body = body
+                .await
+                .map_err(malformed)?
+                .to_bytes_limited(MAX_PATCH_BYTES)
+                .await
+                .map_err(|_| AppError::payload_too_large(MAX_PATCH_BYTES))?
+                .map_err(malformed)?;
+
+            let document = if content_type == MERGE_PATCH {
+                serde_json::from_slice(&body).map(PatchDocument::Merge)
+            } else {
+                serde_json::from_slice(&body).map(PatchDocument::Json)
+            };
+            document.map_err(|e| body_error(&e))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Contact {
+        email: String,
+        phone: Option<String>,
+    }
+
+    impl Validate for Contact {
+        fn validate(&self, errors: &mut FieldErrors) {
+            errors.email("email", &self.email);
+        }
+    }
+
+    fn current() -> Value {
+        json!({ "email": "ana@example.com", "phone": "11 5555-0000" })
+    }
+
+    /// `(field, code)` of every field error, or the detail code alone.
+    fn codes(error: AppError) -> Vec<(String, &'static str)> {
+        match error {
+            AppError::Validation { errors, .. } if !errors.is_empty() => errors
+                .into_iter()
+                .map(|e| (e.field, e.message.code()))
+                .collect(),
+            AppError::Validation { detail, .. } => vec![(String::new(), detail.code())],
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    async fn extract(content_type: &str, body: &str) -> Result<PatchDocument, AppError> {
+        let (req, mut payload) = TestRequest::default()
+            .insert_header(("Content-Type", content_type))
+            .set_payload(body.to_string())
+            .to_http_parts();
+        PatchDocument::from_request(&req, &mut payload).await
+    }
+
+    #[test]
+    fn merge_patch_sets_and_removes_fields() {
+        let patch = PatchDocument::Merge(json!({ "email": "bia@example.com", "phone": null }));
+        let contact: Contact = patch.apply_to(current()).unwrap();
+        assert_eq!(
+            contact,
+            Contact {
+                email: "bia@example.com".into(),
+                phone: None,
+            }
+        );
+    }
+
+    #[test]
+    fn json_patch_applies_operations_in_order() {
+        let patch = PatchDocument::Json(
+            serde_json::from_value(json!([
+                { "op": "test", "path": "/email", "value": "ana@example.com" },
+                { "op": "replace", "path": "/phone", "value": "11 5555-1111" }
+            ]))
+            .unwrap(),
+        );
+        let contact: Contact = patch.apply_to(current()).unwrap();
+        assert_eq!(contact.phone.as_deref(), Some("11 5555-1111"));
+    }
+
+    #[test]
+    fn failed_json_patch_test_is_a_validation_error() {
+        let patch = PatchDocument::Json(
+            serde_json::from_value(json!([
+                { "op": "test", "path": "/email", "value": "someone@else.com" },
+                { "op": "remove", "path": "/phone" }
+            ]))
+            .unwrap(),
+        );
+        let error = patch.apply_to::<Contact>(current()).unwrap_err();
+        assert_eq!(codes(error), [(String::new(), "validation.patch_failed")]);
+    }
+
+    #[test]
+    fn fields_cannot_be_added() {
+        let patch = PatchDocument::Merge(json!({ "role": "admin", "id": 1 }));
+        let mut fields: Vec<_> = codes(patch.apply_to::<Contact>(current()).unwrap_err())
+            .into_iter()
+            .map(|(field, code)| {
+                assert_eq!(code, "validation.unknown_field");
+                field
+            })
+            .collect();
+        fields.sort();
+        assert_eq!(fields, ["id", "role"]);
+    }
+
+    #[test]
+    fn the_result_is_validated() {
+        let patch = PatchDocument::Merge(json!({ "email": "not an email" }));
+        let error = patch.apply_to::<Contact>(current()).unwrap_err();
+        assert_eq!(codes(error), [("email".into(), "validation.invalid_email")]);
+    }
+
+    #[test]
+    fn a_result_that_is_not_an_object_is_rejected() {
+        let patch = PatchDocument::Merge(json!("replaced"));
+        let error = patch.apply_to::<Contact>(current()).unwrap_err();
+        assert_eq!(codes(error), [(String::new(), "validation.patch_failed")]);
+    }
+
+    #[actix_web::test]
+    async fn content_type_picks_the_format() {
+        let merge = extract(MERGE_PATCH, r#"{"phone":null}"#).await.unwrap();
+        assert!(matches!(merge, PatchDocument::Merge(_)));
+
+        let json = extract(JSON_PATCH, r#"[{"op":"remove","path":"/phone"}]"#)
+            .await
+            .unwrap();
+        assert!(matches!(json, PatchDocument::Json(_)));
+    }
+
+    #[actix_web::test]
+    async fn other_content_types_are_415() {
+        let error = extract("application/json", "{}").await.err().unwrap();
+        assert!(matches!(
+            error,
+            AppError::UnsupportedMediaType(Message::UnsupportedMediaType { ref expected })
+                if expected == "application/merge-patch+json, application/json-patch+json"
+        ));
+    }
+
+    #[actix_web::test]
+    async fn bodies_past_the_limit_are_413() {
+        let body = format!(r#"{{"phone":"{}"}}"#, "9".repeat(MAX_PATCH_BYTES));
+        let error = extract(MERGE_PATCH, &body).await.err().unwrap();
+        assert!(matches!(
+            error,
+            AppError::PayloadTooLarge(Message::PayloadTooLarge {
+                max: MAX_PATCH_BYTES
+            })
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt::{Display, Formatter};
+
+use uuid::Uuid;
+
+/// Longest `X-Request-Id` accepted from clients.
+const MAX_LEN: usize = 128;
+
+/// Identifier attached to every request and echoed in problem responses.
+#[derive(Debug, Clone)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn generate() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    /// Accepts a client-supplied id made of ASCII letters, digits, `-`, `_`
+    /// and `.`; anything else is rejected so it cannot corrupt log lines.
+    pub fn from_header(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_LEN
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'));
+
+        valid.then(|| Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_ids_are_kept_when_safe_to_log() {
+        for id in ["abc-123", "req_42.retry", &"a".repeat(MAX_LEN)] {
+            assert_eq!(RequestId::from_header(id).unwrap().as_str(), id);
+        }
+    }
+
+    #[test]
+    fn unsafe_or_oversized_ids_are_rejected() {
+        for id in [
+            "",
+            "has space",
+            "line\nbreak",
+            "ação",
+            &"a".repeat(MAX_LEN + 1),
+        ] {
+            assert!(RequestId::from_header(id).is_none(), "{id:?}");
+        }
+    }
+
+    #[test]
+    fn generated_ids_are_unique_uuids() {
+        let (a, b) = (RequestId::generate(), RequestId::generate());
+        assert_ne!(a.as_str(), b.as_str());
+        assert!(Uuid::parse_str(a.as_str()).is_ok());
+    }
+}
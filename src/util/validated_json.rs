@@ -0,0 +1,103 @@
+use std::ops::Deref;
+
+use actix_web::{Error, FromRequest, HttpRequest, dev::Payload, web};
+use futures_util::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+
+use crate::util::validation::{FieldErrors, Validate};
+
+/// `web::Json<T>` that also runs `T`'s validation rules, rejecting the
+/// request with every failed field before the handler runs.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T> ValidatedJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for ValidatedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = web::Json::<T>::from_request(req, payload);
+
+        Box::pin(async move {
+            let body = json.await?.into_inner();
+
+            let mut errors = FieldErrors::default();
+            body.validate(&mut errors);
+            errors.into_result()?;
+
+            Ok(ValidatedJson(body))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{error::app_error::AppError, i18n::Message};
+
+    #[derive(Deserialize)]
+    struct Signup {
+        name: String,
+        email: String,
+    }
+
+    impl Validate for Signup {
+        fn validate(&self, errors: &mut FieldErrors) {
+            errors.name("name", &self.name);
+            errors.email("email", &self.email);
+        }
+    }
+
+    async fn extract(body: &str) -> Result<ValidatedJson<Signup>, Error> {
+        let (req, mut payload) = TestRequest::post()
+            .insert_header(("Content-Type", "application/json"))
+            .set_payload(body.to_string())
+            .to_http_parts();
+        ValidatedJson::<Signup>::from_request(&req, &mut payload).await
+    }
+
+    #[actix_web::test]
+    async fn valid_bodies_reach_the_handler() {
+        let signup = extract(r#"{"name":"Ana","email":"ana@example.com"}"#)
+            .await
+            .unwrap();
+        assert_eq!(signup.name, "Ana");
+    }
+
+    #[actix_web::test]
+    async fn every_failed_rule_is_reported() {
+        let error = extract(r#"{"name":"","email":"ana"}"#).await.err().unwrap();
+        match error.as_error::<AppError>() {
+            Some(AppError::Validation { errors, .. }) => {
+                let fields: Vec<_> = errors
+                    .iter()
+                    .map(|e| (e.field.as_str(), &e.message))
+                    .collect();
+                assert_eq!(
+                    fields,
+                    [("name", &Message::Blank), ("email", &Message::InvalidEmail)]
+                );
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+}
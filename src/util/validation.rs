@@ -0,0 +1,313 @@
+use chrono::{Datelike, NaiveDate, Utc};
+
+use crate::error::{app_error::AppError, problem::FieldError};
+use crate::i18n::Message;
+use crate::model::cpf::Cpf;
+
+const EMAIL_MAX_LEN: usize = 254;
+const EMAIL_LOCAL_MAX_LEN: usize = 64;
+const DOMAIN_LABEL_MAX_LEN: usize = 63;
+const NAME_MAX_LEN: usize = 150;
+pub const PASSWORD_MIN_LEN: usize = 6;
+/// bcrypt only looks at the first 72 bytes.
+const PASSWORD_MAX_LEN: usize = 72;
+const PHONE_MIN_DIGITS: usize = 10;
+const PHONE_MAX_DIGITS: usize = 13;
+const OLDEST_BIRTH_YEAR: i32 = 1900;
+
+/// RFC 5322 `addr-spec` restricted to the dot-atom form, which is what
+/// real-world addresses use; quoted local parts and address literals are
+/// rejected.
+pub fn is_valid_email(email: &str) -> bool {
+    if email.len() > EMAIL_MAX_LEN {
+        return false;
+    }
+
+    match email.rsplit_once('@') {
+        Some((local, domain)) => {
+            local.len() <= EMAIL_LOCAL_MAX_LEN && is_dot_atom(local) && is_valid_domain(domain)
+        }
+        None => false,
+    }
+}
+
+fn is_dot_atom(value: &str) -> bool {
+    let atext = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~-".contains(c);
+    !value.is_empty()
+        && value
+            .split('.')
+            .all(|atom| !atom.is_empty() && atom.chars().all(atext))
+}
+
+fn is_valid_domain(domain: &str) -> bool {
+    let labels: Vec<&str> = domain.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= DOMAIN_LABEL_MAX_LEN
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Accepts Brazilian numbers with area code, with or without `+55` and
+/// punctuation, e.g. `(11) 98765-4321` or `+55 11 98765 4321`.
+pub fn is_valid_phone(phone: &str) -> bool {
+    let allowed = |c: char| c.is_ascii_digit() || matches!(c, '+' | '(' | ')' | '-' | ' ');
+    let digits = phone.chars().filter(char::is_ascii_digit).count();
+
+    phone.chars().all(allowed) && (PHONE_MIN_DIGITS..=PHONE_MAX_DIGITS).contains(&digits)
+}
+
+pub fn is_valid_birth_date(birth_date: NaiveDate) -> bool {
+    birth_date.year() >= OLDEST_BIRTH_YEAR && birth_date <= Utc::now().date_naive()
+}
+
+/// Request bodies checked by `ValidatedJson` before reaching a handler.
+pub trait Validate {
+    fn validate(&self, errors: &mut FieldErrors);
+}
+
+/// Collects every failed rule so clients get all field errors at once.
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &str, message: Message) {
+        self.0.push(FieldError::new(field, message));
+    }
+
+    pub fn check(&mut self, valid: bool, field: &str, message: Message) {
+        if !valid {
+            self.add(field, message);
+        }
+    }
+
+    /// Non-blank; returns whether the value passed so callers can skip
+    /// further rules on it.
+    pub fn required(&mut self, field: &str, value: &str) -> bool {
+        let present = !value.trim().is_empty();
+        self.check(present, field, Message::Blank);
+        present
+    }
+
+    pub fn max_chars(&mut self, field: &str, value: &str, max: usize) {
+        if value.chars().count() > max {
+            self.add(field, Message::TooLong { max });
+        }
+    }
+
+    pub fn name(&mut self, field: &str, value: &str) {
+        if self.required(field, value) {
+            self.max_chars(field, value.trim(), NAME_MAX_LEN);
+        }
+    }
+
+    pub fn email(&mut self, field: &str, value: &str) {
+        if self.required(field, value) {
+            self.check(is_valid_email(value.trim()), field, Message::InvalidEmail);
+        }
+    }
+
+    pub fn password(&mut self, field: &str, value: &str) {
+        if value.len() < PASSWORD_MIN_LEN {
+            self.add(
+                field,
+                Message::TooShort {
+                    min: PASSWORD_MIN_LEN,
+                },
+            );
+        } else if value.len() > PASSWORD_MAX_LEN {
+            self.add(
+                field,
+                Message::PasswordTooLong {
+                    max: PASSWORD_MAX_LEN,
+                },
+            );
+        }
+    }
+
+    /// Accepts exactly what [`Cpf::parse`] does.
+    pub fn cpf(&mut self, field: &str, value: &str) {
+        if self.required(field, value) {
+            self.check(Cpf::parse(value).is_ok(), field, Message::InvalidCpf);
+        }
+    }
+
+    pub fn birth_date(&mut self, field: &str, value: Option<NaiveDate>) {
+        if let Some(date) = value {
+            self.check(is_valid_birth_date(date), field, Message::InvalidBirthDate);
+        }
+    }
+
+    /// Blank optional emails are treated as absent.
+    pub fn optional_email(&mut self, field: &str, value: Option<&str>) {
+        if let Some(email) = value.map(str::trim).filter(|e| !e.is_empty()) {
+            self.check(is_valid_email(email), field, Message::InvalidEmail);
+        }
+    }
+
+    /// Blank optional phones are treated as absent.
+    pub fn optional_phone(&mut self, field: &str, value: Option<&str>) {
+        if let Some(phone) = value.map(str::trim).filter(|p| !p.is_empty()) {
+            self.check(is_valid_phone(phone), field, Message::InvalidPhone);
+        }
+    }
+
+    /// The collected errors, for callers reporting them outside a problem body.
+    pub fn into_vec(self) -> Vec<FieldError> {
+        self.0
+    }
+
+    pub fn into_result(self) -> Result<(), AppError> {
+        match self.0.len() {
+            0 => Ok(()),
+            1 => Err(AppError::from(self.0.into_iter().next().unwrap())),
+            n => Err(AppError::Validation {
+                detail: Message::FieldsInvalid { count: n },
+                errors: self.0,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpf_errors(value: &str) -> Vec<FieldError> {
+        let mut errors = FieldErrors::default();
+        errors.cpf("cpf", value);
+        errors.into_vec()
+    }
+
+    #[test]
+    fn cpf_accepts_punctuated_and_spaced_digits() {
+        assert!(cpf_errors("529.982.247-25").is_empty());
+        assert!(cpf_errors("52998224725").is_empty());
+        assert!(cpf_errors("529 982 247 25").is_empty());
+    }
+
+    #[test]
+    fn cpf_rejects_non_ascii_digits_without_panicking() {
+        let errors = cpf_errors("529982247٥");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, Message::InvalidCpf);
+    }
+
+    #[test]
+    fn cpf_agrees_with_cpf_parse() {
+        for value in [
+            "529.982.247-25",
+            "529 982 247 25",
+            "529982247-25",
+            "529.982.247-26",
+            "529.982.247/25",
+            "5299822472a",
+            "٥٢٩٩٨٢٢٤٧٢٥",
+            "111.111.111-11",
+        ] {
+            assert_eq!(
+                cpf_errors(value).is_empty(),
+                Cpf::parse(value).is_ok(),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn blank_cpf_is_reported_once() {
+        let errors = cpf_errors("  ");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, Message::Blank);
+    }
+
+    #[test]
+    fn emails_must_be_dot_atoms_at_a_dotted_domain() {
+        for valid in ["ana@example.com", "ana.souza+tag@mail.example.com.br"] {
+            assert!(is_valid_email(valid), "{valid}");
+        }
+        for invalid in [
+            "ana",
+            "ana@example",
+            "ana@@example.com",
+            ".ana@example.com",
+            "ana..souza@example.com",
+            "\"ana\"@example.com",
+            "ana@-example.com",
+            "ana@[127.0.0.1]",
+        ] {
+            assert!(!is_valid_email(invalid), "{invalid}");
+        }
+        let long_local = format!("{}@example.com", "a".repeat(EMAIL_LOCAL_MAX_LEN + 1));
+        assert!(!is_valid_email(&long_local));
+    }
+
+    #[test]
+    fn phones_need_an_area_code_and_allow_punctuation() {
+        for valid in ["(11) 98765-4321", "+55 11 98765 4321", "1133334444"] {
+            assert!(is_valid_phone(valid), "{valid}");
+        }
+        for invalid in ["98765-4321", "+55 (11) 98765-4321 99", "11 9876a-4321"] {
+            assert!(!is_valid_phone(invalid), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn passwords_are_bounded_in_bytes() {
+        let mut errors = FieldErrors::default();
+        errors.password("short", "12345");
+        errors.password("ok", "123456");
+        errors.password("long", &"é".repeat(PASSWORD_MAX_LEN / 2 + 1));
+        let messages: Vec<_> = errors
+            .into_vec()
+            .into_iter()
+            .map(|e| (e.field, e.message))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (
+                    "short".to_string(),
+                    Message::TooShort {
+                        min: PASSWORD_MIN_LEN
+                    }
+                ),
+                (
+                    "long".to_string(),
+                    Message::PasswordTooLong {
+                        max: PASSWORD_MAX_LEN
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn names_are_bounded_in_characters() {
+        let mut errors = FieldErrors::default();
+        errors.name("fits", &"ã".repeat(NAME_MAX_LEN));
+        errors.name("long", &"a".repeat(NAME_MAX_LEN + 1));
+        let errors = errors.into_vec();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "long");
+        assert_eq!(errors[0].message, Message::TooLong { max: NAME_MAX_LEN });
+    }
+
+    #[test]
+    fn several_failures_are_reported_together() {
+        let mut errors = FieldErrors::default();
+        errors.name("name", " ");
+        errors.email("email", "nope");
+        match errors.into_result() {
+            Err(AppError::Validation { detail, errors }) => {
+                assert_eq!(detail, Message::FieldsInvalid { count: 2 });
+                assert_eq!(errors.len(), 2);
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+
+        assert!(FieldErrors::default().into_result().is_ok());
+    }
+}